@@ -0,0 +1,74 @@
+"""
+Serves samples/web/ on http://127.0.0.1:<port>/ for trying out and testing URL analysis.
+
+    python serve-web-samples.py [port]
+    cyber-nutrition-facts http://127.0.0.1:8000/insecure
+    cyber-nutrition-facts http://127.0.0.1:8000/secure
+
+This is plain HTTP, so the report flags the page itself (CNF-WEB-000) and skips the
+HSTS and mixed-content checks; the unit tests in src/analysis/web.rs cover those with
+the same samples served as https:// pages.
+"""
+import os
+import sys
+
+from http.server import BaseHTTPRequestHandler, ThreadingHTTPServer
+
+REPO_DIR = os.path.dirname(os.path.abspath(__file__))
+
+def repo_path(*parts):
+    return os.path.join(REPO_DIR, *list([x for x in parts if x is not None]))
+
+# path -> (sample file, response headers)
+PAGES = {
+    '/secure': ('secure.html', [
+        ('Strict-Transport-Security', 'max-age=31536000; includeSubDomains'),
+        ('Content-Security-Policy', "default-src 'self'; script-src 'self' https://cdn.jsdelivr.net; frame-ancestors 'none'"),
+        ('X-Frame-Options', 'DENY'),
+        ('Referrer-Policy', 'strict-origin-when-cross-origin'),
+        ('Permissions-Policy', 'camera=(), microphone=(), geolocation=()'),
+        ('Set-Cookie', 'session=abc123; Path=/; Secure; HttpOnly; SameSite=Lax'),
+    ]),
+    '/insecure': ('insecure.html', [
+        ('Strict-Transport-Security', 'max-age=300'),
+        ('Content-Security-Policy', "script-src 'self' 'unsafe-inline' *"),
+        ('X-Frame-Options', 'ALLOW-FROM https://example.com'),
+        ('Referrer-Policy', 'unsafe-url'),
+        ('Set-Cookie', 'session=abc123; Path=/'),
+        ('Set-Cookie', 'tracking=xyz; SameSite=None'),
+    ]),
+}
+
+class SampleHandler(BaseHTTPRequestHandler):
+    def do_GET(self):
+        if self.path == '/':
+            self.send_response(302)
+            self.send_header('Location', '/insecure')
+            self.end_headers()
+            return
+        page = PAGES.get(self.path)
+        if page is None:
+            self.send_error(404)
+            return
+        file_name, headers = page
+        with open(repo_path('samples', 'web', file_name), 'rb') as fd:
+            body = fd.read()
+        self.send_response(200)
+        self.send_header('Content-Type', 'text/html; charset=utf-8')
+        self.send_header('Content-Length', str(len(body)))
+        for name, value in headers:
+            self.send_header(name, value)
+        self.end_headers()
+        self.wfile.write(body)
+
+def main():
+    port = int(sys.argv[1]) if len(sys.argv) > 1 else 8000
+    server = ThreadingHTTPServer(('127.0.0.1', port), SampleHandler)
+    print(f'Serving samples/web/ on http://127.0.0.1:{port}/ (Ctrl+C to stop)')
+    try:
+        server.serve_forever()
+    except KeyboardInterrupt:
+        pass
+
+if __name__ == '__main__':
+    main()
@@ -0,0 +1,170 @@
+
+// Every shared object a binary ends up loading, as a tree of which library pulled in which,
+// with the hardening and dangerous-import analysis run on each one. A binary is only as hard as
+// the weakest library in its load set, so that one is named in the summary and a finding.
+
+use std::collections::{BTreeMap, HashSet};
+use std::path::Path;
+
+use crate::args::ReportStyle;
+use goblin::elf::program_header;
+
+use crate::report::{Category, DependencyNode, Entry, Finding, Section, SectionData, Severity};
+
+use super::elf_lib_lookup::LinkSimulation;
+use super::import_catalogue::ImportCatalogue;
+
+const TITLE: &str = "Dependency Tree";
+
+/// Per-library results the tree entries and the weakest link are built from.
+struct LibraryFacts {
+    /// "full RELRO, PIE, NX, canary, no CET"
+    hardening: Option<String>,
+    findings: Vec<Finding>,
+    /// Catalogue category -> dangerous imports of this library
+    imports: BTreeMap<String, Vec<String>>,
+    risk_points: u32,
+}
+
+pub fn dependency_tree(path: &Path, elf: &goblin::elf::Elf, sim: &LinkSimulation, catalogue: &ImportCatalogue) -> Section {
+    let mut section = Section::new(TITLE);
+    let binary = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+    let direct: Vec<String> = elf.libraries.iter().map(|l| l.to_string()).collect();
+    if direct.is_empty() {
+        // Shared objects and -nostdlib PIEs can have no DT_NEEDED and still be dynamic
+        let dynamic = elf.interpreter.is_some() || elf.program_headers.iter().any(|ph| ph.p_type == program_header::PT_DYNAMIC);
+        section.summary = Some(if dynamic { "No DT_NEEDED entries, no shared libraries loaded" } else { "Statically linked, no shared libraries loaded" }.into());
+        return section;
+    }
+
+    let facts: BTreeMap<&str, LibraryFacts> = sim.libraries.iter()
+        .map(|lib| (lib.soname.as_str(), library_facts(&lib.path, catalogue)))
+        .collect();
+
+    // Every object which lists a library in DT_NEEDED, not just the one that loaded it first
+    let mut needed_by: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for soname in direct.iter() {
+        needed_by.entry(soname.as_str()).or_default().push(binary.clone());
+    }
+    for lib in sim.libraries.iter() {
+        for child in lib.needed.iter() {
+            needed_by.entry(child.as_str()).or_default().push(lib.soname.clone());
+        }
+    }
+
+    let mut expanded = HashSet::new();
+    for soname in direct.iter() {
+        section.entries.push(tree_node(soname, sim, &facts, &mut expanded));
+    }
+
+    let mut nodes: Vec<DependencyNode> = sim.libraries.iter().map(|lib| {
+        let facts = &facts[lib.soname.as_str()];
+        DependencyNode {
+            soname: lib.soname.clone(),
+            path: Some(lib.path.display().to_string()),
+            found_by: Some(lib.found_by.as_str().to_string()),
+            needed_by: needed_by.get(lib.soname.as_str()).cloned().unwrap_or_default(),
+            needed: lib.needed.clone(),
+            hardening: facts.hardening.clone(),
+            dangerous_imports: facts.imports.clone(),
+            risk_points: facts.risk_points,
+        }
+    }).collect();
+    nodes.extend(sim.libraries_not_found.iter().map(|soname| DependencyNode {
+        soname: soname.clone(),
+        path: None,
+        found_by: None,
+        needed_by: needed_by.get(soname.as_str()).cloned().unwrap_or_default(),
+        needed: Vec::new(),
+        hardening: None,
+        dangerous_imports: BTreeMap::new(),
+        risk_points: 0,
+    }));
+
+    // Most risk points from hardening findings, then most dangerous imports; load order breaks ties
+    let weakest = sim.libraries.iter()
+        .map(|lib| (lib, &facts[lib.soname.as_str()]))
+        .filter(|(_, f)| f.risk_points > 0)
+        .max_by_key(|(lib, f)| {
+            let imports: usize = f.imports.values().map(|i| i.len()).sum();
+            let position = sim.libraries.iter().position(|l| l.soname == lib.soname).unwrap_or(0);
+            (f.risk_points, imports, std::cmp::Reverse(position))
+        });
+
+    let direct_loaded = direct.iter().filter(|soname| sim.libraries.iter().any(|l| l.soname == **soname)).count();
+    let mut counts = format!("{} libraries loaded ({} direct", sim.libraries.len(), direct_loaded);
+    if !sim.libraries_not_found.is_empty() {
+        counts += &format!(", {} not found", sim.libraries_not_found.len());
+    }
+    // Libraries which could not be read or parsed have no hardening to speak of, good or bad
+    let analyzed = facts.values().filter(|f| f.hardening.is_some()).count();
+    if analyzed < sim.libraries.len() {
+        counts += &format!(", {} not analyzed", sim.libraries.len() - analyzed);
+    }
+    section.summary = Some(match weakest {
+        Some((lib, f)) => format!("{}), weakest link: {} ({})", counts, lib.soname, f.hardening.as_deref().unwrap_or("unknown hardening")),
+        None if analyzed == 0 => format!("{}), hardening unknown", counts),
+        None if analyzed < sim.libraries.len() => format!("{}), all {} analyzed libraries fully hardened", counts, analyzed),
+        None => format!("{}), all fully hardened", counts),
+    });
+    if let Some((lib, f)) = weakest
+        && let Some(severity) = f.findings.iter().map(|finding| finding.severity).max()
+        && severity >= Severity::Medium {
+        let weaknesses: Vec<&str> = f.findings.iter().filter(|finding| finding.severity >= Severity::Medium).map(|finding| finding.title.as_str()).collect();
+        section.findings.push(Finding {
+            rule_id: "CNF-LINK-003".into(),
+            title: "Weakly hardened library in load set".into(),
+            message: format!("{} ({}) is mapped into the process with: {}. Its weaknesses apply to the whole process",
+                lib.soname, lib.path.display(), weaknesses.join(", ")),
+            severity,
+            category: Category::Dependencies,
+            location: None,
+            detail: ReportStyle::Terse,
+        });
+    }
+    section.data = Some(SectionData::DependencyTree { libraries: nodes });
+    section
+}
+
+/// "libfoo.so => /usr/lib/libfoo.so (DT_RUNPATH): partial RELRO, ..." and its own DT_NEEDED below it.
+/// Libraries already shown are not expanded again, the loader only maps each soname once.
+fn tree_node(soname: &str, sim: &LinkSimulation, facts: &BTreeMap<&str, LibraryFacts>, expanded: &mut HashSet<String>) -> Entry {
+    let Some(lib) = sim.libraries.iter().find(|l| l.soname == soname) else {
+        return Entry::new(format!("{} (not found)", soname), ReportStyle::Normal);
+    };
+    if !expanded.insert(soname.to_string()) {
+        return Entry::new(format!("{} (already loaded)", soname), ReportStyle::Detailed);
+    }
+    let f = &facts[soname];
+    let mut entry = Entry::new(format!("{} => {} ({}): {}", soname, lib.path.display(), lib.found_by.as_str(),
+        f.hardening.as_deref().unwrap_or("could not be analyzed")), ReportStyle::Normal);
+    for finding in f.findings.iter() {
+        entry.push(Entry::new(format!("[{}] {}", finding.severity.as_str(), finding.title), ReportStyle::Detailed));
+    }
+    for (category, functions) in f.imports.iter() {
+        entry.push(Entry::new(format!("dangerous imports, {}: {}", category, functions.join(", ")), ReportStyle::Detailed));
+    }
+    for child in lib.needed.iter() {
+        entry.push(tree_node(child, sim, facts, expanded));
+    }
+    entry
+}
+
+fn library_facts(path: &Path, catalogue: &ImportCatalogue) -> LibraryFacts {
+    let Ok(bytes) = std::fs::read(path) else {
+        return LibraryFacts { hardening: None, findings: Vec::new(), imports: BTreeMap::new(), risk_points: 0 };
+    };
+    let Ok(elf) = goblin::elf::Elf::parse(&bytes) else {
+        return LibraryFacts { hardening: None, findings: Vec::new(), imports: BTreeMap::new(), risk_points: 0 };
+    };
+    let hardening = super::hardening::elf_hardening(path, &elf, &bytes);
+    let imports = catalogue.by_category(&super::single_binary::elf_imports(&elf)).into_iter()
+        .map(|(category, functions)| (category, functions.into_iter().map(|(shown, _)| shown).collect()))
+        .collect();
+    LibraryFacts {
+        risk_points: hardening.findings.iter().map(|f| f.severity.risk_points()).sum(),
+        hardening: hardening.summary,
+        findings: hardening.findings,
+        imports,
+    }
+}
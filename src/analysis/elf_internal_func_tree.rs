@@ -1,194 +1,966 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 use std::fs;
 use std::path::Path;
 
-use capstone::arch::x86::{ArchMode as X86ArchMode, X86Operand, X86OperandType};
-use capstone::{prelude::*, Capstone};
-use object::{Object, ObjectSection, ObjectSymbol, SymbolKind};
+use capstone::arch::x86::X86OperandType;
+use capstone::arch::ArchOperand;
+use capstone::{prelude::*, Arch, Capstone, Endian, ExtraMode, Mode};
+use object::{Architecture, Object, ObjectSection, ObjectSymbol, SymbolKind};
 
-/// Print a function call tree beginning at `entry_symbol` (e.g. "_start" or "main").
+use crate::args::ReportStyle;
+use crate::report::{CallGraph, CallGraphEdge, CallGraphNode, CallKind, Entry};
+
+/// Instruction set the walker disassembles, picked from the ELF e_machine / PE machine.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CodeArch {
+    X86_32,
+    X86_64,
+    Aarch64,
+    /// A32 and Thumb; bit 0 of a code address selects Thumb, as in the ARM ELF ABI
+    Arm,
+    Riscv32,
+    Riscv64,
+}
+
+/// ARM condition code suffixes, so "bleq" is recognized as a call but "ble" (branch if less or equal) is not.
+const ARM_CONDITIONS: &[&str] = &["eq", "ne", "cs", "hs", "cc", "lo", "mi", "pl", "vs", "vc", "hi", "ls", "ge", "lt", "gt", "le", "al"];
+
+/// Upper bound on a function whose extent has to be inferred, so undecodable data doesn't run away with us.
+const MAX_INFERRED_EXTENT: u64 = 64 * 1024;
+
+/// Largest PE code section mapped; a crafted VirtualSize could otherwise ask for 4 GiB of zero fill.
+const MAX_PE_SECTION_SIZE: u64 = 256 * 1024 * 1024;
+
+/// Bytes handed to capstone at a time while walking a function.
+const CHUNK_SZ: usize = 4096;
+
+/// Where a function's size came from; functions without one have their extent inferred by recursive descent.
+#[derive(Debug, Clone, Copy)]
+enum ExtentSource {
+    /// st_size of an ELF symbol
+    Symbol,
+    /// An FDE in .eh_frame
+    EhFrame,
+    /// A RUNTIME_FUNCTION in the x64 PE exception directory
+    Pdata,
+}
+
+impl ExtentSource {
+    fn as_str(&self) -> &'static str {
+        match self {
+            ExtentSource::Symbol => "st_size",
+            ExtentSource::EhFrame => ".eh_frame",
+            ExtentSource::Pdata => ".pdata",
+        }
+    }
+}
+
+/// A branch inside a function; `target` is None for register/memory branches (jump tables, PLT stubs).
+struct Branch {
+    target: Option<u64>,
+    conditional: bool,
+}
+
+/// What a call instruction calls.
+enum CallTarget {
+    /// Immediate target, eg `call 0x401000` or `bl 0x10400`
+    Direct(u64),
+    /// Through a pointer slot in memory, eg `call [rip+0x2fe2]`; the slot's address
+    Memory(u64),
+    /// Through a register, eg `blr x8`
+    Indirect,
+}
+
+/// Capstone handles for one image; ARM gets a second one for Thumb code.
+struct Disassembler {
+    arch: CodeArch,
+    cs: Capstone,
+    thumb: Option<Capstone>,
+    /// Where %ebx points in i386 PIC code (.got.plt), so `call [ebx+disp]` can be resolved
+    pic_base: Option<u64>,
+}
+
+impl Disassembler {
+    fn new(arch: CodeArch, big_endian: bool) -> crate::DynResult<Self> {
+        let endian = Some(if big_endian { Endian::Big } else { Endian::Little });
+        let build = |arch, mode, extra_mode: &[ExtraMode], endian| -> crate::DynResult<Capstone> {
+            let mut cs = Capstone::new_raw(arch, mode, extra_mode.iter().copied(), endian)
+                .map_err(|e| -> Box<dyn std::error::Error> { Box::new(e) })?; // adapt to crate::DynResult
+            cs.set_detail(true)
+                .map_err(|e| -> Box<dyn std::error::Error> { Box::new(e) })?;
+            Ok(cs)
+        };
+        let (cs, thumb) = match arch {
+            CodeArch::X86_32 => (build(Arch::X86, Mode::Mode32, &[], None)?, None),
+            CodeArch::X86_64 => (build(Arch::X86, Mode::Mode64, &[], None)?, None),
+            CodeArch::Aarch64 => (build(Arch::ARM64, Mode::Arm, &[], endian)?, None),
+            CodeArch::Arm => (build(Arch::ARM, Mode::Arm, &[], endian)?, Some(build(Arch::ARM, Mode::Thumb, &[], endian)?)),
+            // Compressed instructions decode the same whether or not the binary uses them
+            CodeArch::Riscv32 => (build(Arch::RISCV, Mode::RiscV32, &[ExtraMode::RiscVC], endian)?, None),
+            CodeArch::Riscv64 => (build(Arch::RISCV, Mode::RiscV64, &[ExtraMode::RiscVC], endian)?, None),
+        };
+        Ok(Self { arch, cs, thumb, pic_base: None })
+    }
+
+    /// The handle to decode `addr` with and the address of its first byte (without the Thumb bit).
+    fn for_addr(&self, addr: u64) -> (&Capstone, u64) {
+        match &self.thumb {
+            Some(thumb) if addr & 1 == 1 => (thumb, addr & !1),
+            _ => (&self.cs, addr),
+        }
+    }
+
+    fn call_target(&self, cs: &Capstone, insn: &capstone::Insn, thumb: bool) -> Option<CallTarget> {
+        let mn = insn.mnemonic()?;
+        match self.arch {
+            CodeArch::X86_32 | CodeArch::X86_64 => {
+                if x86_mnemonic(mn) != "call" {
+                    return None;
+                }
+                if let Some(target) = extract_call_imm_target(cs, insn) {
+                    Some(CallTarget::Direct(target))
+                } else if let Some(slot) = memory_operand_address(cs, insn, self.pic_base) {
+                    Some(CallTarget::Memory(slot))
+                } else {
+                    Some(CallTarget::Indirect)
+                }
+            }
+            CodeArch::Aarch64 => match mn {
+                "bl" => extract_call_imm_target(cs, insn).map(CallTarget::Direct),
+                "blr" | "blraa" | "blraaz" | "blrab" | "blrabz" => Some(CallTarget::Indirect),
+                _ => None,
+            },
+            CodeArch::Arm => {
+                let mn = mn.trim_end_matches(".w");
+                let (base, condition) = if let Some(c) = mn.strip_prefix("blx") { ("blx", c) }
+                    else if let Some(c) = mn.strip_prefix("bl") { ("bl", c) }
+                    else { return None };
+                if !condition.is_empty() && !ARM_CONDITIONS.contains(&condition) {
+                    return None;
+                }
+                match (base, extract_call_imm_target(cs, insn)) {
+                    // bl stays in the caller's instruction set
+                    ("bl", Some(target)) => Some(CallTarget::Direct(if thumb { target | 1 } else { target })),
+                    // blx <imm> switches between ARM and Thumb
+                    ("blx", Some(target)) => Some(CallTarget::Direct(if thumb { target & !3 } else { target | 1 })),
+                    ("blx", None) => Some(CallTarget::Indirect),
+                    _ => None,
+                }
+            }
+            CodeArch::Riscv32 | CodeArch::Riscv64 => {
+                // jal/jalr only call when they link into ra; with rd=zero they're plain jumps
+                let rd = insn.op_str().unwrap_or("").split(',').next().unwrap_or("").trim().to_string();
+                let operands = insn.op_str().unwrap_or("").split(',').count();
+                // capstone reports the jal immediate relative to the instruction
+                let target = || extract_call_imm_target(cs, insn).map(|imm| CallTarget::Direct(insn.address().wrapping_add(imm)));
+                match mn {
+                    "jal" if operands == 1 || rd == "ra" => target(),
+                    "c.jal" => target(),
+                    "jalr" if operands == 1 || rd == "ra" => Some(CallTarget::Indirect),
+                    "c.jalr" => Some(CallTarget::Indirect),
+                    _ => None,
+                }
+            }
+        }
+    }
+
+    fn is_return(&self, insn: &capstone::Insn) -> bool {
+        let mn = insn.mnemonic().unwrap_or("");
+        let ops = insn.op_str().unwrap_or("");
+        match self.arch {
+            CodeArch::X86_32 | CodeArch::X86_64 => matches!(x86_mnemonic(mn), "ret" | "retf" | "retn"),
+            CodeArch::Aarch64 => matches!(mn, "ret" | "retaa" | "retab"),
+            CodeArch::Arm => match mn.trim_end_matches(".w") {
+                "bx" => ops == "lr",
+                "pop" | "ldm" | "ldmia" | "ldmfd" => ops.contains("pc"),
+                "mov" => ops.starts_with("pc,"),
+                _ => false,
+            },
+            CodeArch::Riscv32 | CodeArch::Riscv64 => match mn {
+                "ret" | "c.ret" => true,
+                "jr" | "c.jr" => ops == "ra",
+                "jalr" => ops.starts_with("zero, ra") || ops.starts_with("zero, 0(ra)"),
+                _ => false,
+            },
+        }
+    }
+
+    /// What `insn` does to the first integer argument of the next call: Some(Some(value)) when it sets it
+    /// to a constant address, Some(None) when it sets it to anything else, None when it leaves it alone.
+    /// x86-64 passes it in rdi (`lea rdi, [rip + main]`, `mov edi, main`), i386 pushes it last (`push main`).
+    /// Only x86 is followed; other architectures load main through the GOT, which isn't mapped.
+    fn first_argument(&self, insn: &capstone::Insn, image: &Image) -> Option<Option<u64>> {
+        use capstone::arch::x86::X86Reg;
+        if !matches!(self.arch, CodeArch::X86_32 | CodeArch::X86_64) {
+            return None;
+        }
+        let mn = x86_mnemonic(insn.mnemonic()?);
+        let detail = self.cs.insn_detail(insn).ok()?;
+        let operands: Vec<X86OperandType> = detail.arch_detail().operands().into_iter()
+            .filter_map(|op| match op {
+                ArchOperand::X86Operand(op) => Some(op.op_type),
+                _ => None,
+            })
+            .collect();
+        // `loads` reads the pointer at a memory operand, lea takes its address
+        let value = |operand: &X86OperandType, loads: bool| match operand {
+            X86OperandType::Imm(imm) => Some(*imm as u64),
+            X86OperandType::Mem(_) => {
+                let address = memory_operand_address(&self.cs, insn, self.pic_base)?;
+                if loads { image.read_pointer(address) } else { Some(address) }
+            }
+            _ => None,
+        };
+        let is_rdi = |reg: &RegId| *reg == RegId(X86Reg::X86_REG_RDI as u16) || *reg == RegId(X86Reg::X86_REG_EDI as u16);
+        match (self.arch, mn, operands.as_slice()) {
+            (CodeArch::X86_64, "lea" | "mov", [X86OperandType::Reg(reg), source]) if is_rdi(reg) => Some(value(source, mn == "mov")),
+            (CodeArch::X86_64, "cmp" | "test", _) => None,
+            (CodeArch::X86_64, _, [X86OperandType::Reg(reg), ..]) if is_rdi(reg) => Some(None),
+            (CodeArch::X86_32, "push", [source]) => Some(value(source, true)),
+            _ => None,
+        }
+    }
+
+    /// Jumps within (or, for tail calls, out of) a function. Instructions which never fall through,
+    /// like hlt or udf, are unconditional branches without a target.
+    fn branch(&self, cs: &Capstone, insn: &capstone::Insn) -> Option<Branch> {
+        let mn = insn.mnemonic()?;
+        let ops = insn.op_str().unwrap_or("");
+        let target = || extract_call_imm_target(cs, insn);
+        let jump = |conditional, target| Some(Branch { target, conditional });
+        match self.arch {
+            CodeArch::X86_32 | CodeArch::X86_64 => match x86_mnemonic(mn) {
+                "jmp" | "ljmp" => jump(false, target()),
+                "hlt" | "ud2" | "int3" => jump(false, None),
+                mn if mn.starts_with('j') || mn.starts_with("loop") => jump(true, target()),
+                _ => None,
+            },
+            CodeArch::Aarch64 => match mn {
+                "b" => jump(false, target()),
+                "cbz" | "cbnz" | "tbz" | "tbnz" => jump(true, target()),
+                "br" | "braa" | "braaz" | "brab" | "brabz" | "brk" | "udf" => jump(false, None),
+                _ if mn.starts_with("b.") => jump(true, target()),
+                _ => None,
+            },
+            CodeArch::Arm => {
+                let mn = mn.trim_end_matches(".w").trim_end_matches(".n");
+                match mn {
+                    "b" => jump(false, target()),
+                    "cbz" | "cbnz" => jump(true, target()),
+                    // bx lr is a return and was handled before; any other bx is a computed jump
+                    "bx" | "tbb" | "tbh" | "udf" => jump(false, None),
+                    "ldr" if ops.starts_with("pc,") => jump(false, None),
+                    _ if mn.len() == 3 && mn.starts_with('b') && ARM_CONDITIONS.contains(&&mn[1..]) => jump(true, target()),
+                    _ => None,
+                }
+            }
+            CodeArch::Riscv32 | CodeArch::Riscv64 => {
+                // capstone reports branch immediates relative to the instruction
+                let target = || target().map(|imm| insn.address().wrapping_add(imm));
+                match mn {
+                    "j" | "c.j" => jump(false, target()),
+                    "jr" | "c.jr" | "jalr" | "unimp" | "c.unimp" | "ebreak" | "c.ebreak" => jump(false, None),
+                    "beq" | "bne" | "blt" | "bge" | "bltu" | "bgeu" | "beqz" | "bnez" | "blez" | "bgez" | "bltz" | "bgtz"
+                    | "bgt" | "ble" | "bgtu" | "bleu" | "c.beqz" | "c.bnez" => jump(true, target()),
+                    _ => None,
+                }
+            }
+        }
+    }
+}
+
+/// The parts of an executable image the tree walker needs, all keyed by virtual address.
+struct Image {
+    /// (VA, bytes, size) of every executable section
+    sections: Vec<(u64, Vec<u8>, u64)>,
+    addr_to_name: HashMap<u64, String>,
+    /// Import slot VA (IAT entry, GOT slot) -> "DLL!Function" / "printf@libc.so.6"
+    imports: HashMap<u64, String>,
+    /// ELF PLT stub VA -> "printf@libc.so.6"
+    stubs: HashMap<u64, String>,
+    /// 4 for 32-bit images, 8 for 64-bit ones
+    pointer_size: usize,
+    big_endian: bool,
+    /// Code address (without the Thumb bit) -> known size of the function starting there
+    extents: HashMap<u64, (u64, ExtentSource)>,
+    /// Code addresses of every function we know of, to bound functions with inferred extents
+    function_starts: BTreeSet<u64>,
+}
+
+impl Image {
+    /// Exactly `size` bytes at VA `addr`, if they all lie in one section.
+    fn read_bytes(&self, addr: u64, size: usize) -> Option<&[u8]> {
+        let end = addr.checked_add(size as u64)?;
+        for (sec_addr, bytes, sec_size) in &self.sections {
+            if addr >= *sec_addr && end <= sec_addr.saturating_add(*sec_size) {
+                let off = (addr - *sec_addr) as usize;
+                return bytes.get(off..off + size);
+            }
+        }
+        None
+    }
+
+    /// Up to `size` bytes at VA `addr`, cut short at the end of the section.
+    fn code_at(&self, addr: u64, size: usize) -> Option<&[u8]> {
+        for (sec_addr, bytes, _) in &self.sections {
+            if addr >= *sec_addr && addr < sec_addr.saturating_add(bytes.len() as u64) {
+                let off = (addr - *sec_addr) as usize;
+                return Some(&bytes[off..bytes.len().min(off.saturating_add(size))]);
+            }
+        }
+        None
+    }
+
+    /// Where a function starting at `code_addr` ends at the latest: its known extent, or else
+    /// the next known function (and never further than MAX_INFERRED_EXTENT).
+    fn function_bound(&self, code_addr: u64) -> u64 {
+        match self.extents.get(&code_addr) {
+            Some((size, _)) => code_addr.saturating_add(*size),
+            None => self.function_starts.range(code_addr.saturating_add(1)..).next().copied()
+                .unwrap_or(u64::MAX)
+                .min(code_addr.saturating_add(MAX_INFERRED_EXTENT)),
+        }
+    }
+
+    fn read_pointer(&self, addr: u64) -> Option<u64> {
+        let bytes = self.read_bytes(addr, self.pointer_size)?;
+        match (self.pointer_size, self.big_endian) {
+            (4, false) => Some(u32::from_le_bytes(bytes.try_into().ok()?) as u64),
+            (4, true) => Some(u32::from_be_bytes(bytes.try_into().ok()?) as u64),
+            (_, false) => Some(u64::from_le_bytes(bytes.try_into().ok()?)),
+            (_, true) => Some(u64::from_be_bytes(bytes.try_into().ok()?)),
+        }
+    }
+}
+
+/// Build the call graph of everything reachable from `entry_symbol` (e.g. "_start" or "main"), or the ELF
+/// entry point, plus main and every exported function: glibc's _start only reaches main through a pointer.
+/// Stripped binaries have no main symbol, so on x86 main is taken from _start's call to __libc_start_main.
+/// Calls through the PLT or GOT are named after the import and, if `sim` resolved it, its library.
 /// Uses `crate::DynResult` for error handling (should be `Result<T, Box<dyn Error>>`).
-pub fn print_tree_of_elf<P: AsRef<Path>>(elf_path: P, entry_symbol: &str) -> crate::DynResult<()> {
+pub fn graph_of_elf<P: AsRef<Path>>(elf_path: P, entry_symbol: &str, sim: Option<&super::elf_lib_lookup::LinkSimulation>) -> crate::DynResult<CallGraph> {
     // Read file
     let data = fs::read(&elf_path)?;
     let obj = object::File::parse(&*data)?;
+    let elf = goblin::elf::Elf::parse(&data)?;
 
     // Collect executable/text sections for VA -> bytes mapping
     let mut sections: Vec<(u64, Vec<u8>, u64)> = Vec::new();
     for sec in obj.sections() {
-        if sec.kind() == object::SectionKind::Text {
-            if let Ok(bytes) = sec.data() {
-                sections.push((sec.address(), bytes.to_vec(), sec.size()));
-            }
+        if sec.kind() == object::SectionKind::Text
+            && let Ok(bytes) = sec.data() {
+            sections.push((sec.address(), bytes.to_vec(), sec.size()));
         }
     }
 
-    // Build symbol maps (text symbols)
+    // Bit 0 of an ARM code address only selects Thumb
+    let code_mask = if obj.architecture() == Architecture::Arm { !1 } else { !0 };
+
+    // Build symbol maps (text symbols); st_size gives the function's extent
     let mut addr_to_name: HashMap<u64, String> = HashMap::new();
     let mut name_to_addr: HashMap<String, u64> = HashMap::new();
+    let mut extents: HashMap<u64, (u64, ExtentSource)> = HashMap::new();
     for sym in obj.symbols().chain(obj.dynamic_symbols()) {
-        if sym.kind() == SymbolKind::Text {
-            if let Ok(name) = sym.name() {
-                let addr = sym.address();
-                if addr != 0 {
-                    addr_to_name.insert(addr, name.to_string());
-                    name_to_addr.insert(name.to_string(), addr);
+        // ARM mapping symbols ($a, $t, $d) mark instruction set changes, not functions
+        if sym.kind() == SymbolKind::Text
+            && let Ok(name) = sym.name()
+            && !name.starts_with('$') {
+            let addr = sym.address();
+            if addr != 0 {
+                addr_to_name.insert(addr, name.to_string());
+                name_to_addr.insert(name.to_string(), addr);
+                if sym.size() > 0 {
+                    extents.insert(addr & code_mask, (sym.size(), ExtentSource::Symbol));
                 }
             }
         }
     }
+    // FDEs also cover stripped binaries and static functions; symbol sizes win where both exist
+    for (start, size) in eh_frame_ranges(&obj).unwrap_or_default() {
+        extents.entry(start & code_mask).or_insert((size, ExtentSource::EhFrame));
+    }
+    let function_starts = extents.keys().copied()
+        .chain(addr_to_name.keys().map(|a| a & code_mask))
+        .collect();
 
     // Choose entry address: prefer provided symbol, else ELF entry.
     let entry_addr = name_to_addr
         .get(entry_symbol)
         .copied()
         .unwrap_or_else(|| obj.entry());
+    let main_addr = name_to_addr.get("main").copied();
+    let mut exports: Vec<u64> = elf.dynsyms.iter()
+        .filter(|sym| sym.is_function() && sym.st_shndx != 0 && sym.st_value != 0)
+        .map(|sym| sym.st_value)
+        .collect();
+    exports.sort();
+    exports.dedup();
 
-    // Build Capstone using the documented builder API
-    // (Capstone::new().x86().mode(...).detail(true).build())
-    let cs = Capstone::new()
-        .x86()
-        .mode(X86ArchMode::Mode64)
-        .detail(true)
-        .build()
-        .map_err(|e| -> Box<dyn std::error::Error> { Box::new(e) })?; // adapt to crate::DynResult
-
-    // Closure to read bytes at a virtual address from collected sections
-    let read_bytes = |addr: u64, size: usize| -> Option<&[u8]> {
-        for (sec_addr, bytes, sec_size) in &sections {
-            if addr >= *sec_addr && addr + (size as u64) <= *sec_addr + *sec_size {
-                let off = (addr - *sec_addr) as usize;
-                return Some(&bytes[off..off + size]);
+    // e_machine (and for x86/RISC-V the class) picks the instruction set
+    let arch = match obj.architecture() {
+        Architecture::X86_64 | Architecture::X86_64_X32 => CodeArch::X86_64,
+        Architecture::I386 => CodeArch::X86_32,
+        Architecture::Aarch64 | Architecture::Aarch64_Ilp32 => CodeArch::Aarch64,
+        Architecture::Arm => CodeArch::Arm,
+        Architecture::Riscv32 => CodeArch::Riscv32,
+        Architecture::Riscv64 => CodeArch::Riscv64,
+        other => return Err(crate::tracked_err!(format!("ELF architecture {:?} is not supported for disassembly", other)).into()),
+    };
+    // BE8 ARM images keep big-endian data but little-endian instructions
+    const EF_ARM_BE8: u32 = 0x0080_0000;
+    let be8 = matches!(obj.flags(), object::FileFlags::Elf { e_flags, .. } if arch == CodeArch::Arm && e_flags & EF_ARM_BE8 != 0);
+    let big_endian = !obj.is_little_endian();
+    let mut disasm = Disassembler::new(arch, big_endian && !be8)?;
+    if arch == CodeArch::X86_32 {
+        disasm.pic_base = obj.section_by_name(".got.plt").map(|s| s.address());
+    }
+
+    // "printf" -> "printf@libc.so.6" for every import the link simulation resolved
+    let mut sonames: HashMap<&str, &str> = HashMap::new();
+    for (soname, funcs) in sim.map(|s| &s.lib_funcs).into_iter().flatten() {
+        for func in funcs.iter() {
+            sonames.insert(func.as_str(), soname.as_str());
+        }
+    }
+    let import_name = |name: &str, fallback: &str| match sonames.get(name) {
+        Some(soname) => format!("{}@{}", name, soname),
+        None => format!("{}@{}", name, fallback),
+    };
+    let slots = elf_import_slots(&elf);
+    let stubs = elf_plt_stubs(&elf, &data, &disasm, &slots).into_iter()
+        .map(|(stub, name)| (stub, import_name(&name, "plt")))
+        .collect();
+    let imports = slots.iter().map(|(slot, name)| (*slot, import_name(name, "got"))).collect();
+
+    let image = Image {
+        sections,
+        addr_to_name,
+        imports,
+        stubs,
+        pointer_size: if obj.is_64() { 8 } else { 4 },
+        big_endian,
+        extents,
+        function_starts,
+    };
+
+    // Start DFS; shared libraries usually have no entry point
+    let mut builder = GraphBuilder::new(&disasm, &image);
+    if entry_addr != 0 {
+        builder.root(entry_addr)?;
+    }
+    // Stripped binaries have no main symbol, but _start passes main to __libc_start_main
+    let recovered_main = builder.start_main.filter(|_| main_addr.is_none());
+    for addr in main_addr.or(recovered_main).into_iter().chain(exports) {
+        if addr != 0 {
+            builder.root(addr)?;
+        }
+    }
+    if let Some(addr) = recovered_main {
+        let index = builder.functions[&addr];
+        builder.graph.nodes[index].name.get_or_insert_with(|| "main".into());
+    }
+    Ok(builder.graph)
+}
+
+/// Build the call graph of everything reachable from the PE entry point (AddressOfEntryPoint) and the exports.
+/// Calls through the import address table end in a "DLL!Function" node.
+pub fn graph_of_pe(pe: &goblin::pe::PE, data: &[u8]) -> crate::DynResult<CallGraph> {
+    use goblin::pe::header::{COFF_MACHINE_ARM64, COFF_MACHINE_X86, COFF_MACHINE_X86_64};
+    use goblin::pe::section_table::{IMAGE_SCN_CNT_CODE, IMAGE_SCN_MEM_EXECUTE};
+
+    let arch = match pe.header.coff_header.machine {
+        COFF_MACHINE_X86_64 => CodeArch::X86_64,
+        COFF_MACHINE_X86 => CodeArch::X86_32,
+        COFF_MACHINE_ARM64 => CodeArch::Aarch64,
+        machine => return Err(crate::tracked_err!(format!("PE machine type 0x{:04x} is not supported for disassembly", machine)).into()),
+    };
+    let base = pe.image_base;
+
+    // Map executable sections by RVA; the loader zero-fills past the raw data up to VirtualSize.
+    // Both sizes come straight from the file, so the raw data is bounds checked and the zero fill
+    // capped at what SizeOfImage leaves for the section
+    let size_of_image = pe.header.optional_header.map(|oh| oh.windows_fields.size_of_image as u64).unwrap_or(MAX_PE_SECTION_SIZE);
+    let mut sections: Vec<(u64, Vec<u8>, u64)> = Vec::new();
+    for sec in pe.sections.iter() {
+        if sec.characteristics & (IMAGE_SCN_CNT_CODE | IMAGE_SCN_MEM_EXECUTE) == 0 {
+            continue;
+        }
+        let Some(raw) = data.get(sec.pointer_to_raw_data as usize..) else {
+            continue; // raw data starts past the end of a truncated file
+        };
+        let raw = &raw[..raw.len().min(sec.size_of_raw_data as usize)];
+        let virtual_size = if sec.virtual_size == 0 { sec.size_of_raw_data } else { sec.virtual_size } as u64;
+        let virtual_size = virtual_size
+            .min(size_of_image.saturating_sub(sec.virtual_address as u64))
+            .min(MAX_PE_SECTION_SIZE);
+        let mut bytes = raw.to_vec();
+        bytes.resize(virtual_size as usize, 0);
+        sections.push((base.wrapping_add(sec.virtual_address as u64), bytes, virtual_size));
+    }
+
+    let mut addr_to_name: HashMap<u64, String> = HashMap::new();
+    for export in pe.exports.iter() {
+        if let Some(name) = export.name && export.reexport.is_none() {
+            addr_to_name.insert(base.wrapping_add(export.rva as u64), name.to_string());
+        }
+    }
+    let entry_addr = base.wrapping_add(pe.entry as u64);
+    addr_to_name.entry(entry_addr).or_insert_with(|| "AddressOfEntryPoint".into());
+
+    // x64 images describe every non-leaf function in the exception directory
+    let mut extents: HashMap<u64, (u64, ExtentSource)> = HashMap::new();
+    if arch == CodeArch::X86_64 && let Some(exception_data) = &pe.exception_data {
+        for function in exception_data.functions().flatten() {
+            if function.end_address > function.begin_address {
+                let size = (function.end_address - function.begin_address) as u64;
+                extents.entry(base.wrapping_add(function.begin_address as u64)).or_insert((size, ExtentSource::Pdata));
             }
         }
-        None
+    }
+    let function_starts = extents.keys().chain(addr_to_name.keys()).copied().collect();
+
+    // goblin's Import::offset is the RVA of the import's IAT slot
+    let imports = pe.imports.iter()
+        .map(|i| (base.wrapping_add(i.offset as u64), format!("{}!{}", i.dll, i.name)))
+        .collect();
+
+    let image = Image {
+        sections,
+        addr_to_name,
+        imports,
+        stubs: HashMap::new(),
+        pointer_size: if pe.is_64 { 8 } else { 4 },
+        big_endian: false,
+        extents,
+        function_starts,
+    };
+    let disasm = Disassembler::new(arch, false)?;
+
+    let mut builder = GraphBuilder::new(&disasm, &image);
+    builder.root(entry_addr)?;
+    let mut exports: Vec<u64> = pe.exports.iter()
+        .filter(|e| e.reexport.is_none() && e.rva != 0)
+        .map(|e| base.wrapping_add(e.rva as u64))
+        .collect();
+    exports.sort();
+    exports.dedup();
+    for addr in exports {
+        builder.root(addr)?;
+    }
+    Ok(builder.graph)
+}
+
+/// Render `graph` as one tree per root for the text report: each function's calls in call-site order,
+/// a function reached a second time is marked "(already visited)" instead of being expanded again.
+pub fn call_tree(graph: &CallGraph) -> Vec<Entry> {
+    let mut calls: Vec<Vec<&CallGraphEdge>> = vec![Vec::new(); graph.nodes.len()];
+    for edge in graph.edges.iter() {
+        calls[edge.from].push(edge);
+    }
+    let mut visited: HashSet<usize> = HashSet::new();
+    graph.roots.iter().map(|root| tree_node(graph, &calls, *root, &mut visited)).collect()
+}
+
+fn tree_node(graph: &CallGraph, calls: &[Vec<&CallGraphEdge>], index: usize, visited: &mut HashSet<usize>) -> Entry {
+    let function = &graph.nodes[index];
+    let address = function.address.unwrap_or(0);
+    // "main (0x1149, 52 bytes from st_size)" / "0x1160 (40 bytes inferred)"
+    let extent = match (function.size, function.extent.as_str()) {
+        (Some(size), "inferred") => Some(format!("{} bytes inferred", size)),
+        (Some(size), source) => Some(format!("{} bytes from {}", size, source)),
+        (None, _) => None,
+    };
+    let label = |extent: Option<String>| match (&function.name, extent) {
+        (Some(name), Some(extent)) => format!("{} (0x{:x}, {})", name, address, extent),
+        (Some(name), None) => format!("{} (0x{:x})", name, address),
+        (None, Some(extent)) => format!("0x{:x} ({})", address, extent),
+        (None, None) => format!("0x{:x}", address),
     };
 
-    // visited set to avoid infinite recursion
-    let mut visited: HashSet<u64> = HashSet::new();
+    if !visited.insert(index) {
+        let mut node = Entry::new(label(None), ReportStyle::Normal);
+        node.push(Entry::new("(already visited)", ReportStyle::Detailed));
+        return node;
+    }
 
-    // Start DFS print
-    dfs_print(&cs, entry_addr, 0, &read_bytes, &addr_to_name, &mut visited)?;
+    let mut node = Entry::new(label(extent), ReportStyle::Normal);
+    for edge in calls[index].iter() {
+        match edge.to {
+            Some(to) if graph.nodes[to].is_import() => node.push(Entry::new(graph.nodes[to].id(), ReportStyle::Normal)),
+            Some(to) => node.push(tree_node(graph, calls, to, visited)),
+            // couldn't resolve statically (call rax / blr x8 / jalr a5); record placeholder
+            None => node.push(Entry::new(format!("(indirect call at 0x{:x})", edge.call_site), ReportStyle::Detailed)),
+        }
+    }
+    node
+}
 
-    Ok(())
+/// Walks functions depth-first from the roots, adding every function and call site it finds to `graph`.
+struct GraphBuilder<'a> {
+    disasm: &'a Disassembler,
+    image: &'a Image,
+    graph: CallGraph,
+    /// Function address (with the Thumb bit) -> node index
+    functions: HashMap<u64, usize>,
+    /// Import name -> node index
+    imports: HashMap<String, usize>,
+    /// First argument of the first call to __libc_start_main, ie main, which stripped binaries have no symbol for
+    start_main: Option<u64>,
 }
 
-/// Recursively disassemble from `addr`, print name (if any), and recurse into direct call targets.
-/// - `read_bytes` should return Some(&[u8]) for bytes starting at VA `addr`.
-fn dfs_print<'a>(
-    cs: &Capstone,
-    addr: u64,
-    depth: usize,
-    read_bytes: &impl Fn(u64, usize) -> Option<&'a [u8]>,
-    addr_to_name: &HashMap<u64, String>,
-    visited: &mut HashSet<u64>,
-) -> crate::DynResult<()> {
-    // indentation
-    for _ in 0..depth {
-        print!("    ");
+impl<'a> GraphBuilder<'a> {
+    fn new(disasm: &'a Disassembler, image: &'a Image) -> Self {
+        Self { disasm, image, graph: CallGraph::default(), functions: HashMap::new(), imports: HashMap::new(), start_main: None }
+    }
+
+    fn root(&mut self, addr: u64) -> crate::DynResult<()> {
+        let index = self.function(addr)?;
+        if !self.graph.roots.contains(&index) {
+            self.graph.roots.push(index);
+        }
+        Ok(())
+    }
+
+    fn import(&mut self, name: &str) -> usize {
+        if let Some(index) = self.imports.get(name) {
+            return *index;
+        }
+        self.graph.nodes.push(CallGraphNode { name: Some(name.to_string()), address: None, size: None, extent: "import".into() });
+        self.imports.insert(name.to_string(), self.graph.nodes.len() - 1);
+        self.graph.nodes.len() - 1
     }
-    if let Some(name) = addr_to_name.get(&addr) {
-        println!("{} (0x{:x})", name, addr);
-    } else {
-        println!("0x{:x}", addr);
+
+    fn call(&mut self, from: usize, to: Option<usize>, insn: &capstone::Insn, kind: CallKind) {
+        self.graph.edges.push(CallGraphEdge { from, to, call_site: insn.address(), kind });
     }
 
-    if !visited.insert(addr) {
-        for _ in 0..(depth + 1) {
-            print!("    ");
+    /// Remembers main when `import` is libc's startup function and its first argument is known.
+    fn import_called(&mut self, import: &str, first_argument: Option<u64>) {
+        let name = import.split('@').next().unwrap_or(import);
+        if matches!(name, "__libc_start_main" | "__uClibc_main") && self.start_main.is_none() {
+            self.start_main = first_argument.filter(|addr| *addr != 0);
         }
-        println!("(already visited)");
-        return Ok(());
     }
 
-    // Heuristic read length per function (adjust if needed)
-    const READ_SZ: usize = 4096;
-    let bytes = match read_bytes(addr, READ_SZ) {
-        Some(b) => b,
-        None => return Ok(()), // can't read bytes at this VA
-    };
+    /// Recursively disassemble the function at `addr` (once) and return its node.
+    /// The function is walked by recursive descent: conditional branches queue their target, unconditional
+    /// ones and returns end the current block, and nothing is read past the function's known extent
+    /// (or, failing that, the next known function).
+    fn function(&mut self, addr: u64) -> crate::DynResult<usize> {
+        if let Some(index) = self.functions.get(&addr) {
+            return Ok(*index);
+        }
+        let (disasm, image) = (self.disasm, self.image);
+        let (cs, code_addr) = disasm.for_addr(addr);
+        let index = self.graph.nodes.len();
+        self.graph.nodes.push(CallGraphNode {
+            name: image.addr_to_name.get(&addr).cloned(),
+            address: Some(code_addr),
+            size: None,
+            extent: "inferred".into(),
+        });
+        // registered before walking, so recursion and loops terminate
+        self.functions.insert(addr, index);
 
-    // Disassemble chunk
-    let insns = cs.disasm_all(bytes, addr)
-        .map_err(|e| -> Box<dyn std::error::Error> { Box::new(e) })?;
-
-    for insn in insns.iter() {
-        // Use mnemonic approach: 'call' instructions. This avoids unstable numeric group constants.
-        if let Some(mn) = insn.mnemonic() {
-            if mn == "call" {
-                if let Some(target) = extract_call_imm_target(cs, &insn) {
-                    // recurse into direct immediate target
-                    dfs_print(cs, target, depth + 1, read_bytes, addr_to_name, visited)?;
-                } else {
-                    // indirect call (call rax / call [rip+...]) — try to resolve RIP+disp -> pointer in section
-                    if let Some(mem_target) = resolve_rip_relative_call(cs, &insn, read_bytes) {
-                        dfs_print(cs, mem_target, depth + 1, read_bytes, addr_to_name, visited)?;
-                    } else {
-                        // couldn't resolve statically; print placeholder
-                        for _ in 0..(depth + 1) {
-                            print!("    ");
+        let thumb_bit = addr - code_addr;
+        let known = image.extents.get(&code_addr).copied();
+        let bound = image.function_bound(code_addr);
+        let mut blocks = BTreeSet::from([code_addr]);
+        let mut seen: HashSet<u64> = HashSet::new();
+        let mut walked_end = code_addr;
+
+        while let Some(block) = blocks.pop_first() {
+            let mut pc = block;
+            let mut first_argument = None;
+            'block: while pc < bound {
+                let bytes = match image.code_at(pc, CHUNK_SZ.min((bound - pc) as usize)) {
+                    Some(b) => b,
+                    None => break, // can't read bytes at this VA
+                };
+                let insns = cs.disasm_all(bytes, pc)
+                    .map_err(|e| -> Box<dyn std::error::Error> { Box::new(e) })?;
+                if insns.is_empty() {
+                    break; // undecodable, eg a literal pool
+                }
+
+                for insn in insns.iter() {
+                    if !seen.insert(insn.address()) {
+                        break 'block; // joined a block we already walked
+                    }
+                    pc = insn.address().wrapping_add(insn.len() as u64);
+                    walked_end = walked_end.max(pc);
+                    if let Some(value) = disasm.first_argument(insn, image) {
+                        first_argument = value;
+                    }
+
+                    match disasm.call_target(cs, insn, thumb_bit == 1) {
+                        Some(CallTarget::Direct(target)) => {
+                            // a direct call to an import thunk (jmp [IAT]) is a call to the import
+                            let callee = match import_thunk(disasm, target, image) {
+                                Some(import) => {
+                                    self.import_called(&import, first_argument);
+                                    self.import(&import)
+                                }
+                                // recurse into direct immediate target
+                                None => self.function(target)?,
+                            };
+                            self.call(index, Some(callee), insn, CallKind::Direct);
+                            continue;
+                        }
+                        Some(CallTarget::Memory(slot)) => {
+                            // call [rip+disp] / call [disp32]: either an IAT slot or a pointer we can read
+                            let callee = if let Some(import) = image.imports.get(&slot) {
+                                self.import_called(import, first_argument);
+                                Some(self.import(import))
+                            } else if let Some(mem_target) = image.read_pointer(slot) {
+                                Some(self.function(mem_target)?)
+                            } else {
+                                None
+                            };
+                            self.call(index, callee, insn, CallKind::Indirect);
+                            continue;
+                        }
+                        Some(CallTarget::Indirect) => {
+                            // couldn't resolve statically (call rax / blr x8 / jalr a5)
+                            self.call(index, None, insn, CallKind::Indirect);
+                            continue;
+                        }
+                        None => {}
+                    }
+
+                    if disasm.is_return(insn) {
+                        break 'block;
+                    }
+                    if let Some(branch) = disasm.branch(cs, insn) {
+                        match branch.target {
+                            // jmp printf@plt: a tail call to the import, like the call arm above
+                            Some(target) if let Some(import) = import_thunk(disasm, target | thumb_bit, image) => {
+                                let callee = self.import(&import);
+                                self.call(index, Some(callee), insn, CallKind::TailCall);
+                            }
+                            // a jump to the start of another function is a tail call
+                            Some(target) if target != code_addr && image.function_starts.contains(&target) => {
+                                let callee = self.function(target | thumb_bit)?;
+                                self.call(index, Some(callee), insn, CallKind::TailCall);
+                            }
+                            Some(target) if target >= code_addr && target < bound => {
+                                blocks.insert(target);
+                            }
+                            // out of bounds: a tail call to a function we have no symbol or FDE for
+                            Some(target) => {
+                                let callee = self.function(target | thumb_bit)?;
+                                self.call(index, Some(callee), insn, CallKind::TailCall);
+                            }
+                            None => {}
+                        }
+                        if !branch.conditional {
+                            break 'block;
                         }
-                        println!("(indirect call at 0x{:x})", insn.address());
                     }
                 }
-            } else if mn == "ret" {
-                // stop at ret to avoid falling through to next function bytes
-                break;
             }
         }
+
+        let node = &mut self.graph.nodes[index];
+        match known {
+            Some((size, source)) => {
+                node.size = Some(size);
+                node.extent = source.as_str().into();
+            }
+            None => node.size = Some(walked_end - code_addr),
+        }
+        Ok(index)
+    }
+}
+
+/// "printf@libc.so.6" if `addr` is an ELF PLT stub, or "DLL!Function" if the code at `addr` is a
+/// `jmp [IAT slot]` stub, as MSVC and MinGW emit for imports.
+fn import_thunk(disasm: &Disassembler, addr: u64, image: &Image) -> Option<String> {
+    if let Some(name) = image.stubs.get(&disasm.for_addr(addr).1) {
+        return Some(name.clone());
+    }
+    if image.imports.is_empty() || !matches!(disasm.arch, CodeArch::X86_32 | CodeArch::X86_64) {
+        return None;
+    }
+    let cs = &disasm.cs;
+    let bytes = image.code_at(addr, 16)?;
+    let insns = cs.disasm_count(bytes, addr, 1).ok()?;
+    let insn = insns.iter().next()?;
+    if insn.mnemonic().map(x86_mnemonic) != Some("jmp") {
+        return None;
+    }
+    image.imports.get(&memory_operand_address(cs, insn, None)?).cloned()
+}
+
+/// "jmp" for "notrack jmp", "call" for "bnd call": capstone keeps the CET and MPX branch prefixes
+/// in the mnemonic, and glibc and every -fcf-protection build are full of them.
+fn x86_mnemonic(mn: &str) -> &str {
+    let mut mn = mn;
+    while let Some(rest) = mn.strip_prefix("bnd ").or_else(|| mn.strip_prefix("notrack ")) {
+        mn = rest;
+    }
+    mn
+}
+
+/// GOT slot VA -> name of the dynamic symbol the loader writes there, from .rela.plt (JUMP_SLOT)
+/// and .rela.dyn / .rel.dyn (GLOB_DAT and friends).
+fn elf_import_slots(elf: &goblin::elf::Elf) -> HashMap<u64, String> {
+    let mut slots = HashMap::new();
+    for reloc in elf.pltrelocs.iter().chain(elf.dynrelas.iter()).chain(elf.dynrels.iter()) {
+        if reloc.r_sym == 0 {
+            continue; // RELATIVE, IRELATIVE: no symbol
+        }
+        if let Some(sym) = elf.dynsyms.get(reloc.r_sym)
+            && let Some(name) = elf.dynstrtab.get_at(sym.st_name)
+            && !name.is_empty() {
+            slots.insert(reloc.r_offset, name.to_string());
+        }
     }
+    slots
+}
 
-    Ok(())
+/// PLT stub VA -> name of the import it jumps to.
+/// x86 stubs are decoded (`jmp [GOT slot]`, possibly after endbr64 and with a bnd prefix) so .plt,
+/// .plt.sec and .plt.got all work; other architectures have one fixed-size .plt entry per .rela.plt
+/// entry, in order, after a fixed-size header.
+fn elf_plt_stubs(elf: &goblin::elf::Elf, data: &[u8], disasm: &Disassembler, slots: &HashMap<u64, String>) -> HashMap<u64, String> {
+    let mut stubs = HashMap::new();
+    for sh in elf.section_headers.iter() {
+        let name = elf.shdr_strtab.get_at(sh.sh_name).unwrap_or("");
+        if !matches!(name, ".plt" | ".plt.sec" | ".plt.got") {
+            continue;
+        }
+        let Some(end) = sh.sh_offset.checked_add(sh.sh_size) else {
+            continue; // crafted section header
+        };
+        let Some(bytes) = data.get(sh.sh_offset as usize..end as usize) else {
+            continue;
+        };
+
+        match disasm.arch {
+            CodeArch::X86_32 | CodeArch::X86_64 => {
+                let entry_size = if sh.sh_entsize == 0 { 16 } else { sh.sh_entsize };
+                let Ok(insns) = disasm.cs.disasm_all(bytes, sh.sh_addr) else {
+                    continue;
+                };
+                for insn in insns.iter() {
+                    if insn.mnemonic().map(x86_mnemonic) != Some("jmp") {
+                        continue;
+                    }
+                    if let Some(slot) = memory_operand_address(&disasm.cs, insn, disasm.pic_base)
+                        && let Some(import) = slots.get(&slot) {
+                        let stub = sh.sh_addr.wrapping_add(insn.address().wrapping_sub(sh.sh_addr) / entry_size * entry_size);
+                        stubs.entry(stub).or_insert_with(|| import.clone());
+                    }
+                }
+            }
+            _ if name == ".plt" => {
+                let (header, entry_size) = match disasm.arch {
+                    CodeArch::Arm => (20, 12),
+                    _ => (32, 16), // aarch64, RISC-V
+                };
+                for (i, reloc) in elf.pltrelocs.iter().enumerate() {
+                    if let Some(import) = slots.get(&reloc.r_offset) {
+                        stubs.insert(sh.sh_addr.wrapping_add(header).wrapping_add((i as u64).wrapping_mul(entry_size)), import.clone());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    stubs
 }
 
-/// Try to extract an immediate call target from operand imm if present.
+/// Try to extract an immediate call/branch target from the last imm operand if present
+/// (tbz and friends have a bit number before the target).
 /// Returns Some(target_addr) on success.
 fn extract_call_imm_target(cs: &Capstone, insn: &capstone::Insn) -> Option<u64> {
     let detail = cs.insn_detail(insn).ok()?;
     let arch_detail = detail.arch_detail();
-    for op in arch_detail.operands() {
-        // ArchOperand enum — match the X86 operand variant and inspect its op_type()
+    for op in arch_detail.operands().into_iter().rev() {
+        // ArchOperand enum — match the operand variant of each architecture and inspect its type
         match op {
-            capstone::arch::ArchOperand::X86Operand(x86op) => {
+            ArchOperand::X86Operand(x86op) => {
                 if let X86OperandType::Imm(imm) = x86op.op_type {
                     return Some(imm as u64);
                 }
             }
+            ArchOperand::Arm64Operand(op) => {
+                if let capstone::arch::arm64::Arm64OperandType::Imm(imm) = op.op_type {
+                    return Some(imm as u64);
+                }
+            }
+            ArchOperand::ArmOperand(op) => {
+                if let capstone::arch::arm::ArmOperandType::Imm(imm) = op.op_type {
+                    return Some(imm as u32 as u64);
+                }
+            }
+            ArchOperand::RiscVOperand(capstone::arch::riscv::RiscVOperand::Imm(imm)) => {
+                return Some(imm as u64);
+            }
             _ => {}
         }
     }
     None
 }
 
-/// Resolve common RIP-relative GOT/PLT style call: call [rip + disp] -> load pointer from that address if bytes are readable.
-fn resolve_rip_relative_call<'a>(
-    cs: &Capstone,
-    insn: &capstone::Insn,
-    read_bytes: &impl Fn(u64, usize) -> Option<&'a [u8]>,
-) -> Option<u64> {
-    // Inspect operands to find MEM with base RIP and displacement
+/// Address of the memory operand of `call [rip + disp]` (64-bit), `call [disp32]` (32-bit) or, given
+/// where %ebx points, `call [ebx + disp]` (32-bit PIC), ie the GOT/IAT slot holding the real target.
+fn memory_operand_address(cs: &Capstone, insn: &capstone::Insn, pic_base: Option<u64>) -> Option<u64> {
+    // Inspect operands to find MEM with base RIP and displacement, or with no base/index at all
     let detail = cs.insn_detail(insn).ok()?;
     let arch_detail = detail.arch_detail();
     for op in arch_detail.operands() {
-        if let capstone::arch::ArchOperand::X86Operand(x86op) = op {
-            if let X86OperandType::Mem(mem) = x86op.op_type {
-                // mem.base is a register id: if it's RIP (X86_REG_RIP) then compute pointer address
-                // mem.disp is i64 displacement
-                // X86_REG_RIP constant is available via capstone::arch::x86::X86Reg::X86_REG_RIP
-                use capstone::arch::x86::X86Reg;
-                if mem.base() == capstone::RegId(X86Reg::X86_REG_RIP as u16) {
-                    // address of the memory operand = insn.address() + insn.size() as u64 + disp
-                    let addr_ptr = insn.address().wrapping_add(insn.bytes().len() as u64).wrapping_add(mem.disp() as u64);
-                    // read 8 bytes for a 64-bit pointer
-                    if let Some(ptr_bytes) = read_bytes(addr_ptr, 8) {
-                        let mut arr = [0u8; 8];
-                        arr.copy_from_slice(&ptr_bytes[0..8]);
-                        let pointee = u64::from_le_bytes(arr);
-                        return Some(pointee);
-                    }
-                }
+        if let ArchOperand::X86Operand(x86op) = op
+            && let X86OperandType::Mem(mem) = x86op.op_type {
+            use capstone::arch::x86::X86Reg;
+            if mem.base() == capstone::RegId(X86Reg::X86_REG_RIP as u16) {
+                // address of the memory operand = insn.address() + insn.size() as u64 + disp
+                return Some(insn.address().wrapping_add(insn.bytes().len() as u64).wrapping_add(mem.disp() as u64));
+            }
+            let invalid = capstone::RegId(X86Reg::X86_REG_INVALID as u16);
+            if mem.base() == invalid && mem.index() == invalid {
+                // absolute address, as 32-bit code addresses the IAT
+                return Some(mem.disp() as u32 as u64);
+            }
+            if let Some(pic_base) = pic_base
+                && mem.base() == capstone::RegId(X86Reg::X86_REG_EBX as u16)
+                && mem.index() == invalid {
+                return Some((pic_base as u32).wrapping_add(mem.disp() as u32) as u64);
             }
         }
     }
     None
 }
+
+/// (start, length) of every FDE in .eh_frame.
+fn eh_frame_ranges(obj: &object::File) -> crate::DynResult<Vec<(u64, u64)>> {
+    use gimli::UnwindSection;
+
+    let Some(section) = obj.section_by_name(".eh_frame") else {
+        return Ok(Vec::new());
+    };
+    let endian = if obj.is_little_endian() { gimli::RunTimeEndian::Little } else { gimli::RunTimeEndian::Big };
+    let data = section.data()?;
+    let mut eh_frame = gimli::EhFrame::new(data, endian);
+    eh_frame.set_address_size(if obj.is_64() { 8 } else { 4 });
+
+    // pc-relative and datarel pointer encodings need to know where things are loaded
+    let mut bases = gimli::BaseAddresses::default().set_eh_frame(section.address());
+    if let Some(text) = obj.section_by_name(".text") {
+        bases = bases.set_text(text.address());
+    }
+    if let Some(got) = obj.section_by_name(".got") {
+        bases = bases.set_got(got.address());
+    }
+
+    let mut ranges = Vec::new();
+    let mut entries = eh_frame.entries(&bases);
+    while let Some(entry) = entries.next()? {
+        if let gimli::CieOrFde::Fde(partial) = entry
+            && let Ok(fde) = partial.parse(|section, bases, offset| section.cie_from_offset(bases, offset)) {
+            ranges.push((fde.initial_address(), fde.len()));
+        }
+    }
+    Ok(ranges)
+}
@@ -1,114 +1,681 @@
-use goblin::elf::{Elf, sym};
+use goblin::elf::{Elf, sym, symver};
 use goblin::elf::sym::STT_NOTYPE;
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
 use std::fs;
 use std::path::{Path, PathBuf};
 
-/// Default library search paths (you can extend).
-const DEFAULT_SEARCH_PATHS: &[&str] = &[
-    "/lib",
-    "/lib64",
-    "/usr/lib",
-    "/usr/lib64",
-];
+use crate::report::UnresolvedSymbol;
+
+/// Default directories of the loader, searched last; see `LibrarySearch::default_dirs`.
+/// glibc compiles in only one of these sets, but the ELF class/machine check throws out the wrong ones.
+const DEFAULT_SEARCH_PATHS_64: &[&str] = &["/lib64", "/usr/lib64", "/lib", "/usr/lib"];
+const DEFAULT_SEARCH_PATHS_32: &[&str] = &["/lib", "/usr/lib", "/lib32", "/usr/lib32"];
+
+const LD_SO_CONF: &str = "/etc/ld.so.conf";
+const LD_SO_CACHE: &str = "/etc/ld.so.cache";
 
 /// Parse ELF bytes into goblin::elf::Elf
-fn parse_elf_bytes(buf: &[u8]) -> crate::DynResult<Elf> {
+fn parse_elf_bytes(buf: &[u8]) -> crate::DynResult<Elf<'_>> {
     Ok(Elf::parse(buf)?)
 }
 
-/// Find a library file named `soname` inside the provided search paths.
-/// Returns the first matching path if found.
-fn find_library_on_paths(soname: &str, search_paths: &[PathBuf]) -> Option<PathBuf> {
-    for dir in search_paths {
-        let candidate = dir.join(soname);
-        if candidate.exists() {
-            return Some(candidate);
+/// Which step of the loader's search order located a library, see ld.so(8).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchRule {
+    /// The DT_NEEDED entry contains a slash and is used as a path
+    Path,
+    Rpath,
+    LdLibraryPath,
+    Runpath,
+    LdSoCache,
+    /// A directory from /etc/ld.so.conf which the cache did not list, eg a stale cache
+    LdSoConf,
+    DefaultPath,
+    /// Search paths passed in by the caller instead of the loader's rules
+    Given,
+}
+
+impl SearchRule {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SearchRule::Path => "path in DT_NEEDED",
+            SearchRule::Rpath => "DT_RPATH",
+            SearchRule::LdLibraryPath => "LD_LIBRARY_PATH",
+            SearchRule::Runpath => "DT_RUNPATH",
+            SearchRule::LdSoCache => "ld.so.cache",
+            SearchRule::LdSoConf => "ld.so.conf",
+            SearchRule::DefaultPath => "default path",
+            SearchRule::Given => "search path",
+        }
+    }
+}
+
+/// An object whose DT_NEEDED entries are being resolved: the executable or a library.
+struct Loader {
+    /// Directory $ORIGIN expands to, as seen from inside the sysroot
+    origin: PathBuf,
+    rpath: Vec<String>,
+    runpath: Vec<String>,
+    nodeflib: bool,
+    /// Index of the object which loaded this one, None for the executable
+    loader: Option<usize>,
+}
+
+impl Loader {
+    /// `path` is where the object lives as seen from inside the sysroot.
+    fn of(elf: &Elf, path: &Path, loader: Option<usize>) -> Loader {
+        // glibc ignores DT_RPATH when DT_RUNPATH is present
+        let rpath = if elf.runpaths.is_empty() { split_path_list(&elf.rpaths) } else { Vec::new() };
+        let flags_1 = elf.dynamic.as_ref().map(|d| d.info.flags_1).unwrap_or(0);
+        Loader {
+            origin: path.parent().map(Path::to_path_buf).unwrap_or_default(),
+            rpath,
+            runpath: split_path_list(&elf.runpaths),
+            nodeflib: flags_1 & goblin::elf::dynamic::DF_1_NODEFLIB != 0,
+            loader,
+        }
+    }
+}
+
+/// DT_RPATH/DT_RUNPATH strings are colon separated lists.
+fn split_path_list(lists: &[&str]) -> Vec<String> {
+    lists.iter()
+        .flat_map(|list| list.split(':'))
+        .filter(|dir| !dir.is_empty())
+        .map(|dir| dir.to_string())
+        .collect()
+}
+
+/// Everything the loader's search order depends on besides the objects themselves.
+/// Paths are as the target system sees them; `host()` maps them into --sysroot when one is given.
+struct LibrarySearch {
+    sysroot: Option<PathBuf>,
+    /// ELF class and machine of the executable; libraries of another kind are skipped like ld.so does
+    is_64: bool,
+    machine: u16,
+    ld_library_path: Vec<String>,
+    /// What $LIB expands to
+    lib: String,
+    /// soname -> paths, in cache order
+    cache: HashMap<String, Vec<PathBuf>>,
+    conf_dirs: Vec<PathBuf>,
+    default_dirs: Vec<PathBuf>,
+    /// Given search paths replace all of the above
+    given: Option<Vec<PathBuf>>,
+}
+
+impl LibrarySearch {
+    fn new(elf: &Elf, given: Option<&[PathBuf]>, sysroot: Option<&Path>) -> LibrarySearch {
+        // Absolute, so target() can tell which host paths are inside it
+        let sysroot = sysroot.map(|root| fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf()));
+        let sysroot = sysroot.as_deref();
+        let is_64 = elf.is_64;
+        let host = |path: &Path| match sysroot {
+            Some(root) => resolve_in_root(root, path),
+            None => path.to_path_buf(),
+        };
+        let defaults = if is_64 { DEFAULT_SEARCH_PATHS_64 } else { DEFAULT_SEARCH_PATHS_32 };
+        // Debian style multiarch directories come first where they exist
+        let mut default_dirs: Vec<PathBuf> = Vec::new();
+        if let Some(triplet) = multiarch_triplet(elf.header.e_machine, is_64) {
+            default_dirs.push(PathBuf::from(format!("/lib/{}", triplet)));
+            default_dirs.push(PathBuf::from(format!("/usr/lib/{}", triplet)));
+        }
+        let lib = match multiarch_triplet(elf.header.e_machine, is_64) {
+            Some(triplet) if host(&Path::new("/lib").join(triplet)).is_dir() => format!("lib/{}", triplet),
+            _ if is_64 => "lib64".to_string(),
+            _ => "lib".to_string(),
+        };
+        default_dirs.extend(defaults.iter().map(PathBuf::from));
+
+        let mut conf_dirs = Vec::new();
+        ld_so_conf_dirs(Path::new(LD_SO_CONF), sysroot, &mut conf_dirs, 0);
+
+        LibrarySearch {
+            sysroot: sysroot.map(Path::to_path_buf),
+            is_64,
+            machine: elf.header.e_machine,
+            lib,
+            // Our environment says nothing about how the target system runs its binaries
+            ld_library_path: std::env::var("LD_LIBRARY_PATH").ok()
+                .filter(|_| sysroot.is_none())
+                .map(|v| v.split([':', ';']).filter(|d| !d.is_empty()).map(|d| d.to_string()).collect())
+                .unwrap_or_default(),
+            cache: read_ld_so_cache(&host(Path::new(LD_SO_CACHE))).unwrap_or_default(),
+            conf_dirs,
+            default_dirs,
+            given: given.map(|g| g.to_vec()),
+        }
+    }
+
+    /// `path` on the target system -> where it is on this host.
+    fn host(&self, path: &Path) -> PathBuf {
+        match &self.sysroot {
+            Some(root) => resolve_in_root(root, path),
+            None => path.to_path_buf(),
+        }
+    }
+
+    /// Where a host path inside the sysroot is on the target system; paths outside it are kept as they are.
+    fn target(&self, path: &Path) -> PathBuf {
+        match self.sysroot.as_ref().and_then(|root| path.strip_prefix(root).ok()) {
+            Some(inside) => Path::new("/").join(inside),
+            None => path.to_path_buf(),
+        }
+    }
+
+    /// Locates `soname` for `loaders[requester]`, following the glibc order:
+    /// DT_RPATH of the requester and the objects which loaded it (unless the requester has DT_RUNPATH),
+    /// LD_LIBRARY_PATH, DT_RUNPATH of the requester, ld.so.cache, then the default directories.
+    fn find(&self, soname: &str, loaders: &[Loader], requester: usize) -> Option<(PathBuf, SearchRule)> {
+        if soname.contains('/') {
+            let path = self.host(&self.expand(soname, &loaders[requester]).map(PathBuf::from)?);
+            return self.compatible(&path).then_some((path, SearchRule::Path));
+        }
+        if let Some(given) = &self.given {
+            return self.find_in(soname, given.iter().cloned(), SearchRule::Given);
+        }
+
+        let this = &loaders[requester];
+        if this.runpath.is_empty() {
+            let mut chain = Some(requester);
+            let mut rpath_dirs = Vec::new();
+            while let Some(i) = chain {
+                rpath_dirs.extend(loaders[i].rpath.iter().filter_map(|d| self.expand(d, &loaders[i])).map(PathBuf::from));
+                chain = loaders[i].loader;
+            }
+            if let Some(found) = self.find_in(soname, rpath_dirs.into_iter(), SearchRule::Rpath) {
+                return Some(found);
+            }
+        }
+        let ld_library_path = self.ld_library_path.iter().filter_map(|d| self.expand(d, this)).map(PathBuf::from);
+        if let Some(found) = self.find_in(soname, ld_library_path, SearchRule::LdLibraryPath) {
+            return Some(found);
+        }
+        let runpath = this.runpath.iter().filter_map(|d| self.expand(d, this)).map(PathBuf::from);
+        if let Some(found) = self.find_in(soname, runpath, SearchRule::Runpath) {
+            return Some(found);
+        }
+        // -z nodeflib skips the cache and the default directories
+        if this.nodeflib {
+            return None;
+        }
+        if let Some(paths) = self.cache.get(soname)
+            && let Some(path) = paths.iter().map(|p| self.host(p)).find(|p| self.compatible(p)) {
+            return Some((path, SearchRule::LdSoCache));
+        }
+        self.find_in(soname, self.conf_dirs.iter().cloned(), SearchRule::LdSoConf)
+            .or_else(|| self.find_in(soname, self.default_dirs.iter().cloned(), SearchRule::DefaultPath))
+    }
+
+    fn find_in(&self, soname: &str, dirs: impl Iterator<Item = PathBuf>, rule: SearchRule) -> Option<(PathBuf, SearchRule)> {
+        dirs.map(|dir| self.host(&dir.join(soname)))
+            .find(|candidate| self.compatible(candidate))
+            .map(|path| (path, rule))
+    }
+
+    /// ld.so keeps searching past a file of the wrong ELF class or machine, so we do too.
+    fn compatible(&self, path: &Path) -> bool {
+        let mut header = [0u8; 20];
+        let Ok(mut file) = fs::File::open(path) else { return false };
+        if std::io::Read::read_exact(&mut file, &mut header).is_err() || &header[..4] != b"\x7fELF" {
+            return false;
+        }
+        let is_64 = header[4] == 2;
+        let machine = if header[5] == 2 { u16::from_be_bytes([header[18], header[19]]) } else { u16::from_le_bytes([header[18], header[19]]) };
+        is_64 == self.is_64 && machine == self.machine
+    }
+
+    /// Expands $ORIGIN, $LIB and $PLATFORM (also as ${...}); None if the entry uses a token we can't expand,
+    /// which ld.so drops as well.
+    fn expand(&self, dir: &str, loader: &Loader) -> Option<String> {
+        let mut out = dir.to_string();
+        let tokens = [
+            ("ORIGIN", Some(loader.origin.to_string_lossy().to_string())),
+            ("LIB", Some(self.lib.clone())),
+            ("PLATFORM", platform_name(self.machine, self.is_64).map(|p| p.to_string())),
+        ];
+        for (token, value) in tokens {
+            if let Some(value) = value {
+                out = out.replace(&format!("${{{}}}", token), &value).replace(&format!("${}", token), &value);
+            }
+        }
+        if out.contains('$') { None } else { Some(out) }
+    }
+}
+
+/// Debian/Ubuntu multiarch directory name for an ELF machine.
+fn multiarch_triplet(machine: u16, is_64: bool) -> Option<&'static str> {
+    use goblin::elf::header::*;
+    match (machine, is_64) {
+        (EM_X86_64, true) => Some("x86_64-linux-gnu"),
+        (EM_386, false) => Some("i386-linux-gnu"),
+        (EM_AARCH64, true) => Some("aarch64-linux-gnu"),
+        (EM_ARM, false) => Some("arm-linux-gnueabihf"),
+        (EM_RISCV, true) => Some("riscv64-linux-gnu"),
+        (EM_PPC64, true) => Some("powerpc64le-linux-gnu"),
+        (EM_S390, true) => Some("s390x-linux-gnu"),
+        _ => None,
+    }
+}
+
+/// AT_PLATFORM as ld.so would see it on matching hardware.
+fn platform_name(machine: u16, is_64: bool) -> Option<&'static str> {
+    use goblin::elf::header::*;
+    match (machine, is_64) {
+        (EM_X86_64, true) => Some("x86_64"),
+        (EM_386, false) => Some("i686"),
+        (EM_AARCH64, true) => Some("aarch64"),
+        (EM_ARM, false) => Some("v7l"),
+        (EM_RISCV, _) => Some("riscv"),
+        _ => None,
+    }
+}
+
+/// Directories listed in ld.so.conf, following `include` lines (with * and ? globs) like ldconfig does.
+/// `conf` and the directories are target paths; files are read from inside `sysroot` when given.
+fn ld_so_conf_dirs(conf: &Path, sysroot: Option<&Path>, dirs: &mut Vec<PathBuf>, depth: usize) {
+    // Include loops are a config error; don't recurse forever on one
+    if depth > 8 {
+        return;
+    }
+    let host = |path: &Path| match sysroot {
+        Some(root) => resolve_in_root(root, path),
+        None => path.to_path_buf(),
+    };
+    let Ok(text) = fs::read_to_string(host(conf)) else { return };
+    for line in text.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        if let Some(pattern) = line.strip_prefix("include").filter(|rest| rest.starts_with([' ', '\t'])) {
+            for pattern in pattern.split_whitespace() {
+                // Relative includes are relative to the including file
+                let pattern = conf.parent().unwrap_or(Path::new("/")).join(pattern);
+                let (Some(dir), Some(name)) = (pattern.parent(), pattern.file_name()) else { continue };
+                for file in glob_files(&host(dir).join(name)) {
+                    if let Some(file_name) = file.file_name() {
+                        ld_so_conf_dirs(&dir.join(file_name), sysroot, dirs, depth + 1);
+                    }
+                }
+            }
+        }
+        else if line.starts_with('/') {
+            // Old "dir=libc5" style type suffixes are still accepted by ldconfig
+            let dir = PathBuf::from(line.split('=').next().unwrap_or(line).trim_end_matches(':'));
+            if !dirs.contains(&dir) {
+                dirs.push(dir);
+            }
+        }
+        // "hwcap" lines and anything else are ignored, ldconfig warns about them
+    }
+}
+
+/// Files matching a glob in the last path component only, eg /etc/ld.so.conf.d/*.conf, sorted like glob(3).
+fn glob_files(pattern: &Path) -> Vec<PathBuf> {
+    let (Some(dir), Some(name)) = (pattern.parent(), pattern.file_name().map(|n| n.to_string_lossy().to_string())) else {
+        return Vec::new();
+    };
+    if !name.contains(['*', '?']) {
+        return if pattern.is_file() { vec![pattern.to_path_buf()] } else { Vec::new() };
+    }
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .map(|rd| rd.filter_map(|e| e.ok())
+            .filter(|e| wildcard_match(name.as_bytes(), e.file_name().to_string_lossy().as_bytes()))
+            .map(|e| e.path())
+            .filter(|p| p.is_file())
+            .collect())
+        .unwrap_or_default();
+    files.sort();
+    files
+}
+
+fn wildcard_match(pattern: &[u8], name: &[u8]) -> bool {
+    match (pattern.first(), name.first()) {
+        (None, None) => true,
+        (Some(b'*'), _) => wildcard_match(&pattern[1..], name) || (!name.is_empty() && wildcard_match(pattern, &name[1..])),
+        (Some(b'?'), Some(_)) => wildcard_match(&pattern[1..], &name[1..]),
+        (Some(p), Some(n)) if p == n => wildcard_match(&pattern[1..], &name[1..]),
+        _ => false,
+    }
+}
+
+/// `path` as it resolves with `root` as /: absolute symlinks in an extracted filesystem point back into it,
+/// not at the host's files. Symlink loops give up and return what was resolved so far.
+fn resolve_in_root(root: &Path, path: &Path) -> PathBuf {
+    use std::path::Component;
+    // Normal components by name, ".." as None
+    let parts = |p: &Path| -> Vec<Option<std::ffi::OsString>> {
+        p.components().filter_map(|c| match c {
+            Component::Normal(name) => Some(Some(name.to_os_string())),
+            Component::ParentDir => Some(None),
+            _ => None,
+        }).collect()
+    };
+    let mut pending: VecDeque<Option<std::ffi::OsString>> = parts(path).into();
+    let mut resolved = PathBuf::new();
+    let mut links = 0;
+    while let Some(part) = pending.pop_front() {
+        let Some(name) = part else {
+            resolved.pop();
+            continue;
+        };
+        let next = resolved.join(&name);
+        match fs::read_link(root.join(&next)) {
+            // Same limit as the kernel's MAXSYMLINKS
+            Ok(link) if links < 40 => {
+                links += 1;
+                if link.is_absolute() {
+                    resolved = PathBuf::new();
+                }
+                for part in parts(&link).into_iter().rev() {
+                    pending.push_front(part);
+                }
+            }
+            _ => resolved = next,
+        }
+    }
+    root.join(resolved)
+}
+
+/// soname -> paths from the "glibc-ld.so.cache1.1" format ldconfig writes, alone or after the old "ld.so-1.7.0" table.
+fn read_ld_so_cache(path: &Path) -> Option<HashMap<String, Vec<PathBuf>>> {
+    const OLD_MAGIC: &[u8] = b"ld.so-1.7.0";
+    const NEW_MAGIC: &[u8] = b"glibc-ld.so.cache1.1";
+    let bytes = fs::read(path).ok()?;
+    let u32_at = |at: usize| bytes.get(at..at + 4).map(|b| u32::from_ne_bytes([b[0], b[1], b[2], b[3]]) as usize);
+
+    let mut start = 0;
+    if bytes.starts_with(OLD_MAGIC) {
+        // Old header is magic + nlibs, then 12 byte entries; the new table follows 8 byte aligned
+        let nlibs = u32_at(12)?;
+        start = (16 + nlibs * 12).next_multiple_of(8);
+    }
+    if !bytes.get(start..)?.starts_with(NEW_MAGIC) {
+        return None;
+    }
+    let nlibs = u32_at(start + 20)?;
+    let mut cache: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    // 48 byte header, then { flags, key, value, osversion, hwcap: u64 } entries with string offsets from the header
+    for i in 0..nlibs {
+        let entry = start + 48 + i * 24;
+        let (key, value) = (u32_at(entry + 4)?, u32_at(entry + 8)?);
+        let string_at = |offset: usize| -> Option<String> {
+            let s = bytes.get(start + offset..)?;
+            let end = s.iter().position(|b| *b == 0)?;
+            Some(String::from_utf8_lossy(&s[..end]).to_string())
+        };
+        if let (Some(soname), Some(path)) = (string_at(key), string_at(value)) {
+            cache.entry(soname).or_default().push(PathBuf::from(path));
+        }
+    }
+    Some(cache)
+}
+
+/// Dynamic symbols a shared object defines, with their symbol versions.
+#[derive(Debug, Clone, Default)]
+struct LibraryExports {
+    /// Name -> version of each definition; None for unversioned (or base version) definitions
+    symbols: HashMap<String, Vec<Option<String>>>,
+    /// Every version the library defines in .gnu.version_d, eg "GLIBC_2.34"
+    versions: HashSet<String>,
+}
+
+impl LibraryExports {
+    /// Whether an import of `name`, bound to `version` if it has one, resolves here.
+    /// An unversioned definition satisfies any versioned import, as in ld.so.
+    fn provides(&self, name: &str, version: Option<&str>) -> bool {
+        self.symbols.get(name).is_some_and(|defs| match version {
+            Some(version) => defs.iter().any(|v| v.is_none() || v.as_deref() == Some(version)),
+            None => true,
+        })
+    }
+}
+
+/// Version index -> name for the versions an object defines (.gnu.version_d), skipping the base version
+/// which only names the file.
+fn version_definitions(elf: &Elf) -> HashMap<u16, String> {
+    let mut names = HashMap::new();
+    if let Some(verdef) = &elf.verdef {
+        for def in verdef.iter() {
+            if def.vd_flags & symver::VER_FLG_BASE != 0 {
+                continue;
+            }
+            if let Some(aux) = def.iter().next()
+                && let Some(name) = elf.dynstrtab.get_at(aux.vda_name) {
+                names.insert(def.vd_ndx, name.to_string());
+            }
         }
     }
-    None
+    names
 }
 
-/// Parse a shared object and return a set of exported symbol names (dynamic symbols
-/// that are defined in the DSO, i.e., st_shndx != SHN_UNDEF).
-fn exported_symbols_from_so(path: &Path) -> crate::DynResult<HashSet<String>> {
+/// Version index -> (soname, version) for the versions an object needs (.gnu.version_r).
+fn version_needs(elf: &Elf) -> HashMap<u16, (String, String)> {
+    let mut needs = HashMap::new();
+    if let Some(verneed) = &elf.verneed {
+        for need in verneed.iter() {
+            let Some(file) = elf.dynstrtab.get_at(need.vn_file) else { continue };
+            for aux in need.iter() {
+                if let Some(name) = elf.dynstrtab.get_at(aux.vna_name) {
+                    needs.insert(aux.vna_other, (file.to_string(), name.to_string()));
+                }
+            }
+        }
+    }
+    needs
+}
+
+/// Index into .gnu.version for dynamic symbol `index`, without the hidden bit; 0 (local) or 1 (global) mean unversioned.
+fn symbol_version_index(elf: &Elf, index: usize) -> u16 {
+    elf.versym.as_ref()
+        .and_then(|versym| versym.get_at(index))
+        .map(|v| v.version())
+        .unwrap_or(symver::VER_NDX_GLOBAL)
+}
+
+/// Parse a shared object and return its exported symbol names (dynamic symbols
+/// that are defined in the DSO, i.e., st_shndx != SHN_UNDEF) and their versions.
+fn exported_symbols_from_so(path: &Path) -> crate::DynResult<LibraryExports> {
     let buf = fs::read(path)?;
     let elf = parse_elf_bytes(&buf)?;
+    let definitions = version_definitions(&elf);
 
-    let mut exports = HashSet::new();
+    let mut exports = LibraryExports {
+        versions: definitions.values().cloned().collect(),
+        ..Default::default()
+    };
     // dynsyms generally indicate exported/needed runtime symbols
-    for sym_entry in &elf.dynsyms {
+    for (i, sym_entry) in elf.dynsyms.iter().enumerate() {
         // if st_shndx != 0 (SHN_UNDEF), it's defined in this object
-        if sym_entry.st_shndx != goblin::elf::section_header::SHN_UNDEF as usize {
-            if let Some(name) = elf.dynstrtab.get_at(sym_entry.st_name) {
-                // Skip empty or internal names
-                if !name.is_empty() {
-                    exports.insert(name.to_string());
-                }
-            }
+        if sym_entry.st_shndx != goblin::elf::section_header::SHN_UNDEF as usize
+            && let Some(name) = elf.dynstrtab.get_at(sym_entry.st_name)
+            // Skip empty or internal names
+            && !name.is_empty() {
+            let version = definitions.get(&symbol_version_index(&elf, i)).cloned();
+            exports.symbols.entry(name.to_string()).or_default().push(version);
         }
     }
     Ok(exports)
 }
 
-/// Build a recursive load order from an initial list of sonames (DT_NEEDED),
-/// resolving each soname file on disk via `search_paths`. This returns a vector
-/// of resolved file paths in the order they should be searched (first -> last).
+/// "GLIBC_2.34" -> ("GLIBC", [2, 34]), "GLIBCXX_3.4.29" -> ("GLIBCXX", [3, 4, 29]); None for eg "GLIBC_PRIVATE".
+pub fn split_version(version: &str) -> Option<(&str, Vec<u64>)> {
+    let (family, number) = version.rsplit_once('_')?;
+    let parts: Option<Vec<u64>> = number.split('.').map(|p| p.parse().ok()).collect();
+    parts.map(|parts| (family, parts))
+}
+
+/// A DT_NEEDED soname which was located on disk.
+#[derive(Debug, Clone)]
+pub struct ResolvedLibrary {
+    pub soname: String,
+    pub path: PathBuf,
+    /// This library's own DT_NEEDED entries
+    pub needed: Vec<String>,
+    /// Soname of the library whose DT_NEEDED entry loaded this one, None for the executable's own
+    pub needed_by: Option<String>,
+    pub found_by: SearchRule,
+}
+
+/// Build a recursive load order from the executable's DT_NEEDED entries, resolving each
+/// soname on disk with the loader's search rules (see `LibrarySearch::find`). This returns the
+/// resolved libraries in the order they should be searched (first -> last).
 ///
 /// We perform a breadth-first recursive discovery while respecting the
-/// initial ordering (left-to-right).
-fn build_load_order(initial_sonames: &[&str], search_paths: &[PathBuf]) -> crate::DynResult<Vec<PathBuf>> {
-    let mut order = Vec::new();
+/// initial ordering (left-to-right), which is also the order ld.so loads them in.
+/// Sonames which cannot be located are appended to `missing_sonames`.
+fn build_load_order(target_path: &Path, elf: &Elf, search: &LibrarySearch, missing_sonames: &mut Vec<String>) -> crate::DynResult<Vec<ResolvedLibrary>> {
+    let mut order: Vec<ResolvedLibrary> = Vec::new();
     let mut seen_sonames = HashSet::new();
-    let mut queue: VecDeque<String> = VecDeque::new();
+    // The executable's $ORIGIN is where it really lives, ld.so resolves it through /proc/self/exe
+    let real_target = fs::canonicalize(target_path).unwrap_or_else(|_| target_path.to_path_buf());
+    // loaders[0] is the executable, loaders[i + 1] is order[i]
+    let mut loaders = vec![Loader::of(elf, &search.target(&real_target), None)];
+    // (soname, index into loaders of the object which needs it)
+    let mut queue: VecDeque<(String, usize)> = VecDeque::new();
 
     // seed with initial sonames preserving order
-    for s in initial_sonames {
-        queue.push_back(s.to_string());
+    for s in elf.libraries.iter() {
+        queue.push_back((s.to_string(), 0));
     }
 
-    while let Some(soname) = queue.pop_front() {
+    while let Some((soname, requester)) = queue.pop_front() {
         if seen_sonames.contains(&soname) {
             continue;
         }
         seen_sonames.insert(soname.clone());
 
-        if let Some(path) = find_library_on_paths(&soname, search_paths) {
-            order.push(path.clone());
-
+        if let Some((path, found_by)) = search.find(&soname, &loaders, requester) {
             // parse the library to find its DT_NEEDED children and queue them
             let buf = fs::read(&path)?;
-            let elf = parse_elf_bytes(&buf)?;
-            for dep in &elf.libraries {
+            let lib = parse_elf_bytes(&buf)?;
+            loaders.push(Loader::of(&lib, &search.target(&path), Some(requester)));
+            for dep in &lib.libraries {
                 if !seen_sonames.contains(*dep) {
-                    queue.push_back(dep.to_string());
+                    queue.push_back((dep.to_string(), loaders.len() - 1));
                 }
             }
+            order.push(ResolvedLibrary {
+                soname,
+                path,
+                needed: lib.libraries.iter().map(|s| s.to_string()).collect(),
+                needed_by: if requester == 0 { None } else { Some(order[requester - 1].soname.clone()) },
+                found_by,
+            });
         } else {
             // library file not found; we still mark it seen to avoid infinite loop,
-            // but we warn (returning an error could be another choice)
-            eprintln!("warning: could not locate '{}' in provided search paths", soname);
+            // and report it to the caller (returning an error could be another choice)
+            missing_sonames.push(soname);
         }
     }
 
     Ok(order)
 }
 
-/// Simulate symbol resolution for `target_path` using the `search_paths`.
+/// Symbols every object defines for the linker's own use; defining them twice is not a conflict.
+const LINKER_SYMBOLS: &[&str] = &[
+    "_init", "_fini", "_edata", "_end", "__bss_start", "_DYNAMIC", "_GLOBAL_OFFSET_TABLE_",
+    "__data_start", "data_start", "_IO_stdin_used", "__dso_handle",
+];
+
+/// Undefined symbols the toolchain's start files reference weakly, which are normally absent.
+const OPTIONAL_SYMBOLS: &[(&str, &str)] = &[
+    ("__gmon_start__", "gprof hook, only defined when built with -pg"),
+    ("_ITM_registerTMCloneTable", "libitm transactional memory hook"),
+    ("_ITM_deregisterTMCloneTable", "libitm transactional memory hook"),
+    ("__cxa_finalize", "C++ runtime hook run from crtstuff when unloading"),
+    ("_Jv_RegisterClasses", "GCJ Java runtime hook"),
+    ("__register_frame_info", "libgcc unwinder hook, only used without .eh_frame_hdr"),
+    ("__deregister_frame_info", "libgcc unwinder hook, only used without .eh_frame_hdr"),
+];
+
+/// A symbol defined by more than one object in the global scope; ld.so binds every reference to the first.
+#[derive(Debug, Clone)]
+pub struct DuplicateExport {
+    pub symbol: String,
+    /// Objects defining it in scope order (the executable by file name, then libraries by soname); the first wins
+    pub providers: Vec<String>,
+}
+
+/// Result of `simulate_dynamic_linking`.
+#[derive(Debug, Clone, Default)]
+pub struct LinkSimulation {
+    /// Library soname -> undefined symbols of the target which it resolves, sorted
+    pub lib_funcs: BTreeMap<String, Vec<String>>,
+    /// Undefined symbols of the target which no library in the load order exports, sorted
+    pub symbols_not_found: Vec<String>,
+    /// `symbols_not_found` with their binding and type; only the strong ones stop the loader
+    pub unresolved_symbols: Vec<UnresolvedSymbol>,
+    /// DT_NEEDED sonames (direct or transitive) which were not located on disk
+    pub libraries_not_found: Vec<String>,
+    /// Non-fatal problems hit while simulating, eg a library which failed to parse
+    pub warnings: Vec<String>,
+    /// Every library located on disk, in load order
+    pub libraries: Vec<ResolvedLibrary>,
+    /// Undefined symbol of the target -> the version it is bound to, eg "printf" -> "GLIBC_2.2.5"
+    pub symbol_versions: BTreeMap<String, String>,
+    /// Soname -> versions the target needs from it (.gnu.version_r), sorted
+    pub required_versions: BTreeMap<String, Vec<String>>,
+    /// Required versions (or versioned imports) the resolved library does not provide; ld.so refuses to start these
+    pub version_mismatches: Vec<String>,
+    /// Symbols more than one object in the global scope exports, sorted by name
+    pub duplicate_exports: Vec<DuplicateExport>,
+}
+
+/// Dynamic symbol indices of data the executable imports through copy relocations. They are defined
+/// in its .bss, but ld.so still has to find the original in a library to copy it from.
+fn copy_relocated(elf: &Elf) -> HashSet<usize> {
+    use goblin::elf::header;
+    use goblin::elf::reloc;
+    let copy = match elf.header.e_machine {
+        header::EM_X86_64 => reloc::R_X86_64_COPY,
+        header::EM_386 => reloc::R_386_COPY,
+        header::EM_AARCH64 => reloc::R_AARCH64_COPY,
+        header::EM_ARM => reloc::R_ARM_COPY,
+        _ => return HashSet::new(),
+    };
+    elf.dynrelas.iter().chain(elf.dynrels.iter())
+        .filter(|r| r.r_type == copy)
+        .map(|r| r.r_sym)
+        .collect()
+}
+
+/// A weak undefined symbol binds to 0 when nothing defines it, a strong one makes ld.so fail with
+/// "undefined symbol": at load time for data and TLS, or for functions under BIND_NOW, otherwise on the first call.
+fn unresolved_symbol(name: &str, bind: u8, typ: u8) -> UnresolvedSymbol {
+    let binding = match bind {
+        sym::STB_WEAK => "weak",
+        sym::STB_GLOBAL => "global",
+        sym::STB_GNU_UNIQUE => "unique",
+        _ => "local",
+    };
+    let symbol_type = match typ {
+        sym::STT_FUNC => "func",
+        sym::STT_OBJECT => "object",
+        sym::STT_TLS => "tls",
+        sym::STT_GNU_IFUNC => "ifunc",
+        STT_NOTYPE => "notype",
+        _ => "other",
+    };
+    UnresolvedSymbol {
+        name: name.to_string(),
+        binding: binding.into(),
+        symbol_type: symbol_type.into(),
+        optional: OPTIONAL_SYMBOLS.iter().find(|(optional, _)| *optional == name).map(|(_, why)| why.to_string()),
+        fatal: bind != sym::STB_WEAK,
+    }
+}
+
+/// Simulate symbol resolution for `target_path`, locating libraries the way ld.so would,
+/// or only in `search_paths` when given.
 ///
 /// For each undefined dynamic symbol in the target, find the first library in
-/// the load order which exports it, and print the mapping.
-pub fn simulate_dynamic_linking(target_path: &Path, search_paths: Option<&[PathBuf]>, args: &crate::args::Args) -> crate::DynResult< (std::collections::HashMap<String, Vec<String>>, Vec<String>) > {
-    let mut lib_funcs: std::collections::HashMap<String, Vec<String>> = std::collections::HashMap::new();
-    let mut symbols_not_found: Vec<String> = Vec::new();
-
-    let search_paths_vec: Vec<PathBuf> = match search_paths {
-        Some(sp) => sp.to_vec(),
-        None => DEFAULT_SEARCH_PATHS.iter().map(|s| PathBuf::from(s)).collect(),
-    };
+/// the load order which exports it, and record the mapping.
+pub fn simulate_dynamic_linking(target_path: &Path, search_paths: Option<&[PathBuf]>, args: &crate::args::Args) -> crate::DynResult<LinkSimulation> {
+    let mut sim = LinkSimulation::default();
 
     let buf = fs::read(target_path)?;
     let elf = parse_elf_bytes(&buf)?;
+    let search = LibrarySearch::new(&elf, search_paths, args.sysroot.as_deref());
 
     // println!("Target: {}", target_path.display());
     // println!("DT_NEEDED (declared shared libs):");
@@ -117,7 +684,8 @@ pub fn simulate_dynamic_linking(target_path: &Path, search_paths: Option<&[PathB
     // }
 
     // Build full load order (search order) by resolving sonames on disk
-    let load_order_files = build_load_order(&elf.libraries, &search_paths_vec)?;
+    sim.libraries = build_load_order(target_path, &elf, &search, &mut sim.libraries_not_found)?;
+    let load_order_files: Vec<PathBuf> = sim.libraries.iter().map(|l| l.path.clone()).collect();
 
     // if load_order_files.is_empty() {
     //     println!("No resolved DT_NEEDED libraries found in search paths.");
@@ -129,63 +697,434 @@ pub fn simulate_dynamic_linking(target_path: &Path, search_paths: Option<&[PathB
     // }
 
     // Parse each library once and cache its exported symbols
-    let mut lib_exports: Vec<(PathBuf, HashSet<String>)> = Vec::new();
+    let mut lib_exports: Vec<(PathBuf, LibraryExports)> = Vec::new();
     for libpath in &load_order_files {
         match exported_symbols_from_so(libpath) {
-            Ok(set) => lib_exports.push((libpath.clone(), set)),
+            Ok(exports) => lib_exports.push((libpath.clone(), exports)),
             Err(e) => {
-                eprintln!(
-                    "warning: failed to parse exports from {}: {}",
+                sim.warnings.push(format!(
+                    "failed to parse exports from {}: {}",
                     libpath.display(),
                     e
-                );
+                ));
             }
         }
     }
 
-    // Collect undefined dynamic symbols from target (.dynsym where st_shndx == SHN_UNDEF)
-    let mut undefined_funcs: Vec<String> = Vec::new();
-    for sym_entry in &elf.dynsyms {
-        if sym_entry.st_shndx == goblin::elf::section_header::SHN_UNDEF as usize {
-            if let Some(name) = elf.dynstrtab.get_at(sym_entry.st_name) {
-                if !name.is_empty() {
-                    // optional: check symbol type to prefer functions only
-                    let typ = sym::st_type(sym_entry.st_info);
-                    // We'll include all undefined symbols; optionally filter for STT_FUNC
-                    undefined_funcs.push(name.to_string());
-                }
+    // The global scope is the executable, then every library in load order. Resolution below still binds
+    // the target's imports to libraries only, an executable which defines a symbol doesn't import it
+    let target_name = target_path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+    let mut providers: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    let mut target_exports = exported_symbols_from_so(target_path).unwrap_or_default();
+    // stdout, optarg, ... are copies the executable's R_*_COPY relocations made of the library's
+    // definition, which is exactly what the library expects; not interposition
+    let copied = copy_relocated(&elf);
+    for index in copied.iter() {
+        if let Some(sym) = elf.dynsyms.get(*index) && let Some(name) = elf.dynstrtab.get_at(sym.st_name) {
+            target_exports.symbols.remove(name);
+        }
+    }
+    let scope = std::iter::once((target_name, &target_exports))
+        .chain(lib_exports.iter().map(|(libpath, exports)| {
+            let soname = sim.libraries.iter().find(|l| l.path == *libpath).map(|l| l.soname.clone()).unwrap_or_default();
+            (soname, exports)
+        }));
+    for (object, exports) in scope {
+        for name in exports.symbols.keys() {
+            // Version definitions show up as absolute symbols named after the version
+            if LINKER_SYMBOLS.contains(&name.as_str()) || exports.versions.contains(name) {
+                continue;
             }
+            providers.entry(name.as_str()).or_default().push(object.clone());
         }
     }
+    sim.duplicate_exports = providers.into_iter()
+        .filter(|(_, objects)| objects.len() > 1)
+        .map(|(symbol, providers)| DuplicateExport { symbol: symbol.to_string(), providers })
+        .collect();
 
-    // println!("\nUndefined dynamic symbols (candidates to resolve):");
-    // for name in &undefined_funcs {
-    //     println!("  {}", name);
-    // }
+    // Collect undefined dynamic symbols from target (.dynsym where st_shndx == SHN_UNDEF)
+    // along with the version each is bound to in .gnu.version/.gnu.version_r
+    let needs = version_needs(&elf);
+    let mut undefined_funcs: Vec<(String, Option<String>)> = Vec::new();
+    let mut undefined_kinds: HashMap<String, (u8, u8)> = HashMap::new();
+    for (i, sym_entry) in elf.dynsyms.iter().enumerate() {
+        if (sym_entry.st_shndx == goblin::elf::section_header::SHN_UNDEF as usize || copied.contains(&i))
+            && let Some(name) = elf.dynstrtab.get_at(sym_entry.st_name)
+            && !name.is_empty() {
+            undefined_kinds.insert(name.to_string(), (sym_entry.st_bind(), sym_entry.st_type()));
+            let version = needs.get(&symbol_version_index(&elf, i)).map(|(_, version)| version.clone());
+            undefined_funcs.push((name.to_string(), version));
+        }
+    }
+
+    // Sorted so every report built from this is stable across runs and diff-friendly
+    undefined_funcs.sort();
+    undefined_funcs.dedup();
+
+    for (file, version) in needs.values() {
+        sim.required_versions.entry(file.clone()).or_default().push(version.clone());
+    }
+    for versions in sim.required_versions.values_mut() {
+        versions.sort();
+        versions.dedup();
+    }
 
-    //println!("\nResolution results:");
-    for sym_name in &undefined_funcs {
+    // ld.so checks every needed version against the library of that soname before binding anything
+    let mut missing_versions: HashSet<String> = HashSet::new();
+    for (file, versions) in sim.required_versions.iter() {
+        let Some(lib) = sim.libraries.iter().find(|l| l.soname == *file) else { continue };
+        let Some((_, exports)) = lib_exports.iter().find(|(path, _)| *path == lib.path) else { continue };
+        if exports.versions.is_empty() {
+            sim.warnings.push(format!("{} has no version information, but versions {} are required from it", lib.path.display(), versions.join(", ")));
+            continue;
+        }
+        for version in versions.iter().filter(|v| !exports.versions.contains(*v)) {
+            missing_versions.insert(version.clone());
+            let users: Vec<&str> = undefined_funcs.iter()
+                .filter(|(_, v)| v.as_deref() == Some(version.as_str()))
+                .map(|(name, _)| name.as_str())
+                .collect();
+            sim.version_mismatches.push(format!("{} ({}) does not define version {}, required by {}",
+                file, lib.path.display(), version, if users.is_empty() { "the binary".to_string() } else { users.join(", ") }));
+        }
+    }
+
+    for (sym_name, version) in &undefined_funcs {
+        if let Some(version) = version {
+            sim.symbol_versions.insert(sym_name.clone(), version.clone());
+        }
         let mut found: Option<&PathBuf> = None;
         for (libpath, exports) in &lib_exports {
-            if exports.contains(sym_name) {
+            if exports.provides(sym_name, version.as_deref()) {
                 found = Some(libpath);
                 break;
             }
         }
         match found {
             Some(libpath) => {
-                //println!("  {:<40} -> {}", sym_name, libpath.display());
-                let lib_path_str = format!("{}", libpath.file_name().unwrap_or(libpath.as_os_str()).to_string_lossy() );
-                lib_funcs.entry(lib_path_str)
-                         .or_insert_with(Vec::new)
+                // Keyed by soname; the file found may be named differently, eg through a sysroot symlink
+                let soname = sim.libraries.iter().find(|l| l.path == *libpath).map(|l| l.soname.clone())
+                    .unwrap_or_else(|| libpath.file_name().unwrap_or(libpath.as_os_str()).to_string_lossy().to_string());
+                sim.lib_funcs.entry(soname)
+                         .or_default()
                          .push(sym_name.to_string());
             },
             None => {
-                //println!("  {:<40} -> <NOT FOUND in load order>", sym_name);
-                symbols_not_found.push(sym_name.to_string());
+                // Exported, just not at the version this binary was linked against
+                let other = lib_exports.iter().find_map(|(libpath, exports)| exports.symbols.get(sym_name).map(|defs| (libpath, defs)));
+                match (other, version) {
+                    // Already reported with the whole version missing
+                    (Some(_), Some(version)) if missing_versions.contains(version) => {}
+                    (Some((libpath, defs)), Some(version)) => {
+                        let provided: Vec<String> = defs.iter().map(|v| format!("{}@{}", sym_name, v.as_deref().unwrap_or("(none)"))).collect();
+                        sim.version_mismatches.push(format!("{}@{} is required but {} provides only {}",
+                            sym_name, version, libpath.display(), provided.join(", ")));
+                    }
+                    _ => {
+                        sim.symbols_not_found.push(sym_name.to_string());
+                        let (bind, typ) = undefined_kinds.get(sym_name).copied().unwrap_or((sym::STB_GLOBAL, STT_NOTYPE));
+                        sim.unresolved_symbols.push(unresolved_symbol(sym_name, bind, typ));
+                    }
+                }
             }
         }
     }
 
-    Ok( (lib_funcs, symbols_not_found) )
+    Ok(sim)
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use std::ffi::OsString;
+
+    use clap::Parser;
+    use goblin::elf::header::EM_X86_64;
+
+    use super::*;
+    use crate::analysis::test_fixtures::FixtureDir;
+    use crate::args::Args;
+
+    fn simulate(binary: &Path, flags: &[&str]) -> LinkSimulation {
+        let mut argv: Vec<OsString> = vec!["cyber-nutrition-facts".into(), binary.into()];
+        argv.extend(flags.iter().map(OsString::from));
+        simulate_dynamic_linking(binary, None, &Args::parse_from(argv)).unwrap()
+    }
+
+    fn resolved<'a>(sim: &'a LinkSimulation, soname: &str) -> &'a ResolvedLibrary {
+        sim.libraries.iter().find(|l| l.soname == soname).unwrap_or_else(|| panic!("{} not resolved: {:?}", soname, sim))
+    }
+
+    /// Nothing configured, for an x86-64 executable; see `fake_library`.
+    fn empty_search() -> LibrarySearch {
+        LibrarySearch {
+            sysroot: None,
+            is_64: true,
+            machine: EM_X86_64,
+            ld_library_path: Vec::new(),
+            lib: "lib64".into(),
+            cache: HashMap::new(),
+            conf_dirs: Vec::new(),
+            default_dirs: Vec::new(),
+            given: None,
+        }
+    }
+
+    fn loader(origin: &Path, rpath: &[&str], runpath: &[&str], loaded_by: Option<usize>) -> Loader {
+        Loader {
+            origin: origin.to_path_buf(),
+            rpath: rpath.iter().map(|d| d.to_string()).collect(),
+            runpath: runpath.iter().map(|d| d.to_string()).collect(),
+            nodeflib: false,
+            loader: loaded_by,
+        }
+    }
+
+    /// Just the ELF header bytes `LibrarySearch::compatible` looks at, for an x86-64 shared object.
+    fn fake_library(fixtures: &FixtureDir, name: &str) -> PathBuf {
+        let mut header = b"\x7fELF\x02\x01\x01".to_vec();
+        header.resize(16, 0);
+        header.extend(3u16.to_le_bytes());
+        header.extend(EM_X86_64.to_le_bytes());
+        fixtures.write(name, header)
+    }
+
+    #[test]
+    fn rpath_comes_before_ld_library_path_and_runpath_after_it() {
+        let fixtures = FixtureDir::new("search-order");
+        for dir in ["rpath", "env", "runpath"] {
+            fake_library(&fixtures, &format!("{}/libcnf.so", dir));
+        }
+        let dir = |name: &str| fixtures.path().join(name).to_string_lossy().to_string();
+        let (rpath, env, runpath) = (dir("rpath"), dir("env"), dir("runpath"));
+        let mut search = empty_search();
+        search.ld_library_path = vec![env.clone()];
+        let find = |search: &LibrarySearch, loaders: &[Loader], requester: usize| {
+            let (path, rule) = search.find("libcnf.so", loaders, requester).unwrap();
+            (path.parent().unwrap().to_string_lossy().to_string(), rule)
+        };
+
+        let exe = loader(Path::new("/"), &[&rpath], &[], None);
+        assert_eq!(find(&search, &[exe], 0), (rpath.clone(), SearchRule::Rpath));
+        // DT_RUNPATH switches DT_RPATH off and is searched after LD_LIBRARY_PATH
+        let exe = loader(Path::new("/"), &[&rpath], &[&runpath], None);
+        assert_eq!(find(&search, &[exe], 0), (env.clone(), SearchRule::LdLibraryPath));
+        let exe = loader(Path::new("/"), &[], &[&runpath], None);
+        assert_eq!(find(&empty_search(), &[exe], 0), (runpath.clone(), SearchRule::Runpath));
+
+        // A library without DT_RUNPATH also searches the DT_RPATH of whatever loaded it, one with it doesn't
+        let loaders = [loader(Path::new("/"), &[&rpath], &[], None), loader(Path::new("/"), &[], &[], Some(0))];
+        assert_eq!(find(&search, &loaders, 1), (rpath.clone(), SearchRule::Rpath));
+        let loaders = [loader(Path::new("/"), &[&rpath], &[], None), loader(Path::new("/"), &[], &[&runpath], Some(0))];
+        assert_eq!(find(&search, &loaders, 1), (env.clone(), SearchRule::LdLibraryPath));
+    }
+
+    #[test]
+    fn origin_expands_to_the_requesting_objects_directory() {
+        let fixtures = FixtureDir::new("origin");
+        let library = fake_library(&fixtures, "app/lib/libcnf.so");
+        let search = empty_search();
+        let bin = fixtures.write("app/bin/app", "").parent().unwrap().to_path_buf();
+        let exe = loader(&bin, &["$ORIGIN/../lib"], &[], None);
+        let (path, rule) = search.find("libcnf.so", &[exe], 0).unwrap();
+        assert_eq!((fs::canonicalize(path).unwrap(), rule), (fs::canonicalize(library).unwrap(), SearchRule::Rpath));
+
+        let exe = loader(&bin, &[], &[], None);
+        assert_eq!(search.expand("${ORIGIN}/x:$LIB", &exe), Some(format!("{}/x:lib64", bin.display())));
+        // ld.so drops entries with tokens it doesn't know
+        assert_eq!(search.expand("$UNKNOWN/lib", &exe), None);
+    }
+
+    #[test]
+    fn ld_so_conf_follows_includes_in_order() {
+        let fixtures = FixtureDir::new("ld-so-conf");
+        fixtures.write("etc/ld.so.conf", "include ld.so.conf.d/*.conf\n/opt/a # comment\n/opt/b=libc5\nhwcap 0 nosegneg\n");
+        fixtures.write("etc/ld.so.conf.d/b.conf", "/opt/c\n/opt/a\n");
+        fixtures.write("etc/ld.so.conf.d/a.conf", "/opt/d\n");
+        fixtures.write("etc/ld.so.conf.d/ignored.txt", "/opt/e\n");
+        let mut dirs = Vec::new();
+        ld_so_conf_dirs(Path::new(LD_SO_CONF), Some(fixtures.path()), &mut dirs, 0);
+        assert_eq!(dirs, ["/opt/d", "/opt/c", "/opt/a", "/opt/b"].map(PathBuf::from));
+
+        // An include loop must not hang
+        fixtures.write("etc/ld.so.conf.d/a.conf", "/opt/d\ninclude /etc/ld.so.conf\n");
+        let mut dirs = Vec::new();
+        ld_so_conf_dirs(Path::new(LD_SO_CONF), Some(fixtures.path()), &mut dirs, 0);
+        assert_eq!(dirs.len(), 4);
+    }
+
+    #[test]
+    fn binaries_report_the_rule_which_found_each_library() {
+        let fixtures = FixtureDir::new("rpath-runpath");
+        fixtures.compile("lib/libcnfdep.so", "int cnf_dep(void) { return 1; }\n", &["-shared", "-fPIC", "-Wl,-soname,libcnfdep.so"]);
+        let source = "int cnf_dep(void);\nint main(void) { return cnf_dep(); }\n";
+        let rpath = fixtures.compile("rpath", source, &["-Llib", "-lcnfdep", "-Wl,-rpath,$ORIGIN/lib", "-Wl,--disable-new-dtags"]);
+        let runpath = fixtures.compile("runpath", source, &["-Llib", "-lcnfdep", "-Wl,-rpath,$ORIGIN/lib", "-Wl,--enable-new-dtags"]);
+        let bare = fixtures.compile("bare", source, &["-Llib", "-lcnfdep"]);
+
+        let sim = simulate(&rpath, &[]);
+        assert_eq!(resolved(&sim, "libcnfdep.so").found_by, SearchRule::Rpath);
+        assert_eq!(sim.lib_funcs["libcnfdep.so"], ["cnf_dep"]);
+        assert_eq!(resolved(&simulate(&runpath, &[]), "libcnfdep.so").found_by, SearchRule::Runpath);
+        assert_eq!(simulate(&bare, &[]).libraries_not_found, ["libcnfdep.so"]);
+    }
+
+    #[test]
+    fn unversioned_definitions_satisfy_versioned_imports() {
+        let exports = LibraryExports {
+            symbols: HashMap::from([
+                ("old".to_string(), vec![Some("V_1".to_string())]),
+                ("plain".to_string(), vec![None]),
+            ]),
+            versions: HashSet::from(["V_1".to_string()]),
+        };
+        assert!(exports.provides("old", Some("V_1")));
+        assert!(exports.provides("old", None));
+        assert!(!exports.provides("old", Some("V_2")));
+        assert!(exports.provides("plain", Some("V_2")));
+        assert!(!exports.provides("missing", None));
+
+        assert_eq!(split_version("GLIBCXX_3.4.29"), Some(("GLIBCXX", vec![3, 4, 29])));
+        assert_eq!(split_version("GLIBC_PRIVATE"), None);
+    }
+
+    #[test]
+    fn missing_symbol_versions_are_reported() {
+        let fixtures = FixtureDir::new("symbol-versions");
+        let library = "int cnf_old(void) { return 1; }\nint cnf_new(void) { return 2; }\n";
+        fixtures.write("both.map", "CNF_1 { global: cnf_old; local: *; };\nCNF_2 { global: cnf_new; } CNF_1;\n");
+        fixtures.compile("lib/libcnfver.so", library, &["-shared", "-fPIC", "-Wl,-soname,libcnfver.so", "-Wl,--version-script=both.map"]);
+        let binary = fixtures.compile("app", "int cnf_old(void);\nint cnf_new(void);\nint main(void) { return cnf_old() + cnf_new(); }\n",
+            &["-Llib", "-lcnfver", "-Wl,-rpath,$ORIGIN/lib"]);
+
+        let sim = simulate(&binary, &[]);
+        assert_eq!(sim.required_versions["libcnfver.so"], ["CNF_1", "CNF_2"]);
+        assert_eq!(sim.symbol_versions["cnf_new"], "CNF_2");
+        assert_eq!(sim.lib_funcs["libcnfver.so"], ["cnf_new", "cnf_old"]);
+        assert!(sim.version_mismatches.is_empty(), "{:?}", sim.version_mismatches);
+
+        // An older build of the library without CNF_2 at all
+        fixtures.write("old.map", "CNF_1 { global: cnf_old; local: *; };\n");
+        fixtures.compile("lib/libcnfver.so", library, &["-shared", "-fPIC", "-Wl,-soname,libcnfver.so", "-Wl,--version-script=old.map"]);
+        let sim = simulate(&binary, &[]);
+        assert_eq!(sim.version_mismatches.len(), 1, "{:?}", sim.version_mismatches);
+        assert!(sim.version_mismatches[0].starts_with("libcnfver.so ("), "{}", sim.version_mismatches[0]);
+        assert!(sim.version_mismatches[0].ends_with("does not define version CNF_2, required by cnf_new"), "{}", sim.version_mismatches[0]);
+
+        // CNF_2 exists, but cnf_new moved to CNF_1
+        fixtures.write("moved.map", "CNF_1 { global: cnf_old; cnf_new; local: *; };\nCNF_2 { global: cnf_other; } CNF_1;\n");
+        fixtures.compile("lib/libcnfver.so", &format!("{}int cnf_other(void) {{ return 3; }}\n", library),
+            &["-shared", "-fPIC", "-Wl,-soname,libcnfver.so", "-Wl,--version-script=moved.map"]);
+        let sim = simulate(&binary, &[]);
+        assert_eq!(sim.version_mismatches.len(), 1, "{:?}", sim.version_mismatches);
+        assert!(sim.version_mismatches[0].starts_with("cnf_new@CNF_2 is required but "), "{}", sim.version_mismatches[0]);
+        assert!(sim.version_mismatches[0].ends_with("provides only cnf_new@CNF_1"), "{}", sim.version_mismatches[0]);
+        assert_eq!(sim.lib_funcs["libcnfver.so"], ["cnf_old"]);
+    }
+
+    #[test]
+    fn sysroot_paths_resolve_symlinks_inside_the_root() {
+        let fixtures = FixtureDir::new("resolve-in-root");
+        let root = fixtures.path();
+        fixtures.write("usr/lib/libx.so", "");
+        std::os::unix::fs::symlink("/usr/lib", root.join("lib")).unwrap();
+        std::os::unix::fs::symlink("../usr/lib/libx.so", root.join("usr/libx.so")).unwrap();
+        std::os::unix::fs::symlink("/loop", root.join("loop")).unwrap();
+
+        assert_eq!(resolve_in_root(root, Path::new("/lib/libx.so")), root.join("usr/lib/libx.so"));
+        assert_eq!(resolve_in_root(root, Path::new("/usr/libx.so")), root.join("usr/lib/libx.so"));
+        assert_eq!(resolve_in_root(root, Path::new("/../../etc/passwd")), root.join("etc/passwd"));
+        assert_eq!(resolve_in_root(root, Path::new("/loop/libx.so")), root.join("loop/libx.so"));
+    }
+
+    #[test]
+    fn sysroot_rebases_rpath_and_ld_so_conf() {
+        let fixtures = FixtureDir::new("sysroot");
+        fixtures.write("root/etc/ld.so.conf", "/opt/cnf\n");
+        fixtures.compile("root/opt/cnf/libcnfconf.so", "int cnf_conf(void) { return 1; }\n", &["-shared", "-fPIC", "-Wl,-soname,libcnfconf.so"]);
+        fixtures.compile("root/usr/lib/cnf/libcnfrpath.so", "int cnf_rpath(void) { return 2; }\n", &["-shared", "-fPIC", "-Wl,-soname,libcnfrpath.so"]);
+        let binary = fixtures.compile("root/usr/bin/app", "int cnf_conf(void);\nint cnf_rpath(void);\nint main(void) { return cnf_conf() + cnf_rpath(); }\n",
+            &["-Lroot/opt/cnf", "-Lroot/usr/lib/cnf", "-lcnfconf", "-lcnfrpath", "-Wl,-rpath,/usr/lib/cnf", "-Wl,--disable-new-dtags"]);
+
+        // Without --sysroot the absolute DT_RPATH and ld.so.conf are the host's
+        let host = simulate(&binary, &[]);
+        assert!(host.libraries_not_found.contains(&"libcnfconf.so".to_string()), "{:?}", host.libraries_not_found);
+        assert!(host.libraries_not_found.contains(&"libcnfrpath.so".to_string()), "{:?}", host.libraries_not_found);
+
+        let root = fixtures.path().join("root");
+        let sim = simulate(&binary, &["--sysroot", root.to_str().unwrap()]);
+        let root = fs::canonicalize(root).unwrap();
+        let conf = resolved(&sim, "libcnfconf.so");
+        assert_eq!((conf.path.as_path(), conf.found_by), (root.join("opt/cnf/libcnfconf.so").as_path(), SearchRule::LdSoConf));
+        let rpath = resolved(&sim, "libcnfrpath.so");
+        assert_eq!((rpath.path.as_path(), rpath.found_by), (root.join("usr/lib/cnf/libcnfrpath.so").as_path(), SearchRule::Rpath));
+        assert_eq!(sim.lib_funcs["libcnfrpath.so"], ["cnf_rpath"]);
+    }
+
+    #[test]
+    fn duplicate_exports_skip_copy_relocated_data() {
+        let fixtures = FixtureDir::new("duplicates");
+        let shared = ["-shared", "-fPIC"];
+        fixtures.compile("libcnfa.so", "int cnf_counter = 1;\nint cnf_shared(void) { return 1; }\n", &[&shared[..], &["-Wl,-soname,libcnfa.so"]].concat());
+        fixtures.compile("libcnfb.so", "int cnf_shared(void) { return 2; }\n", &[&shared[..], &["-Wl,-soname,libcnfb.so"]].concat());
+        // Non-PIE code reaches cnf_counter through an R_*_COPY relocation into the executable's .bss;
+        // nothing from libcnfb.so is used, so --as-needed would drop it
+        let binary = fixtures.compile("app", "extern int cnf_counter;\nint cnf_shared(void);\nint main(void) { return cnf_counter + cnf_shared(); }\n",
+            &["-no-pie", "-fno-pic", "-L.", "-Wl,--no-as-needed", "-lcnfa", "-lcnfb", "-Wl,-rpath,$ORIGIN"]);
+
+        let file = fs::read(&binary).unwrap();
+        let elf = Elf::parse(&file).unwrap();
+        let copied: Vec<&str> = copy_relocated(&elf).iter().filter_map(|i| elf.dynstrtab.get_at(elf.dynsyms.get(*i)?.st_name)).collect();
+        assert_eq!(copied, ["cnf_counter"]);
+
+        let sim = simulate(&binary, &[]);
+        let duplicate = |symbol: &str| sim.duplicate_exports.iter().find(|d| d.symbol == symbol);
+        assert_eq!(duplicate("cnf_shared").map(|d| d.providers.as_slice()), Some(["libcnfa.so".to_string(), "libcnfb.so".to_string()].as_slice()));
+        assert!(duplicate("cnf_counter").is_none(), "{:?}", sim.duplicate_exports);
+        assert!(duplicate("_init").is_none(), "{:?}", sim.duplicate_exports);
+        // Still an import, bound to the library the copy is made from
+        assert_eq!(sim.lib_funcs["libcnfa.so"], ["cnf_counter", "cnf_shared"]);
+    }
+
+    #[test]
+    fn only_strong_unresolved_symbols_are_fatal() {
+        let fixtures = FixtureDir::new("weak-symbols");
+        let flags = ["-shared", "-fPIC", "-Wl,-soname,libcnfweak.so"];
+        fixtures.compile("libcnfweak.so", "int cnf_data = 1;\nvoid cnf_optional(void) {}\nvoid cnf_required(void) {}\n", &flags);
+        let binary = fixtures.compile("app", r#"
+extern int cnf_data;
+void cnf_optional(void) __attribute__((weak));
+void cnf_required(void);
+int main(void) {
+    if (cnf_optional)
+        cnf_optional();
+    cnf_required();
+    return cnf_data;
+}
+"#, &["-L.", "-lcnfweak", "-Wl,-rpath,$ORIGIN"]);
+        assert!(simulate(&binary, &[]).unresolved_symbols.iter().all(|s| s.optional.is_some()));
+
+        // A later build of the library which dropped all three
+        fixtures.compile("libcnfweak.so", "void cnf_other(void) {}\n", &flags);
+        let sim = simulate(&binary, &[]);
+        let unresolved = |name: &str| sim.unresolved_symbols.iter()
+            .find(|s| s.name == name)
+            .map(|s| (s.binding.as_str(), s.symbol_type.as_str(), s.fatal))
+            .unwrap_or_else(|| panic!("{} resolved: {:?}", name, sim.unresolved_symbols));
+        assert_eq!(unresolved("cnf_optional"), ("weak", "func", false));
+        assert_eq!(unresolved("cnf_required"), ("global", "func", true));
+        assert_eq!(unresolved("cnf_data"), ("global", "object", true));
+        for name in ["cnf_data", "cnf_optional", "cnf_required"] {
+            assert!(sim.symbols_not_found.iter().any(|s| s == name), "{:?}", sim.symbols_not_found);
+        }
+    }
+
+    #[test]
+    fn toolchain_hooks_are_optional() {
+        let gmon = unresolved_symbol("__gmon_start__", sym::STB_WEAK, STT_NOTYPE);
+        assert_eq!((gmon.binding.as_str(), gmon.symbol_type.as_str(), gmon.fatal), ("weak", "notype", false));
+        assert_eq!(gmon.optional.as_deref(), Some("gprof hook, only defined when built with -pg"));
+        let tls = unresolved_symbol("cnf_tls", sym::STB_GLOBAL, sym::STT_TLS);
+        assert_eq!((tls.symbol_type.as_str(), tls.optional, tls.fatal), ("tls", None, true));
+    }
 }
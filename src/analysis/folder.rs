@@ -0,0 +1,172 @@
+
+// Recursive analysis of a project folder.
+// Every file goes through analyze_single_file(); the per-file reports are folded into one
+// NutritionFacts whose label scores the whole project and whose sections drill down per file.
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::args::ReportStyle;
+use crate::report::{Entry, Location, Metric, NutritionFacts, Provenance, Section, SectionData, SectionStatus};
+
+/// Directory names skipped unless --include-vendored is passed; third-party and generated code
+/// would otherwise drown out the project's own label.
+const SKIPPED_DIRS: &[&str] = &[
+    "vendor",
+    "vendored",
+    "third_party",
+    "third-party",
+    "thirdparty",
+    "external",
+    "node_modules",
+    "bower_components",
+    "target",
+    "build",
+    "dist",
+    "out",
+    "__pycache__",
+    ".venv",
+    "venv",
+    ".git",
+];
+
+/// Project-level metrics which are summed across source files; the label reads sloc and cyclomatic_sum.
+const SUMMED_METRICS: &[&str] = &["sloc", "functions", "cyclomatic_sum", "cognitive_sum"];
+
+pub fn analyze_folder(root: &Path, args: &crate::args::Args) -> crate::DynResult<NutritionFacts> {
+    let files = files_in_folder(root, args.include_vendored)?;
+
+    let mut total_bytes = 0u64;
+    let mut kinds: BTreeMap<String, usize> = BTreeMap::new();
+    let mut sums: BTreeMap<&str, f64> = BTreeMap::new();
+    let mut skipped = Entry::new("", ReportStyle::Detailed);
+    let mut file_sections = Vec::new();
+
+    for file in files.iter() {
+        let relative = file.strip_prefix(root).unwrap_or(file).display().to_string();
+        match super::analyze_single_file(file, args) {
+            Ok(facts) => {
+                total_bytes += facts.provenance.file_size.unwrap_or(0);
+                *kinds.entry(file_kind(&facts)).or_default() += 1;
+                for name in SUMMED_METRICS.iter() {
+                    if let Some(value) = facts.metric(name) {
+                        *sums.entry(name).or_default() += value;
+                    }
+                }
+                file_sections.push(file_section(&relative, file, facts));
+            }
+            Err(e) => skipped.push(Entry::new(format!("{}: {}", relative, e), ReportStyle::Detailed)),
+        }
+    }
+
+    let analyzed = file_sections.len();
+    let mut summary = Section::new("Project Summary");
+    summary.summary = Some(format!("{} files analyzed, {} skipped", analyzed, skipped.children.len()));
+    for (kind, count) in kinds.iter() {
+        summary.entries.push(Entry::new(format!("{}: {} files", kind, count), ReportStyle::Normal));
+    }
+    if !skipped.children.is_empty() {
+        skipped.text = format!("{} files could not be analyzed:", skipped.children.len());
+        summary.entries.push(skipped);
+    }
+    summary.metrics.push(Metric::new("files", analyzed as f64, ReportStyle::Normal));
+    for name in SUMMED_METRICS.iter() {
+        if let Some(value) = sums.get(name) {
+            summary.metrics.push(Metric::new(*name, *value, ReportStyle::Normal));
+        }
+    }
+
+    let mut facts = NutritionFacts::new(Provenance::for_folder(root, total_bytes));
+    facts.push(summary);
+    for section in file_sections {
+        facts.push(section);
+    }
+    Ok(facts)
+}
+
+/// Every regular file under `root` in path order, honoring .gitignore/.ignore files and skipping hidden files.
+fn files_in_folder(root: &Path, include_vendored: bool) -> crate::DynResult<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    let walker = ignore::WalkBuilder::new(root)
+        // .gitignore applies even if the folder is an unpacked tarball rather than a checkout
+        .require_git(false)
+        .filter_entry(move |entry| {
+            include_vendored
+                || entry.depth() == 0
+                || !entry.file_type().is_some_and(|t| t.is_dir())
+                || !SKIPPED_DIRS.contains(&entry.file_name().to_string_lossy().as_ref())
+        })
+        .build();
+    for entry in walker {
+        let entry = entry?;
+        if entry.file_type().is_some_and(|t| t.is_file()) {
+            files.push(entry.into_path());
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+/// "c source", "ELF binary", ... used to count what the project is made of.
+fn file_kind(facts: &NutritionFacts) -> String {
+    let mime = facts.provenance.mime.as_deref().unwrap_or("");
+    if super::is_elf(mime) {
+        "ELF binary".into()
+    }
+    else if super::is_pe32(mime) || super::is_pe64(mime) {
+        "PE binary".into()
+    }
+    else {
+        let lang = facts.sections.iter()
+            .find(|s| s.title == "Source Language")
+            .and_then(|s| s.summary.clone())
+            .unwrap_or_else(|| "unknown".into());
+        format!("{} source", lang)
+    }
+}
+
+/// Folds one file's report into a single section; each of its sections becomes a top-level entry.
+/// Metrics become entries too, so NutritionFacts::metric() only ever sees the project totals.
+/// Section data is kept, keyed by the inner section's title.
+fn file_section(relative: &str, file: &Path, facts: NutritionFacts) -> Section {
+    let mut section = Section::new(relative);
+    section.detail = ReportStyle::Normal;
+    let finding_count = facts.findings().count();
+    section.summary = Some(format!("{} ({}), {} findings", file_kind(&facts), facts.provenance.analyzer, finding_count));
+    let mut data = BTreeMap::new();
+
+    for inner in facts.sections {
+        if let Some(inner_data) = inner.data {
+            data.insert(inner.title.clone(), inner_data);
+        }
+        let mut entry = Entry::new(inner.title.clone(), inner.detail.clone().max(ReportStyle::Normal));
+        match (&inner.status, &inner.summary) {
+            (SectionStatus::Unavailable(reason), _) => entry.text = format!("{}: MISSING/UNKNOWN: {}", inner.title, reason),
+            (SectionStatus::Available, Some(summary)) => entry.text = format!("{}: {}", inner.title, summary),
+            (SectionStatus::Available, None) => {}
+        }
+        for metric in inner.metrics.iter() {
+            entry.push(Entry::new(format!("{} = {:.2}", metric.name, metric.value), metric.detail.clone()));
+        }
+        for child in inner.entries {
+            entry.push(child);
+        }
+        section.entries.push(entry);
+
+        for mut finding in inner.findings {
+            // Binary findings often have no location; in a project report they still need to say which file
+            if finding.location.is_none() {
+                finding.location = Some(Location { file: file.to_path_buf(), ..Default::default() });
+            }
+            // A file whose findings show up at Terse must show up at Terse too
+            if finding.detail < section.detail {
+                section.detail = finding.detail.clone();
+            }
+            section.findings.push(finding);
+        }
+    }
+    if !data.is_empty() {
+        section.data = Some(SectionData::FileReport { sections: data });
+    }
+    section
+}
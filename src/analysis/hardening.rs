@@ -0,0 +1,636 @@
+
+// Exploit mitigations a binary was built with.
+// Each mitigation is one Normal entry ("RELRO: full") and, when it is missing or weak, one
+// finding in Category::Hardening, which is what the label's hardening row scores.
+// ELF and PE mitigations which do the same job share a rule id (PIE and ASLR, NX and DEP, canary and /GS, CET)
+// so Linux and Windows builds of one product can be compared finding by finding.
+
+use std::path::Path;
+
+use goblin::elf::{dynamic, header, note, program_header, Elf};
+use goblin::pe::{dll_characteristic, load_config, PE};
+
+use crate::args::ReportStyle;
+use crate::report::{Category, Entry, Finding, Location, Section, Severity};
+
+const TITLE: &str = "Binary Hardening";
+
+/// libc functions glibc provides a `__<name>_chk` variant of under _FORTIFY_SOURCE.
+const FORTIFIABLE: &[&str] = &[
+    "memcpy", "memmove", "mempcpy", "memset", "stpcpy", "stpncpy", "strcat", "strcpy", "strncat", "strncpy",
+    "sprintf", "snprintf", "vsprintf", "vsnprintf", "printf", "fprintf", "vprintf", "vfprintf", "dprintf",
+    "gets", "fgets", "read", "pread", "recv", "recvfrom", "realpath", "getcwd", "wcscpy", "wcsncpy",
+    "wmemcpy", "wmemmove", "wmemset", "confstr", "ttyname_r", "getlogin_r", "gethostname", "poll", "ppoll",
+];
+
+/// GNU_PROPERTY_X86_FEATURE_1_AND and its Intel CET bits, see the x86-64 psABI.
+const GNU_PROPERTY_X86_FEATURE_1_AND: u32 = 0xc000_0002;
+const GNU_PROPERTY_X86_FEATURE_1_IBT: u32 = 1 << 0;
+const GNU_PROPERTY_X86_FEATURE_1_SHSTK: u32 = 1 << 1;
+
+/// Offsets from e_lfanew: the COFF Characteristics field, the optional header and its DllCharacteristics field.
+const PE_COFF_CHARACTERISTICS: u64 = 4 + 18;
+const PE_OPTIONAL_HEADER: u64 = 4 + 20;
+const PE_DLL_CHARACTERISTICS: u64 = PE_OPTIONAL_HEADER + 70;
+/// Data directory indexes, see the PE format's optional header data directories
+const PE_CERTIFICATE_TABLE: usize = 4;
+const PE_DEBUG_TABLE: usize = 6;
+const PE_LOAD_CONFIG_TABLE: usize = 10;
+
+/// Findings point at the program header of the segment they are about, eg PT_GNU_STACK for an executable stack.
+pub fn elf_hardening(path: &Path, elf: &Elf, bytes: &[u8]) -> Section {
+    let mut section = Section::new(TITLE);
+    let mut summary = Vec::new();
+
+    let has_phdr = |p_type: u32| elf.program_headers.iter().any(|ph| ph.p_type == p_type);
+    let (flags, flags_1) = elf.dynamic.as_ref()
+        .map(|d| (d.info.flags, d.info.flags_1))
+        .unwrap_or((0, 0));
+    let has_dyn_tag = |tag: u64| elf.dynamic.as_ref().is_some_and(|d| d.dyns.iter().any(|dy| dy.d_tag == tag));
+    let segment = |p_types: &[u32]| segment_location(path, elf, p_types);
+
+    // RELRO: PT_GNU_RELRO makes .got/.dynamic read-only after relocation, BIND_NOW extends that to .got.plt
+    let bind_now = flags & dynamic::DF_BIND_NOW != 0 || flags_1 & dynamic::DF_1_NOW != 0 || has_dyn_tag(dynamic::DT_BIND_NOW);
+    let relro = has_phdr(program_header::PT_GNU_RELRO);
+    section.entries.push(Entry::new(format!("BIND_NOW: {}", yes_no(bind_now)), ReportStyle::Normal));
+    match (relro, bind_now) {
+        (true, true) => {
+            summary.push("full RELRO");
+            section.entries.push(Entry::new("RELRO: full (PT_GNU_RELRO + BIND_NOW)", ReportStyle::Normal));
+        }
+        (true, false) => {
+            summary.push("partial RELRO");
+            section.entries.push(Entry::new("RELRO: partial (PT_GNU_RELRO without BIND_NOW)", ReportStyle::Normal));
+            section.findings.push(hardening_finding("CNF-HARD-002", "Partial RELRO", Severity::Low,
+                "The GOT used for lazy binding stays writable; link with -Wl,-z,relro,-z,now".into(), segment(&[program_header::PT_GNU_RELRO])));
+        }
+        (false, _) => {
+            summary.push("no RELRO");
+            section.entries.push(Entry::new("RELRO: none", ReportStyle::Normal));
+            section.findings.push(hardening_finding("CNF-HARD-001", "No RELRO", Severity::Medium,
+                "No PT_GNU_RELRO segment, the GOT and .dynamic stay writable for the whole run; link with -Wl,-z,relro,-z,now".into(), segment(&[program_header::PT_DYNAMIC])));
+        }
+    }
+
+    // PIE: executables must be ET_DYN to be loaded at a random base. Shared libraries always are.
+    // Old linkers don't set DF_1_PIE, so fall back to PT_INTERP; libc.so.6 and ld.so have one too,
+    // but they also have a library soname
+    let library_soname = elf.soname.is_some_and(|s| s.starts_with("lib") || s.starts_with("ld") || s.contains(".so"));
+    let is_pie = elf.header.e_type == header::ET_DYN
+        && (flags_1 & dynamic::DF_1_PIE != 0 || (elf.interpreter.is_some() && !library_soname));
+    if elf.header.e_type == header::ET_DYN && !is_pie {
+        summary.push("PIC shared object");
+        section.entries.push(Entry::new("PIE: n/a (PIC shared object, always position independent)", ReportStyle::Normal));
+    }
+    else if is_pie {
+        summary.push("PIE");
+        section.entries.push(Entry::new("PIE: yes", ReportStyle::Normal));
+    }
+    else {
+        summary.push("no PIE");
+        section.entries.push(Entry::new(format!("PIE: no ({})", header::et_to_str(elf.header.e_type)), ReportStyle::Normal));
+        section.findings.push(hardening_finding("CNF-HARD-003", "Not position independent", Severity::Medium,
+            "The executable is loaded at a fixed address, which defeats ASLR for its code; build with -fPIE -pie".into(), segment(&[program_header::PT_LOAD])));
+    }
+
+    // NX: without PT_GNU_STACK the kernel and loader assume an executable stack
+    let stack = elf.program_headers.iter().find(|ph| ph.p_type == program_header::PT_GNU_STACK);
+    match stack {
+        Some(ph) if ph.p_flags & program_header::PF_X == 0 => {
+            summary.push("NX");
+            section.entries.push(Entry::new("NX: yes (PT_GNU_STACK is RW)", ReportStyle::Normal));
+        }
+        _ => {
+            summary.push("executable stack");
+            let why = if stack.is_some() { "PT_GNU_STACK is executable" } else { "no PT_GNU_STACK, the stack defaults to executable" };
+            section.entries.push(Entry::new(format!("NX: no ({})", why), ReportStyle::Normal));
+            section.findings.push(hardening_finding("CNF-HARD-004", "Executable stack", Severity::High,
+                format!("{}; anything written to the stack can be run as code. Link with -Wl,-z,noexecstack", why), segment(&[program_header::PT_GNU_STACK])));
+        }
+    }
+
+    // Stack canary and FORTIFY_SOURCE both show up as calls into libc's checking functions
+    let imports = imported_symbols(elf);
+    let mut canary = imports.iter().any(|s| s == "__stack_chk_fail" || s == "__stack_chk_guard");
+    // Static binaries and libc itself define __stack_chk_fail rather than import it
+    canary |= elf.syms.iter().any(|sym| elf.strtab.get_at(sym.st_name) == Some("__stack_chk_fail"))
+        || elf.dynsyms.iter().any(|sym| elf.dynstrtab.get_at(sym.st_name) == Some("__stack_chk_fail"));
+    if canary {
+        summary.push("canary");
+        section.entries.push(Entry::new("Stack canary: yes (__stack_chk_fail)", ReportStyle::Normal));
+    }
+    else {
+        summary.push("no canary");
+        section.entries.push(Entry::new("Stack canary: no", ReportStyle::Normal));
+        section.findings.push(hardening_finding("CNF-HARD-005", "No stack canary", Severity::Low,
+            "__stack_chk_fail is never referenced, so stack buffer overflows go undetected; build with -fstack-protector-strong".into(), segment(&[program_header::PT_DYNAMIC])));
+    }
+
+    let fortified: Vec<&String> = imports.iter()
+        .filter(|s| s.starts_with("__") && s.ends_with("_chk") && s.as_str() != "__stack_chk_fail")
+        .collect();
+    let unfortified: Vec<&String> = imports.iter().filter(|s| FORTIFIABLE.contains(&s.as_str())).collect();
+    let mut fortify = match (fortified.is_empty(), unfortified.is_empty()) {
+        (false, _) => {
+            summary.push("FORTIFY");
+            Entry::new(format!("FORTIFY_SOURCE: yes ({} checked, {} unchecked functions)", fortified.len(), unfortified.len()), ReportStyle::Normal)
+        }
+        (true, false) => {
+            summary.push("no FORTIFY");
+            section.findings.push(hardening_finding("CNF-HARD-006", "FORTIFY_SOURCE not used", Severity::Low,
+                format!("{} fortifiable libc functions are called without their __*_chk variants; build with -O2 -D_FORTIFY_SOURCE=3", unfortified.len()), segment(&[program_header::PT_DYNAMIC])));
+            Entry::new(format!("FORTIFY_SOURCE: no ({} unchecked functions)", unfortified.len()), ReportStyle::Normal)
+        }
+        (true, true) => Entry::new("FORTIFY_SOURCE: n/a (no fortifiable functions imported)", ReportStyle::Normal),
+    };
+    for name in fortified.iter() {
+        fortify.push(Entry::new(format!("checked: {}", name), ReportStyle::Detailed));
+    }
+    for name in unfortified.iter() {
+        fortify.push(Entry::new(format!("unchecked: {}", name), ReportStyle::Detailed));
+    }
+    section.entries.push(fortify);
+
+    // Intel CET is only defined for x86
+    if matches!(elf.header.e_machine, header::EM_X86_64 | header::EM_386) {
+        let features = x86_feature_1_and(elf, bytes);
+        let ibt = features & GNU_PROPERTY_X86_FEATURE_1_IBT != 0;
+        let shstk = features & GNU_PROPERTY_X86_FEATURE_1_SHSTK != 0;
+        section.entries.push(Entry::new(format!("CET: IBT {}, SHSTK {}", yes_no(ibt), yes_no(shstk)), ReportStyle::Normal));
+        match (ibt, shstk) {
+            (true, true) => summary.push("CET"),
+            _ => {
+                summary.push(if ibt || shstk { "partial CET" } else { "no CET" });
+                let missing: Vec<&str> = [(ibt, "IBT"), (shstk, "SHSTK")].iter().filter(|(on, _)| !on).map(|(_, n)| *n).collect();
+                section.findings.push(hardening_finding("CNF-HARD-007", "No Intel CET", Severity::Low,
+                    format!("{} not marked in .note.gnu.property, so indirect branches/returns are not hardware checked; build with -fcf-protection=full", missing.join(" and ")),
+                    segment(&[program_header::PT_GNU_PROPERTY, program_header::PT_NOTE])));
+            }
+        }
+    }
+
+    section.summary = Some(summary.join(", "));
+    section
+}
+
+/// Findings point at the header field or load config / debug / certificate directory they are about.
+pub fn pe_hardening(path: &Path, pe: &PE) -> Section {
+    let mut section = Section::new(TITLE);
+    let mut summary = Vec::new();
+
+    let Some(optional_header) = pe.header.optional_header.as_ref() else {
+        return Section::unavailable(TITLE, "PE has no optional header (object file?)");
+    };
+    let dll_characteristics = optional_header.windows_fields.dll_characteristics;
+    let has = |flag: u16| dll_characteristics & flag != 0;
+    let load_config = pe.load_config_data.as_ref().map(|l| &l.directory);
+    let guard_flags = load_config.and_then(|l| l.guard_flags).unwrap_or(0);
+    let machine = pe.header.coff_header.machine;
+    let header_field = |offset: u64| pe_header_location(path, pe, pe.header.dos_header.pe_pointer as u64 + offset);
+    let directory = |index: usize| pe_directory_location(path, pe, index);
+    section.entries.push(Entry::new(format!("DllCharacteristics: 0x{:04x}", dll_characteristics), ReportStyle::Detailed));
+
+    // ASLR needs both the flag and relocations to move the image with
+    let relocs_stripped = pe.header.coff_header.characteristics & goblin::pe::characteristic::IMAGE_FILE_RELOCS_STRIPPED != 0;
+    match (has(dll_characteristic::IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE), relocs_stripped) {
+        (true, false) => {
+            summary.push("ASLR");
+            section.entries.push(Entry::new("ASLR: yes (DYNAMIC_BASE)", ReportStyle::Normal));
+        }
+        (dynamic_base, _) => {
+            summary.push("no ASLR");
+            let why = if dynamic_base { "DYNAMIC_BASE is set but relocations were stripped" } else { "DYNAMIC_BASE is not set" };
+            section.entries.push(Entry::new(format!("ASLR: no ({})", why), ReportStyle::Normal));
+            section.findings.push(hardening_finding("CNF-HARD-003", "No ASLR", Severity::Medium,
+                format!("{}, so the image is loaded at a fixed address; link with /DYNAMICBASE", why),
+                header_field(if dynamic_base { PE_COFF_CHARACTERISTICS } else { PE_DLL_CHARACTERISTICS })));
+        }
+    }
+
+    // Only PE32+ images can use a 64-bit address space for ASLR
+    if pe.is_64 {
+        if has(dll_characteristic::IMAGE_DLLCHARACTERISTICS_HIGH_ENTROPY_VA) {
+            summary.push("HighEntropyVA");
+            section.entries.push(Entry::new("HighEntropyVA: yes", ReportStyle::Normal));
+        }
+        else {
+            summary.push("no HighEntropyVA");
+            section.entries.push(Entry::new("HighEntropyVA: no", ReportStyle::Normal));
+            section.findings.push(hardening_finding("CNF-HARD-101", "No high-entropy ASLR", Severity::Low,
+                "HIGH_ENTROPY_VA is not set, so ASLR only uses a 32-bit range of addresses; link with /HIGHENTROPYVA".into(), header_field(PE_DLL_CHARACTERISTICS)));
+        }
+    }
+    else {
+        section.entries.push(Entry::new("HighEntropyVA: n/a (32-bit image)", ReportStyle::Normal));
+    }
+
+    if has(dll_characteristic::IMAGE_DLLCHARACTERISTICS_NX_COMPAT) {
+        summary.push("DEP");
+        section.entries.push(Entry::new("DEP: yes (NX_COMPAT)", ReportStyle::Normal));
+    }
+    else {
+        summary.push("no DEP");
+        section.entries.push(Entry::new("DEP: no", ReportStyle::Normal));
+        section.findings.push(hardening_finding("CNF-HARD-004", "No DEP", Severity::High,
+            "NX_COMPAT is not set, so data pages such as the stack and heap can be run as code; link with /NXCOMPAT".into(), header_field(PE_DLL_CHARACTERISTICS)));
+    }
+
+    // CFG needs the header flag and the compiler's instrumentation recorded in the load config
+    let cfg_flag = has(dll_characteristic::IMAGE_DLLCHARACTERISTICS_GUARD_CF);
+    let cfg_instrumented = guard_flags & load_config::IMAGE_GUARD_CF_INSTRUMENTED != 0;
+    if cfg_flag && cfg_instrumented {
+        summary.push("CFG");
+        let functions = load_config.and_then(|l| l.guard_cf_function_count).unwrap_or(0);
+        section.entries.push(Entry::new(format!("CFG: yes ({} valid call targets)", functions), ReportStyle::Normal));
+    }
+    else {
+        summary.push("no CFG");
+        let why = if cfg_flag { "GUARD_CF is set but the load config does not record CF instrumentation" } else { "GUARD_CF is not set" };
+        section.entries.push(Entry::new(format!("CFG: no ({})", why), ReportStyle::Normal));
+        section.findings.push(hardening_finding("CNF-HARD-102", "No Control Flow Guard", Severity::Low,
+            format!("{}, so indirect calls are not checked; build with /guard:cf", why),
+            if cfg_flag { directory(PE_LOAD_CONFIG_TABLE) } else { header_field(PE_DLL_CHARACTERISTICS) }));
+    }
+
+    // SafeSEH only exists for 32-bit x86; x64 and ARM use table-based unwinding
+    if machine == goblin::pe::header::COFF_MACHINE_X86 {
+        let handlers = load_config.and_then(|l| l.se_handler_count).unwrap_or(0);
+        if has(dll_characteristic::IMAGE_DLLCHARACTERISTICS_NO_SEH) {
+            summary.push("no SEH");
+            section.entries.push(Entry::new("SafeSEH: n/a (NO_SEH, the image has no exception handlers)", ReportStyle::Normal));
+        }
+        else if load_config.and_then(|l| l.se_handler_table).unwrap_or(0) != 0 {
+            summary.push("SafeSEH");
+            section.entries.push(Entry::new(format!("SafeSEH: yes ({} registered handlers)", handlers), ReportStyle::Normal));
+        }
+        else {
+            summary.push("no SafeSEH");
+            section.entries.push(Entry::new("SafeSEH: no", ReportStyle::Normal));
+            section.findings.push(hardening_finding("CNF-HARD-103", "No SafeSEH", Severity::Medium,
+                "No SEH handler table in the load config, so an overwritten exception handler record is called unchecked; link with /SAFESEH".into(), directory(PE_LOAD_CONFIG_TABLE)));
+        }
+    }
+    else {
+        section.entries.push(Entry::new("SafeSEH: n/a (only applies to 32-bit x86)", ReportStyle::Normal));
+    }
+
+    // /GS: the compiler stores the cookie's address in the load config
+    let cookie = load_config.and_then(|l| l.security_cookie).unwrap_or(0);
+    if cookie != 0 && guard_flags & load_config::IMAGE_GUARD_SECURITY_COOKIE_UNUSED == 0 {
+        summary.push("/GS");
+        section.entries.push(Entry::new(format!("/GS stack cookie: yes (cookie at VA 0x{:x})", cookie), ReportStyle::Normal));
+    }
+    else {
+        summary.push("no /GS");
+        section.entries.push(Entry::new("/GS stack cookie: no", ReportStyle::Normal));
+        section.findings.push(hardening_finding("CNF-HARD-005", "No /GS stack cookie", Severity::Low,
+            "The load config has no security cookie, so stack buffer overflows go undetected; build with /GS".into(), directory(PE_LOAD_CONFIG_TABLE)));
+    }
+
+    // CET shadow stacks are opted into through the extended DLL characteristics debug entry
+    if machine == goblin::pe::header::COFF_MACHINE_X86_64 || machine == goblin::pe::header::COFF_MACHINE_X86 {
+        let characteristics_ex = pe.debug_data.as_ref()
+            .and_then(|d| d.ex_dll_characteristics_info.as_ref())
+            .map(|i| i.characteristics_ex)
+            .unwrap_or(0);
+        if characteristics_ex & goblin::pe::debug::IMAGE_DLLCHARACTERISTICS_EX_CET_COMPAT != 0 {
+            summary.push("CET");
+            section.entries.push(Entry::new("CET: SHSTK yes (CET_COMPAT)", ReportStyle::Normal));
+        }
+        else {
+            summary.push("no CET");
+            section.entries.push(Entry::new("CET: SHSTK no", ReportStyle::Normal));
+            section.findings.push(hardening_finding("CNF-HARD-007", "No Intel CET", Severity::Low,
+                "CET_COMPAT is not set, so return addresses are not checked against a shadow stack; link with /CETCOMPAT".into(), directory(PE_DEBUG_TABLE)));
+        }
+    }
+
+    // Only presence: the signature itself is not verified
+    if pe.certificates.is_empty() {
+        summary.push("unsigned");
+        section.entries.push(Entry::new("Authenticode: not signed", ReportStyle::Normal));
+        section.findings.push(hardening_finding("CNF-HARD-104", "Not Authenticode signed", Severity::Low,
+            "The image has no certificate table, so its origin and integrity cannot be checked".into(), directory(PE_CERTIFICATE_TABLE)));
+    }
+    else {
+        summary.push("signed");
+        section.entries.push(Entry::new(format!("Authenticode: {} certificates present (signature not verified)", pe.certificates.len()), ReportStyle::Normal));
+    }
+
+    section.summary = Some(summary.join(", "));
+    section
+}
+
+/// Undefined dynamic symbols, ie what the binary imports.
+fn imported_symbols(elf: &Elf) -> Vec<String> {
+    let mut names: Vec<String> = elf.dynsyms.iter()
+        .filter(|sym| sym.st_shndx == goblin::elf::section_header::SHN_UNDEF as usize)
+        .filter_map(|sym| elf.dynstrtab.get_at(sym.st_name))
+        .filter(|name| !name.is_empty())
+        .map(|name| name.to_string())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+/// The GNU_PROPERTY_X86_FEATURE_1_AND bits from NT_GNU_PROPERTY_TYPE_0, or 0 if there are none.
+/// Looks at the .note.gnu.property section first and falls back to PT_NOTE segments for stripped binaries.
+fn x86_feature_1_and(elf: &Elf, bytes: &[u8]) -> u32 {
+    let notes = elf.iter_note_sections(bytes, Some(".note.gnu.property"))
+        .or_else(|| elf.iter_note_headers(bytes));
+    let Some(notes) = notes else {
+        return 0;
+    };
+    let align = if elf.is_64 { 8 } else { 4 };
+    for note in notes.flatten() {
+        if note.n_type != note::NT_GNU_PROPERTY_TYPE_0 || note.name != "GNU" {
+            continue;
+        }
+        // An array of { u32 pr_type; u32 pr_datasz; u8 pr_data[pr_datasz]; } padded to `align`
+        let desc = note.desc;
+        let mut i = 0;
+        while i + 8 <= desc.len() {
+            let pr_type = read_u32(&desc[i..], elf.little_endian);
+            let pr_datasz = read_u32(&desc[i + 4..], elf.little_endian) as usize;
+            let data = i + 8;
+            if pr_type == GNU_PROPERTY_X86_FEATURE_1_AND && pr_datasz >= 4 && data + 4 <= desc.len() {
+                return read_u32(&desc[data..], elf.little_endian);
+            }
+            i = data + pr_datasz.div_ceil(align) * align;
+        }
+    }
+    0
+}
+
+fn read_u32(bytes: &[u8], little_endian: bool) -> u32 {
+    let b = [bytes[0], bytes[1], bytes[2], bytes[3]];
+    if little_endian { u32::from_le_bytes(b) } else { u32::from_be_bytes(b) }
+}
+
+fn yes_no(value: bool) -> &'static str {
+    if value { "yes" } else { "no" }
+}
+
+/// Where an ELF finding points: the first segment of `p_types` the binary has, or else its program header table.
+fn segment_location(path: &Path, elf: &Elf, p_types: &[u32]) -> Location {
+    let segment = p_types.iter().find_map(|p_type| elf.program_headers.iter().find(|ph| ph.p_type == *p_type));
+    let (offset, address) = match segment {
+        Some(ph) => (ph.p_offset, Some(ph.p_vaddr)),
+        None => (elf.header.e_phoff, elf.program_headers.iter().find(|ph| ph.p_type == program_header::PT_PHDR).map(|ph| ph.p_vaddr)),
+    };
+    Location { file: path.to_path_buf(), offset: Some(offset), address, ..Default::default() }
+}
+
+/// PE headers are mapped at the image base, so their RVA is their file offset.
+fn pe_header_location(path: &Path, pe: &PE, offset: u64) -> Location {
+    Location { file: path.to_path_buf(), offset: Some(offset), address: Some(pe.image_base.wrapping_add(offset)), ..Default::default() }
+}
+
+/// The structure data directory `index` points at, or the directory entry itself when it is empty.
+fn pe_directory_location(path: &Path, pe: &PE, index: usize) -> Location {
+    let entry = pe.header.dos_header.pe_pointer as u64 + PE_OPTIONAL_HEADER + if pe.is_64 { 112 } else { 96 } + 8 * index as u64;
+    let Some(optional_header) = pe.header.optional_header.as_ref() else {
+        return pe_header_location(path, pe, entry);
+    };
+    match optional_header.data_directories.data_directories.get(index).copied().flatten() {
+        // The certificate table is the one directory which holds a file offset rather than an RVA
+        Some((_, dd)) if index == PE_CERTIFICATE_TABLE => Location { file: path.to_path_buf(), offset: Some(dd.virtual_address as u64), ..Default::default() },
+        Some((_, dd)) => {
+            let offset = goblin::pe::utils::find_offset(dd.virtual_address as usize, &pe.sections,
+                optional_header.windows_fields.file_alignment, &goblin::pe::options::ParseOptions::default());
+            Location {
+                file: path.to_path_buf(),
+                offset: offset.map(|o| o as u64),
+                address: Some(pe.image_base.wrapping_add(dd.virtual_address as u64)),
+                ..Default::default()
+            }
+        }
+        None => pe_header_location(path, pe, entry),
+    }
+}
+
+fn hardening_finding(rule_id: &str, title: &str, severity: Severity, message: String, location: Location) -> Finding {
+    Finding {
+        rule_id: rule_id.into(),
+        title: title.into(),
+        message,
+        severity,
+        category: Category::Hardening,
+        location: Some(location),
+        detail: if severity >= Severity::Medium { ReportStyle::Terse } else { ReportStyle::Normal },
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::*;
+    use crate::analysis::test_fixtures::FixtureDir;
+
+    /// Copies argv[1] into a stack buffer, so canaries and FORTIFY_SOURCE have something to guard.
+    const COPY_ARGUMENT: &str = "#include <string.h>\nint main(int argc, char **argv) {\n    char buf[16];\n    strcpy(buf, argv[argc - 1]);\n    return buf[0];\n}\n";
+
+    fn hardening(binary: &Path) -> Section {
+        let bytes = std::fs::read(binary).unwrap();
+        let elf = Elf::parse(&bytes).unwrap();
+        elf_hardening(binary, &elf, &bytes)
+    }
+
+    fn has_entry(section: &Section, text: &str) -> bool {
+        section.entries.iter().any(|e| e.text == text)
+    }
+
+    fn rule_ids(section: &Section) -> Vec<&str> {
+        section.findings.iter().map(|f| f.rule_id.as_str()).collect()
+    }
+
+    /// The file offset and address of the program header a finding should point at.
+    fn segment_of(binary: &Path, p_type: u32) -> (Option<u64>, Option<u64>) {
+        let bytes = std::fs::read(binary).unwrap();
+        let elf = Elf::parse(&bytes).unwrap();
+        let ph = elf.program_headers.iter().find(|ph| ph.p_type == p_type).unwrap();
+        (Some(ph.p_offset), Some(ph.p_vaddr))
+    }
+
+    fn location_of(section: &Section, rule_id: &str) -> (Option<u64>, Option<u64>) {
+        let finding = section.findings.iter().find(|f| f.rule_id == rule_id).unwrap_or_else(|| panic!("no {}: {:?}", rule_id, section.findings));
+        let location = finding.location.as_ref().unwrap();
+        (location.offset, location.address)
+    }
+
+    #[test]
+    fn hardened_build_has_no_findings_but_cet() {
+        let fixtures = FixtureDir::new("hardened");
+        let binary = fixtures.compile("app", COPY_ARGUMENT,
+            &["-O2", "-D_FORTIFY_SOURCE=2", "-fstack-protector-all", "-fPIE", "-pie", "-Wl,-z,relro,-z,now", "-Wl,-z,noexecstack"]);
+        let section = hardening(&binary);
+        for entry in ["RELRO: full (PT_GNU_RELRO + BIND_NOW)", "BIND_NOW: yes", "PIE: yes", "NX: yes (PT_GNU_STACK is RW)", "Stack canary: yes (__stack_chk_fail)"] {
+            assert!(has_entry(&section, entry), "{}: {:?}", entry, section.entries);
+        }
+        assert!(section.entries.iter().any(|e| e.text.starts_with("FORTIFY_SOURCE: yes")), "{:?}", section.entries);
+        assert!(rule_ids(&section).iter().all(|id| *id == "CNF-HARD-007"), "{:?}", section.findings);
+    }
+
+    #[test]
+    fn unhardened_build_gets_a_finding_per_missing_mitigation() {
+        let fixtures = FixtureDir::new("unhardened");
+        let binary = fixtures.compile("app", COPY_ARGUMENT,
+            &["-O0", "-fno-stack-protector", "-fno-pie", "-no-pie", "-Wl,-z,norelro", "-Wl,-z,lazy", "-Wl,-z,execstack"]);
+        let section = hardening(&binary);
+        for entry in ["RELRO: none", "PIE: no (EXEC)", "NX: no (PT_GNU_STACK is executable)", "Stack canary: no", "FORTIFY_SOURCE: no (1 unchecked functions)"] {
+            assert!(has_entry(&section, entry), "{}: {:?}", entry, section.entries);
+        }
+        let mut ids = rule_ids(&section);
+        ids.retain(|id| *id != "CNF-HARD-007");
+        assert_eq!(ids, ["CNF-HARD-001", "CNF-HARD-003", "CNF-HARD-004", "CNF-HARD-005", "CNF-HARD-006"]);
+        assert_eq!(location_of(&section, "CNF-HARD-004"), segment_of(&binary, program_header::PT_GNU_STACK));
+        assert_eq!(location_of(&section, "CNF-HARD-001"), segment_of(&binary, program_header::PT_DYNAMIC));
+        assert_eq!(location_of(&section, "CNF-HARD-003"), segment_of(&binary, program_header::PT_LOAD));
+
+        let partial = fixtures.compile("partial", COPY_ARGUMENT, &["-Wl,-z,relro", "-Wl,-z,lazy"]);
+        let section = hardening(&partial);
+        assert!(has_entry(&section, "RELRO: partial (PT_GNU_RELRO without BIND_NOW)"), "{:?}", section.entries);
+        assert_eq!(location_of(&section, "CNF-HARD-002"), segment_of(&partial, program_header::PT_GNU_RELRO));
+    }
+
+    #[test]
+    fn shared_library_with_an_interpreter_is_not_a_pie() {
+        let fixtures = FixtureDir::new("soname-interp");
+        // Like libc.so.6: a library which can also be run, so it has PT_INTERP but no DF_1_PIE.
+        // ld only writes one for -shared when there is an .interp section, which is how glibc does it
+        let source = "const char interp[] __attribute__((section(\".interp\"))) = \"/lib/ld-cnf.so.1\";\nint cnf_hard(void) { return 1; }\n";
+        let library = fixtures.compile("libcnfhard.so", source, &["-shared", "-fPIC", "-Wl,-soname,libcnfhard.so"]);
+        let bytes = std::fs::read(&library).unwrap();
+        assert_eq!(Elf::parse(&bytes).unwrap().interpreter, Some("/lib/ld-cnf.so.1"));
+        let section = hardening(&library);
+        assert!(has_entry(&section, "PIE: n/a (PIC shared object, always position independent)"), "{:?}", section.entries);
+        assert!(!rule_ids(&section).contains(&"CNF-HARD-003"), "{:?}", section.findings);
+    }
+
+    #[cfg(target_arch = "x86_64")]
+    #[test]
+    fn cet_is_read_from_the_gnu_property_note() {
+        let fixtures = FixtureDir::new("cet");
+        let main = "int main(void) { return 0; }\n";
+        let without = hardening(&fixtures.compile("without", main, &["-fcf-protection=none"]));
+        assert!(has_entry(&without, "CET: IBT no, SHSTK no"), "{:?}", without.entries);
+        assert!(rule_ids(&without).contains(&"CNF-HARD-007"), "{:?}", without.findings);
+
+        // Every object linked in has to be marked, including the C runtime's start files
+        let with = fixtures.compile("with", main, &["-fcf-protection=full", "-Wl,-z,ibt,-z,shstk"]);
+        let section = hardening(&with);
+        assert!(has_entry(&section, "CET: IBT yes, SHSTK yes"), "{:?}", section.entries);
+        assert!(!rule_ids(&section).contains(&"CNF-HARD-007"), "{:?}", section.findings);
+    }
+}
+
+#[cfg(test)]
+mod pe_tests {
+    use goblin::pe::characteristic::{IMAGE_FILE_EXECUTABLE_IMAGE, IMAGE_FILE_RELOCS_STRIPPED};
+    use goblin::pe::dll_characteristic::*;
+    use goblin::pe::header::{COFF_MACHINE_X86, COFF_MACHINE_X86_64};
+
+    use super::*;
+
+    const IMAGE_BASE: u64 = 0x1_4000_0000;
+    const E_LFANEW: u64 = 0x40;
+
+    /// A PE image with one empty section and no data directories: nothing but headers to look at.
+    fn minimal_pe(machine: u16, characteristics: u16, dll_characteristics: u16) -> Vec<u8> {
+        let is_64 = machine == COFF_MACHINE_X86_64;
+        let mut image = vec![0u8; 0x400];
+        let mut put = |at: u64, bytes: &[u8]| image[at as usize..at as usize + bytes.len()].copy_from_slice(bytes);
+        put(0, b"MZ");
+        put(0x3c, &(E_LFANEW as u32).to_le_bytes());
+        put(E_LFANEW, b"PE\0\0");
+        let coff = E_LFANEW + 4;
+        let optional_size: u16 = if is_64 { 240 } else { 224 };
+        put(coff, &machine.to_le_bytes());
+        put(coff + 2, &1u16.to_le_bytes());
+        put(coff + 16, &optional_size.to_le_bytes());
+        put(coff + 18, &characteristics.to_le_bytes());
+        let optional = E_LFANEW + PE_OPTIONAL_HEADER;
+        put(optional, &(if is_64 { 0x20bu16 } else { 0x10b }).to_le_bytes());
+        put(optional + 16, &0x1000u32.to_le_bytes());
+        if is_64 {
+            put(optional + 24, &IMAGE_BASE.to_le_bytes());
+        } else {
+            put(optional + 28, &(IMAGE_BASE as u32).to_le_bytes());
+        }
+        put(optional + 32, &0x1000u32.to_le_bytes());
+        put(optional + 36, &0x200u32.to_le_bytes());
+        put(optional + 48, &6u16.to_le_bytes());
+        put(optional + 56, &0x2000u32.to_le_bytes());
+        put(optional + 60, &0x200u32.to_le_bytes());
+        put(optional + 68, &3u16.to_le_bytes());
+        put(optional + 70, &dll_characteristics.to_le_bytes());
+        put(optional + if is_64 { 108 } else { 92 }, &16u32.to_le_bytes());
+        let section = optional + optional_size as u64;
+        put(section, b".text\0\0\0");
+        for (field, value) in [(8, 0x200u32), (12, 0x1000), (16, 0x200), (20, 0x200), (36, 0x6000_0020)] {
+            put(section + field, &value.to_le_bytes());
+        }
+        image
+    }
+
+    fn hardening(image: &[u8]) -> Section {
+        pe_hardening(Path::new("app.exe"), &PE::parse(image).unwrap())
+    }
+
+    fn findings(section: &Section) -> Vec<(&str, Option<u64>, Option<u64>)> {
+        section.findings.iter().map(|f| {
+            let location = f.location.as_ref().unwrap();
+            (f.rule_id.as_str(), location.offset, location.address)
+        }).collect()
+    }
+
+    #[test]
+    fn dll_characteristics_set_the_verdicts() {
+        let flags = IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE | IMAGE_DLLCHARACTERISTICS_HIGH_ENTROPY_VA
+            | IMAGE_DLLCHARACTERISTICS_NX_COMPAT | IMAGE_DLLCHARACTERISTICS_GUARD_CF;
+        let section = hardening(&minimal_pe(COFF_MACHINE_X86_64, IMAGE_FILE_EXECUTABLE_IMAGE, flags));
+        assert_eq!(section.summary.as_deref(), Some("ASLR, HighEntropyVA, DEP, no CFG, no /GS, no CET, unsigned"));
+        assert!(section.entries.iter().any(|e| e.text == "CFG: no (GUARD_CF is set but the load config does not record CF instrumentation)"), "{:?}", section.entries);
+        // Empty data directories point at their entry in the optional header
+        let directory = |rule_id, index: usize| {
+            let entry = E_LFANEW + PE_OPTIONAL_HEADER + 112 + 8 * index as u64;
+            (rule_id, Some(entry), Some(IMAGE_BASE + entry))
+        };
+        assert_eq!(findings(&section), [
+            directory("CNF-HARD-102", PE_LOAD_CONFIG_TABLE),
+            directory("CNF-HARD-005", PE_LOAD_CONFIG_TABLE),
+            directory("CNF-HARD-007", PE_DEBUG_TABLE),
+            directory("CNF-HARD-104", PE_CERTIFICATE_TABLE),
+        ]);
+    }
+
+    #[test]
+    fn missing_dll_characteristics_point_at_the_header_field() {
+        let section = hardening(&minimal_pe(COFF_MACHINE_X86_64, IMAGE_FILE_EXECUTABLE_IMAGE, 0));
+        assert_eq!(section.summary.as_deref(), Some("no ASLR, no HighEntropyVA, no DEP, no CFG, no /GS, no CET, unsigned"));
+        let field = E_LFANEW + PE_DLL_CHARACTERISTICS;
+        let at_field: Vec<&str> = findings(&section).into_iter()
+            .filter(|(_, offset, address)| *offset == Some(field) && *address == Some(IMAGE_BASE + field))
+            .map(|(id, _, _)| id)
+            .collect();
+        assert_eq!(at_field, ["CNF-HARD-003", "CNF-HARD-101", "CNF-HARD-004", "CNF-HARD-102"]);
+
+        // DYNAMIC_BASE can't move an image without relocations, which the COFF header says are gone
+        let stripped = hardening(&minimal_pe(COFF_MACHINE_X86_64, IMAGE_FILE_EXECUTABLE_IMAGE | IMAGE_FILE_RELOCS_STRIPPED, IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE));
+        assert!(stripped.entries.iter().any(|e| e.text == "ASLR: no (DYNAMIC_BASE is set but relocations were stripped)"), "{:?}", stripped.entries);
+        let field = E_LFANEW + PE_COFF_CHARACTERISTICS;
+        assert_eq!(findings(&stripped)[0], ("CNF-HARD-003", Some(field), Some(IMAGE_BASE + field)));
+    }
+
+    #[test]
+    fn only_32_bit_x86_images_need_safeseh() {
+        let section = hardening(&minimal_pe(COFF_MACHINE_X86, IMAGE_FILE_EXECUTABLE_IMAGE, IMAGE_DLLCHARACTERISTICS_DYNAMIC_BASE | IMAGE_DLLCHARACTERISTICS_NX_COMPAT));
+        assert_eq!(section.summary.as_deref(), Some("ASLR, DEP, no CFG, no SafeSEH, no /GS, no CET, unsigned"));
+        assert!(section.entries.iter().any(|e| e.text == "HighEntropyVA: n/a (32-bit image)"), "{:?}", section.entries);
+        let load_config = E_LFANEW + PE_OPTIONAL_HEADER + 96 + 8 * PE_LOAD_CONFIG_TABLE as u64;
+        assert!(findings(&section).contains(&("CNF-HARD-103", Some(load_config), Some((IMAGE_BASE as u32) as u64 + load_config))), "{:?}", findings(&section));
+
+        let no_seh = hardening(&minimal_pe(COFF_MACHINE_X86, IMAGE_FILE_EXECUTABLE_IMAGE, IMAGE_DLLCHARACTERISTICS_NO_SEH));
+        assert!(no_seh.entries.iter().any(|e| e.text == "SafeSEH: n/a (NO_SEH, the image has no exception handlers)"), "{:?}", no_seh.entries);
+    }
+}
@@ -0,0 +1,197 @@
+
+// Classifies a binary's imports into risk categories (unsafe string handling, command execution, ...).
+// The built-in catalogue can be extended or overridden with --import-catalogue, a TOML file with one
+// table per category mapping function names to a short risk note:
+//
+//   [command-execution]
+//   my_run_helper = "Wraps system()"
+//
+//   [unsafe-string]
+//   strcpy = ""          # an empty note removes the function from the catalogue
+//
+// Naming a function under another category moves it there.
+
+use std::collections::BTreeMap;
+
+use crate::args::ReportStyle;
+use crate::report::{Entry, Section, SectionData};
+
+const TITLE: &str = "Dangerous Imports";
+
+/// Categories in the order they are reported; user categories follow in name order.
+const CATEGORIES: &[&str] = &["unsafe-string", "command-execution", "dynamic-loading", "privilege", "network", "crypto"];
+
+/// (function, category, note)
+const BUILTIN: &[(&str, &str, &str)] = &[
+    ("gets", "unsafe-string", "Reads a line with no length limit; always overflowable"),
+    ("strcpy", "unsafe-string", "No bounds check on the destination"),
+    ("strcat", "unsafe-string", "No bounds check on the destination"),
+    ("stpcpy", "unsafe-string", "No bounds check on the destination"),
+    ("sprintf", "unsafe-string", "No bounds check on the destination; use snprintf"),
+    ("vsprintf", "unsafe-string", "No bounds check on the destination; use vsnprintf"),
+    ("wcscpy", "unsafe-string", "No bounds check on the destination"),
+    ("wcscat", "unsafe-string", "No bounds check on the destination"),
+    ("strncpy", "unsafe-string", "Does not NUL-terminate on truncation"),
+    ("strtok", "unsafe-string", "Hidden static state, not reentrant"),
+    ("scanf", "unsafe-string", "%s without a width overflows"),
+    ("sscanf", "unsafe-string", "%s without a width overflows"),
+    ("fscanf", "unsafe-string", "%s without a width overflows"),
+    ("lstrcpyA", "unsafe-string", "No bounds check on the destination"),
+    ("lstrcpyW", "unsafe-string", "No bounds check on the destination"),
+    ("lstrcatA", "unsafe-string", "No bounds check on the destination"),
+    ("lstrcatW", "unsafe-string", "No bounds check on the destination"),
+    ("wsprintfA", "unsafe-string", "No bounds check on the destination"),
+    ("wsprintfW", "unsafe-string", "No bounds check on the destination"),
+    ("system", "command-execution", "Runs a shell command line; injectable if any part is attacker controlled"),
+    ("popen", "command-execution", "Runs a shell command line; injectable if any part is attacker controlled"),
+    ("execl", "command-execution", "Replaces the process with another program"),
+    ("execlp", "command-execution", "Replaces the process with another program, searched in PATH"),
+    ("execle", "command-execution", "Replaces the process with another program"),
+    ("execv", "command-execution", "Replaces the process with another program"),
+    ("execvp", "command-execution", "Replaces the process with another program, searched in PATH"),
+    ("execve", "command-execution", "Replaces the process with another program"),
+    ("execvpe", "command-execution", "Replaces the process with another program, searched in PATH"),
+    ("posix_spawn", "command-execution", "Starts another program"),
+    ("posix_spawnp", "command-execution", "Starts another program, searched in PATH"),
+    ("WinExec", "command-execution", "Runs a command line"),
+    ("CreateProcessA", "command-execution", "Starts another program; unquoted paths with spaces are ambiguous"),
+    ("CreateProcessW", "command-execution", "Starts another program; unquoted paths with spaces are ambiguous"),
+    ("ShellExecuteA", "command-execution", "Opens a file or runs a program through the shell"),
+    ("ShellExecuteW", "command-execution", "Opens a file or runs a program through the shell"),
+    ("ShellExecuteExW", "command-execution", "Opens a file or runs a program through the shell"),
+    ("dlopen", "dynamic-loading", "Loads code at run time; a relative or writable path allows hijacking"),
+    ("dlmopen", "dynamic-loading", "Loads code at run time; a relative or writable path allows hijacking"),
+    ("dlsym", "dynamic-loading", "Resolves functions at run time, hiding them from the import table"),
+    ("LoadLibraryA", "dynamic-loading", "Loads a DLL at run time; subject to DLL search order hijacking"),
+    ("LoadLibraryW", "dynamic-loading", "Loads a DLL at run time; subject to DLL search order hijacking"),
+    ("LoadLibraryExA", "dynamic-loading", "Loads a DLL at run time; subject to DLL search order hijacking"),
+    ("LoadLibraryExW", "dynamic-loading", "Loads a DLL at run time; subject to DLL search order hijacking"),
+    ("GetProcAddress", "dynamic-loading", "Resolves functions at run time, hiding them from the import table"),
+    ("setuid", "privilege", "Changes user id; unchecked failure keeps privileges"),
+    ("seteuid", "privilege", "Changes effective user id; unchecked failure keeps privileges"),
+    ("setreuid", "privilege", "Changes user ids; unchecked failure keeps privileges"),
+    ("setresuid", "privilege", "Changes user ids; unchecked failure keeps privileges"),
+    ("setgid", "privilege", "Changes group id; unchecked failure keeps privileges"),
+    ("setegid", "privilege", "Changes effective group id; unchecked failure keeps privileges"),
+    ("setregid", "privilege", "Changes group ids; unchecked failure keeps privileges"),
+    ("setresgid", "privilege", "Changes group ids; unchecked failure keeps privileges"),
+    ("setgroups", "privilege", "Changes supplementary groups"),
+    ("chroot", "privilege", "Escapable unless followed by chdir(\"/\") and dropping root"),
+    ("capset", "privilege", "Changes process capabilities"),
+    ("AdjustTokenPrivileges", "privilege", "Enables or disables token privileges"),
+    ("ImpersonateLoggedOnUser", "privilege", "Runs as another user"),
+    ("socket", "network", "Opens a network endpoint"),
+    ("connect", "network", "Makes outbound connections"),
+    ("bind", "network", "Listens on a network address"),
+    ("listen", "network", "Accepts inbound connections"),
+    ("accept", "network", "Accepts inbound connections"),
+    ("recv", "network", "Reads untrusted network data"),
+    ("recvfrom", "network", "Reads untrusted network data"),
+    ("getaddrinfo", "network", "Resolves host names"),
+    ("gethostbyname", "network", "Resolves host names; obsolete and not reentrant"),
+    ("WSAStartup", "network", "Initializes Winsock"),
+    ("InternetOpenA", "network", "Makes HTTP/FTP requests"),
+    ("InternetOpenW", "network", "Makes HTTP/FTP requests"),
+    ("URLDownloadToFileA", "network", "Downloads a file"),
+    ("URLDownloadToFileW", "network", "Downloads a file"),
+    ("rand", "crypto", "Not a cryptographic random number generator"),
+    ("srand", "crypto", "Seeds a non-cryptographic generator"),
+    ("random", "crypto", "Not a cryptographic random number generator"),
+    ("crypt", "crypto", "Legacy password hashing"),
+    ("MD5_Init", "crypto", "MD5 is broken for collision resistance"),
+    ("MD5", "crypto", "MD5 is broken for collision resistance"),
+    ("SHA1_Init", "crypto", "SHA-1 is broken for collision resistance"),
+    ("SHA1", "crypto", "SHA-1 is broken for collision resistance"),
+    ("DES_ecb_encrypt", "crypto", "DES and ECB mode are both weak"),
+    ("RC4", "crypto", "RC4 is broken"),
+    ("EVP_md5", "crypto", "MD5 is broken for collision resistance"),
+    ("EVP_sha1", "crypto", "SHA-1 is broken for collision resistance"),
+    ("EVP_des_ecb", "crypto", "DES and ECB mode are both weak"),
+    ("EVP_rc4", "crypto", "RC4 is broken"),
+    ("CryptGenRandom", "crypto", "Deprecated CryptoAPI; use BCryptGenRandom"),
+];
+
+/// Function name -> (category, note).
+#[derive(Debug, Clone, Default)]
+pub struct ImportCatalogue {
+    pub functions: BTreeMap<String, (String, String)>,
+}
+
+impl ImportCatalogue {
+    pub fn builtin() -> ImportCatalogue {
+        let functions = BUILTIN.iter()
+            .map(|(name, category, note)| (name.to_string(), (category.to_string(), note.to_string())))
+            .collect();
+        ImportCatalogue { functions }
+    }
+
+    /// The built-in catalogue with --import-catalogue applied on top, if one was given.
+    pub fn for_args(args: &crate::args::Args) -> crate::DynResult<ImportCatalogue> {
+        let mut catalogue = ImportCatalogue::builtin();
+        if let Some(path) = &args.import_catalogue {
+            let text = std::fs::read_to_string(path)?;
+            let tables: BTreeMap<String, BTreeMap<String, String>> = toml::from_str(&text)
+                .map_err(|e| crate::tracked_err!(format!("{}: {}", path.display(), e)))?;
+            for (category, functions) in tables {
+                for (name, note) in functions {
+                    if note.is_empty() {
+                        catalogue.functions.remove(&name);
+                    }
+                    else {
+                        catalogue.functions.insert(name, (category.clone(), note));
+                    }
+                }
+            }
+        }
+        Ok(catalogue)
+    }
+
+    /// "strcpy@GLIBC_2.2.5" and "strcpy" both look up strcpy.
+    pub fn classify(&self, name: &str) -> Option<(&str, &str)> {
+        let name = name.split('@').next().unwrap_or(name);
+        self.functions.get(name).map(|(category, note)| (category.as_str(), note.as_str()))
+    }
+
+    /// Category -> (name as shown, note) of every import in the catalogue, in import order.
+    pub fn by_category(&self, imports: &[(String, String)]) -> BTreeMap<String, Vec<(String, String)>> {
+        let mut by_category: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
+        for (shown, name) in imports.iter() {
+            if let Some((category, note)) = self.classify(name) {
+                by_category.entry(category.to_string()).or_default().push((shown.clone(), note.to_string()));
+            }
+        }
+        by_category
+    }
+}
+
+/// `imports` are (name as shown, function name) pairs, eg ("KERNEL32.dll!WinExec", "WinExec").
+pub fn dangerous_imports(imports: &[(String, String)], catalogue: &ImportCatalogue) -> Section {
+    let by_category = catalogue.by_category(imports);
+
+    let mut categories: Vec<&String> = by_category.keys().collect();
+    categories.sort_by_key(|c| (CATEGORIES.iter().position(|known| known == c).unwrap_or(CATEGORIES.len()), c.to_string()));
+
+    let mut section = Section::new(TITLE);
+    let total: usize = by_category.values().map(|f| f.len()).sum();
+    section.summary = Some(if total == 0 {
+        format!("None of {} imports are in the catalogue", imports.len())
+    } else {
+        format!("{} of {} imports are risky: {}", total, imports.len(),
+            categories.iter().map(|c| format!("{} {}", by_category[*c].len(), c)).collect::<Vec<_>>().join(", "))
+    });
+    for category in categories.iter() {
+        let functions = &by_category[*category];
+        let names: Vec<&str> = functions.iter().map(|(shown, _)| shown.as_str()).collect();
+        let mut entry = Entry::new(format!("{} ({}): {}", category, functions.len(), names.join(", ")), ReportStyle::Normal);
+        for (shown, note) in functions.iter() {
+            entry.push(Entry::new(format!("{}: {}", shown, note), ReportStyle::Detailed));
+        }
+        section.entries.push(entry);
+    }
+    section.data = Some(SectionData::DangerousImports {
+        categories: by_category.into_iter()
+            .map(|(category, functions)| (category, functions.into_iter().map(|(shown, _)| shown).collect()))
+            .collect(),
+    });
+    section
+}
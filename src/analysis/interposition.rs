@@ -0,0 +1,122 @@
+
+// Symbols defined by more than one object in a process's global scope.
+// ld.so binds every reference to the first definition in scope order, so a library loaded ahead
+// of libc which exports malloc or open silently takes over those calls for the whole process.
+
+use crate::args::ReportStyle;
+use crate::report::{Category, Entry, Finding, InterposedSymbol, Section, SectionData, Severity};
+
+use super::elf_lib_lookup::{DuplicateExport, LinkSimulation};
+
+const TITLE: &str = "Symbol Interposition";
+
+/// libc functions whose replacement changes memory, file, process or privilege handling.
+const SENSITIVE: &[&str] = &[
+    "malloc", "calloc", "realloc", "free", "memalign", "posix_memalign", "aligned_alloc", "valloc", "pvalloc",
+    "open", "open64", "openat", "openat64", "creat", "fopen", "fopen64", "read", "write", "close",
+    "unlink", "rename", "chmod", "chown", "stat", "access",
+    "execve", "execv", "execvp", "system", "popen", "fork", "vfork", "posix_spawn",
+    "dlopen", "dlsym", "getenv", "secure_getenv", "setenv", "putenv",
+    "setuid", "seteuid", "setgid", "setegid", "setresuid", "setresgid", "getuid", "geteuid",
+    "connect", "accept", "send", "recv", "sendto", "recvfrom", "getaddrinfo",
+    "memcpy", "memmove", "memset", "strcpy", "strncpy", "sprintf", "snprintf", "printf",
+    "getrandom", "rand", "random", "time", "gettimeofday", "clock_gettime",
+    "pthread_create", "signal", "sigaction", "mprotect", "mmap", "ptrace",
+];
+
+/// Sonames which are supposed to define the functions above.
+fn is_system_library(soname: &str) -> bool {
+    ["libc.so", "libc.musl", "ld-linux", "ld-musl", "ld64.so", "ld.so", "libpthread.so", "libdl.so", "librt.so", "libm.so"]
+        .iter()
+        .any(|prefix| soname.starts_with(prefix))
+}
+
+/// `binary` is the analyzed file's name, which is how the executable appears in the provider lists.
+pub fn symbol_interposition(binary: &str, sim: &LinkSimulation) -> Section {
+    let mut section = Section::new(TITLE);
+    if sim.duplicate_exports.is_empty() {
+        section.summary = Some("No symbol is exported by more than one object".into());
+        return section;
+    }
+
+    let is_sensitive = |d: &DuplicateExport| SENSITIVE.contains(&d.symbol.as_str()) && !is_system_library(&d.providers[0]);
+    let (sensitive, other): (Vec<&DuplicateExport>, Vec<&DuplicateExport>) = sim.duplicate_exports.iter().partition(|d| is_sensitive(d));
+    section.summary = Some(format!("{} symbols exported by more than one object, {} security-sensitive functions interposed",
+        sim.duplicate_exports.len(), sensitive.len()));
+
+    for duplicate in sensitive.iter() {
+        let winner = &duplicate.providers[0];
+        let losers = duplicate.providers[1..].join(", ");
+        section.entries.push(Entry::new(format!("{}: {} wins over {}", duplicate.symbol, winner, losers), ReportStyle::Normal));
+        // An executable defining its own allocator is usually deliberate, a library doing it for everyone is not
+        let (severity, who) = if winner == binary {
+            (Severity::Low, "The executable itself".to_string())
+        } else {
+            (Severity::Medium, format!("Library {}", winner))
+        };
+        section.findings.push(Finding {
+            rule_id: "CNF-LINK-004".into(),
+            title: "Security-sensitive function interposed".into(),
+            message: format!("{} defines {} ahead of {} in the global scope, so every call to it in the process goes there",
+                who, duplicate.symbol, losers),
+            severity,
+            category: Category::Dependencies,
+            location: None,
+            detail: if severity >= Severity::Medium { ReportStyle::Terse } else { ReportStyle::Normal },
+        });
+    }
+
+    if !other.is_empty() {
+        let mut entry = Entry::new(format!("{} other duplicate exports:", other.len()), ReportStyle::Normal);
+        for duplicate in other.iter() {
+            entry.push(Entry::new(format!("{}: {} wins over {}", duplicate.symbol, duplicate.providers[0], duplicate.providers[1..].join(", ")), ReportStyle::Detailed));
+        }
+        section.entries.push(entry);
+    }
+    section.data = Some(SectionData::SymbolInterposition {
+        symbols: sim.duplicate_exports.iter()
+            .map(|d| InterposedSymbol { symbol: d.symbol.clone(), providers: d.providers.clone(), sensitive: is_sensitive(d) })
+            .collect(),
+    });
+    section
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn duplicate(symbol: &str, providers: &[&str]) -> DuplicateExport {
+        DuplicateExport { symbol: symbol.into(), providers: providers.iter().map(|p| p.to_string()).collect() }
+    }
+
+    #[test]
+    fn only_sensitive_functions_taken_from_the_system_libraries_are_findings() {
+        let sim = LinkSimulation {
+            duplicate_exports: vec![
+                duplicate("cnf_shared", &["libcnfa.so", "libcnfb.so"]),
+                duplicate("free", &["app", "libc.so.6"]),
+                duplicate("malloc", &["libjemalloc.so.2", "libc.so.6"]),
+                duplicate("_dl_catch_error", &["libc.so.6", "ld-linux-x86-64.so.2"]),
+                duplicate("open", &["libc.so.6", "libfakeroot.so"]),
+            ],
+            ..Default::default()
+        };
+        let section = symbol_interposition("app", &sim);
+        assert_eq!(section.summary.as_deref(), Some("5 symbols exported by more than one object, 2 security-sensitive functions interposed"));
+        let findings: Vec<(Severity, &str)> = section.findings.iter().map(|f| (f.severity, f.message.as_str())).collect();
+        assert_eq!(findings, [
+            (Severity::Low, "The executable itself defines free ahead of libc.so.6 in the global scope, so every call to it in the process goes there"),
+            (Severity::Medium, "Library libjemalloc.so.2 defines malloc ahead of libc.so.6 in the global scope, so every call to it in the process goes there"),
+        ]);
+        let Some(SectionData::SymbolInterposition { symbols }) = &section.data else { panic!("{:?}", section.data) };
+        let sensitive: Vec<&str> = symbols.iter().filter(|s| s.sensitive).map(|s| s.symbol.as_str()).collect();
+        assert_eq!(sensitive, ["free", "malloc"]);
+    }
+
+    #[test]
+    fn no_duplicates_is_a_summary_only() {
+        let section = symbol_interposition("app", &LinkSimulation::default());
+        assert_eq!(section.summary.as_deref(), Some("No symbol is exported by more than one object"));
+        assert!(section.findings.is_empty() && section.data.is_none());
+    }
+}
@@ -2,12 +2,25 @@
 mod single_file;
 mod single_source;
 mod single_binary;
+mod folder;
+mod web;
 
 pub use single_file::*;
 pub use single_source::*;
 pub use single_binary::*;
+pub use folder::*;
+pub use web::*;
 
 
 mod elf_lib_lookup;
 mod elf_internal_func_tree;
+mod source_checks;
+mod hardening;
+mod reachability;
+mod import_catalogue;
+mod dependency_tree;
+mod interposition;
+
+#[cfg(test)]
+mod test_fixtures;
 
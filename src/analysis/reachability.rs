@@ -0,0 +1,219 @@
+
+// Which dangerous functions the code can actually get to, and how.
+// A multi-source BFS over the call graph from every root (entry point, main, exports) gives the
+// shortest call path to each sink; the path is the evidence in the entry and the finding.
+
+use std::collections::VecDeque;
+
+use crate::args::ReportStyle;
+use crate::report::{CallGraph, CallGraphNode, Category, Entry, Finding, Location, Section, Severity};
+
+use super::import_catalogue::ImportCatalogue;
+
+const TITLE: &str = "Dangerous Call Paths";
+
+/// Import catalogue categories traced when no --sink is given, with how bad reaching one is.
+/// The functions come from the catalogue, so --import-catalogue changes the sinks too.
+const SINK_CATEGORIES: &[(&str, Severity, Category)] = &[
+    ("unsafe-string", Severity::Medium, Category::MemorySafety),
+    ("command-execution", Severity::Medium, Category::General),
+];
+
+/// Sinks which are worse than their category: gets can't be used safely at all.
+const HIGH_SEVERITY_SINKS: &[&str] = &["gets"];
+
+/// `imports` are the binary's (name as shown, function name) pairs: a sink the binary imports but the
+/// graph has no call to was called from code the walk didn't find, so it is reported as untraced.
+pub fn dangerous_call_paths(path: &std::path::Path, graph: &CallGraph, imports: &[(String, String)], catalogue: &ImportCatalogue, args: &crate::args::Args) -> Section {
+    let mut section = Section::new(TITLE);
+    let sinks: Vec<&str> = if args.sinks.is_empty() {
+        catalogue.functions.iter()
+            .filter(|(_, (category, _))| SINK_CATEGORIES.iter().any(|(sink_category, _, _)| sink_category == category))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    } else {
+        args.sinks.iter().map(|s| s.as_str()).collect()
+    };
+
+    // Multi-source BFS: parent[n] is the edge n was first reached through
+    let mut calls: Vec<Vec<usize>> = vec![Vec::new(); graph.nodes.len()];
+    for (i, edge) in graph.edges.iter().enumerate() {
+        calls[edge.from].push(i);
+    }
+    let mut reached = vec![false; graph.nodes.len()];
+    let mut parent: Vec<Option<usize>> = vec![None; graph.nodes.len()];
+    let mut queue: VecDeque<usize> = VecDeque::new();
+    for root in graph.roots.iter() {
+        reached[*root] = true;
+        queue.push_back(*root);
+    }
+    while let Some(node) = queue.pop_front() {
+        for edge in calls[node].iter() {
+            if let Some(to) = graph.edges[*edge].to && !reached[to] {
+                reached[to] = true;
+                parent[to] = Some(*edge);
+                queue.push_back(to);
+            }
+        }
+    }
+
+    let mut reachable = Vec::new();
+    let mut untraced = Vec::new();
+    for sink in sinks.iter() {
+        // The same sink can be an import and a statically linked copy; report the shortest path
+        let candidates: Vec<usize> = (0..graph.nodes.len())
+            .filter(|n| graph.nodes[*n].name.as_deref().is_some_and(|name| base_name(name) == *sink))
+            .collect();
+        // A root has no path: the binary defines and exports the sink (libc does), it doesn't call it
+        let edges = candidates.iter()
+            .filter(|n| reached[**n])
+            .map(|n| call_path(graph, &parent, *n))
+            .filter(|edges| !edges.is_empty())
+            .min_by_key(|edges| edges.len());
+
+        match edges {
+            Some(edges) => {
+                reachable.push(*sink);
+                section.entries.push(path_entry(graph, &edges));
+                section.findings.push(sink_finding(path, graph, &edges, sink, catalogue));
+            }
+            None => {
+                // Called through a pointer or from code with no symbol or FDE, which the walk can't see
+                if candidates.is_empty() && imports.iter().any(|(_, name)| base_name(name) == *sink) {
+                    untraced.push(*sink);
+                    section.entries.push(Entry::new(format!("{}: imported, call sites not traced (graph incomplete)", sink), ReportStyle::Normal));
+                }
+                for n in candidates.iter() {
+                    let why = if graph.roots.contains(n) { "an entry point itself, not called from another root" } else { "present but not reachable from any root" };
+                    section.entries.push(Entry::new(format!("{}: {}", short_name(&graph.nodes[*n]), why), ReportStyle::Detailed));
+                }
+            }
+        }
+    }
+
+    let mut summary = match (reachable.is_empty(), untraced.is_empty()) {
+        (true, true) => format!("None of {} dangerous functions reachable", sinks.len()),
+        // Not the same as unreachable: the graph missed whatever calls them
+        (true, false) => format!("No call path found to {} dangerous functions", sinks.len()),
+        (false, _) => format!("{} dangerous {} reachable ({})", reachable.len(),
+            if reachable.len() == 1 { "function" } else { "functions" }, reachable.join(", ")),
+    };
+    if !untraced.is_empty() {
+        summary = format!("{}, {} imported but not traced ({}): the call graph is incomplete", summary, untraced.len(), untraced.join(", "));
+    }
+    section.summary = Some(summary);
+    section
+}
+
+/// "strcpy@libc.so.6", "strcpy@plt", "KERNEL32.dll!lstrcpyA" -> "strcpy", "strcpy", "lstrcpyA"
+fn base_name(name: &str) -> &str {
+    let name = name.rsplit('!').next().unwrap_or(name);
+    name.split('@').next().unwrap_or(name)
+}
+
+fn short_name(node: &CallGraphNode) -> String {
+    node.name.clone().unwrap_or_else(|| node.id())
+}
+
+/// Edge indices from a root to `node`, following the BFS parents back.
+fn call_path(graph: &CallGraph, parent: &[Option<usize>], node: usize) -> Vec<usize> {
+    let mut edges = Vec::new();
+    let mut node = node;
+    while let Some(edge) = parent[node] {
+        edges.push(edge);
+        node = graph.edges[edge].from;
+    }
+    edges.reverse();
+    edges
+}
+
+/// "main -> parse -> strcpy@libc.so.6"
+fn path_text(graph: &CallGraph, edges: &[usize]) -> String {
+    let mut names = Vec::new();
+    if let Some(first) = edges.first() {
+        names.push(short_name(&graph.nodes[graph.edges[*first].from]));
+    }
+    for edge in edges.iter() {
+        names.extend(graph.edges[*edge].to.map(|to| short_name(&graph.nodes[to])));
+    }
+    names.join(" -> ")
+}
+
+fn path_entry(graph: &CallGraph, edges: &[usize]) -> Entry {
+    let mut entry = Entry::new(path_text(graph, edges), ReportStyle::Normal);
+    for edge in edges.iter().map(|e| &graph.edges[*e]) {
+        let to = edge.to.map(|to| short_name(&graph.nodes[to])).unwrap_or_default();
+        entry.push(Entry::new(format!("{} calls {} at 0x{:x}", short_name(&graph.nodes[edge.from]), to, edge.call_site), ReportStyle::Detailed));
+    }
+    entry
+}
+
+fn sink_finding(path: &std::path::Path, graph: &CallGraph, edges: &[usize], sink: &str, catalogue: &ImportCatalogue) -> Finding {
+    let (severity, category) = catalogue.classify(sink)
+        .and_then(|(sink_category, _)| SINK_CATEGORIES.iter().find(|(c, _, _)| *c == sink_category))
+        .map(|(_, severity, category)| (*severity, *category))
+        .unwrap_or((Severity::Medium, Category::General));
+    let severity = if HIGH_SEVERITY_SINKS.contains(&sink) { Severity::High } else { severity };
+    // The call into the sink is the place to look at
+    let address = edges.last().map(|e| graph.edges[*e].call_site);
+    Finding {
+        rule_id: "CNF-REACH-001".into(),
+        title: "Dangerous function reachable".into(),
+        message: format!("{} is reachable in {} {}: {}", sink, edges.len(), if edges.len() == 1 { "call" } else { "calls" }, path_text(graph, edges)),
+        severity,
+        category,
+        location: Some(Location { file: path.to_path_buf(), address, ..Default::default() }),
+        detail: if severity >= Severity::Medium { ReportStyle::Terse } else { ReportStyle::Normal },
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use clap::Parser;
+
+    use crate::analysis::test_fixtures::FixtureDir;
+    use crate::args::Args;
+
+    fn call_paths(binary: &std::path::Path) -> crate::report::Section {
+        let args = Args::parse_from(["cyber-nutrition-facts".as_ref(), binary.as_os_str()]);
+        let facts = crate::analysis::analyze_single_binary(binary, &args).unwrap();
+        facts.sections.into_iter().find(|s| s.title == super::TITLE).unwrap()
+    }
+
+    #[test]
+    fn stripped_binary_recovers_main_from_libc_start_main() {
+        let dir = FixtureDir::new("stripped-main");
+        let binary = dir.compile("stripped", r#"
+            #include <stdlib.h>
+            #include <string.h>
+            int main(int argc, char **argv) {
+                char buf[16];
+                strcpy(buf, argv[1]);
+                return system(buf);
+            }
+        "#, &["-O0", "-fno-builtin", "-s"]);
+
+        let section = call_paths(&binary);
+        assert_eq!(section.summary.as_deref(), Some("2 dangerous functions reachable (strcpy, system)"));
+        assert!(section.entries.iter().all(|e| e.text.starts_with("main -> ")), "{:?}", section.entries);
+    }
+
+    #[test]
+    fn imports_without_a_traced_call_are_not_reported_unreachable() {
+        let dir = FixtureDir::new("untraced-sink");
+        let binary = dir.compile("untraced", r#"
+            #include <stdlib.h>
+            int (*volatile run)(const char *) = system;
+            int main(int argc, char **argv) {
+                return run(argv[1]);
+            }
+        "#, &["-O0", "-s"]);
+
+        let section = call_paths(&binary);
+        let summary = section.summary.unwrap();
+        assert!(summary.starts_with("No call path found to "), "{}", summary);
+        assert!(summary.ends_with(", 1 imported but not traced (system): the call graph is incomplete"), "{}", summary);
+        assert!(section.entries.iter().any(|e| e.text == "system: imported, call sites not traced (graph incomplete)"));
+        assert!(section.findings.is_empty());
+    }
+}
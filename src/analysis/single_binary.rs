@@ -1,82 +1,304 @@
 
+use crate::args::ReportStyle;
+use crate::report::{Category, Entry, Finding, LibraryComponent, NutritionFacts, Provenance, Section, SectionData, Severity};
+
+use super::elf_lib_lookup::LinkSimulation;
+use super::import_catalogue::ImportCatalogue;
+
+pub fn analyze_single_binary(path: &std::path::Path, args: &crate::args::Args) -> crate::DynResult<NutritionFacts> {
 
-pub fn analyze_single_binary(path: &std::path::Path, args: &crate::args::Args) -> crate::DynResult<()> {
-    
     let binary_content_bytes = std::fs::read(path)?;
 
     let obj = goblin::Object::parse(&binary_content_bytes)?;
 
-    print_referenced_libraries("", path, &obj, args);
-    print_function_call_graph("", path, &obj, args);
+    // Reads and parses every library in the load set, so it's done once for all the sections using it
+    let sim = match &obj {
+        goblin::Object::Elf(_) => super::elf_lib_lookup::simulate_dynamic_linking(path, None, args),
+        _ => Err(crate::tracked_err!("Only ELF load sets can be simulated").into()),
+    };
+    // Shared by every section classifying imports, so a broken --import-catalogue fails them all the same way
+    let catalogue = ImportCatalogue::for_args(args);
+
+    let mut facts = NutritionFacts::new(Provenance::for_file(path, "single_binary"));
+    facts.push(referenced_libraries(path, &obj, &sim, args));
+    facts.push(dependency_tree(path, &obj, &sim, &catalogue));
+    facts.push(symbol_interposition(path, &obj, &sim, args));
+    facts.push(binary_hardening(path, &obj, &binary_content_bytes, args));
+    let imports = binary_imports(&obj);
+    facts.push(dangerous_imports(imports.as_deref(), &catalogue));
+    let call_graph = function_call_graph(path, &obj, &binary_content_bytes, &sim, args);
+    let call_paths = dangerous_call_paths(path, &call_graph, imports.as_deref().unwrap_or_default(), &catalogue, args);
+    facts.push(call_graph);
+    facts.push(call_paths);
+
+    Ok(facts)
+}
 
-    Ok(())
+/// Shortest call paths to dangerous functions, traced over the graph function_call_graph() built.
+/// `imports` tells sinks the walk never found a call to apart from ones the binary doesn't use.
+pub fn dangerous_call_paths(path: &std::path::Path, call_graph: &Section, imports: &[(String, String)], catalogue: &crate::DynResult<ImportCatalogue>, args: &crate::args::Args) -> Section {
+    const TITLE: &str = "Dangerous Call Paths";
+    match (&call_graph.data, catalogue) {
+        (_, Err(e)) => Section::unavailable(TITLE, format!("Could not load import catalogue: {}", e)),
+        (Some(SectionData::CallGraph(graph)), Ok(catalogue)) => super::reachability::dangerous_call_paths(path, graph, imports, catalogue, args),
+        _ => Section::unavailable(TITLE, "No call graph to trace"),
+    }
 }
 
-pub fn print_function_call_graph(prefix: &str, path: &std::path::Path, gobj: &goblin::Object, args: &crate::args::Args) {
+pub fn function_call_graph(path: &std::path::Path, gobj: &goblin::Object, bytes: &[u8], sim: &crate::DynResult<LinkSimulation>, args: &crate::args::Args) -> Section {
+    const TITLE: &str = "Internal Function Call Graph";
     match gobj {
         goblin::Object::Elf(elf) => {
-            println!("{}= = = = Internal Function Call Graph = = = =", prefix);
-            // if let Err(e) = super::elf_internal_func_tree::print_tree_of_elf(path, "__libc_start_main") {
-            //     eprintln!("{:?}", e);
-            // }
-            if let Err(e) = super::elf_internal_func_tree::print_tree_of_elf(path, "") {
-                eprintln!("{:?}", e);
+            // super::elf_internal_func_tree::tree_of_elf(path, "__libc_start_main")
+            // Only used to name imports, so a failed simulation just leaves them without a library
+            match super::elf_internal_func_tree::graph_of_elf(path, "", sim.as_ref().ok()) {
+                Ok(graph) => {
+                    let mut section = Section::new(TITLE);
+                    let roots = super::elf_internal_func_tree::call_tree(&graph);
+                    let calls: usize = roots.iter().map(|r| r.count() - 1).sum();
+                    section.summary = match roots.as_slice() {
+                        [] => Some("No entry point or exported functions".into()),
+                        [root] => Some(format!("{} calls reachable from {}", calls, root.text)),
+                        [root, others @ ..] => Some(format!("{} calls reachable from {} and {} more {} (main, exports)",
+                            calls, root.text, others.len(), if others.len() == 1 { "root" } else { "roots" })),
+                    };
+                    section.entries.extend(roots);
+                    section.data = Some(SectionData::CallGraph(graph));
+                    section
+                }
+                Err(e) => Section::unavailable(TITLE, format!("{}", e)),
+            }
+        }
+        goblin::Object::PE(pe) => {
+            match super::elf_internal_func_tree::graph_of_pe(pe, bytes) {
+                Ok(graph) => {
+                    let mut section = Section::new(TITLE);
+                    let roots = super::elf_internal_func_tree::call_tree(&graph);
+                    let calls: usize = roots.iter().map(|r| r.count() - 1).sum();
+                    section.summary = Some(format!("{} calls reachable from the entry point and {} exports", calls, roots.len() - 1));
+                    section.entries.extend(roots);
+                    section.data = Some(SectionData::CallGraph(graph));
+                    section
+                }
+                Err(e) => Section::unavailable(TITLE, format!("{}", e)),
             }
-
         }
+        _ => {
+            Section::unavailable(TITLE, "TODO Implement support in function_call_graph for this object format")
+        }
+    }
+}
+
+pub fn dangerous_imports(imports: Option<&[(String, String)]>, catalogue: &crate::DynResult<ImportCatalogue>) -> Section {
+    const TITLE: &str = "Dangerous Imports";
+    match (imports, catalogue) {
+        (None, _) => Section::unavailable(TITLE, "TODO Implement support in dangerous_imports for this object format"),
+        (_, Err(e)) => Section::unavailable(TITLE, format!("Could not load import catalogue: {}", e)),
+        (Some(imports), Ok(catalogue)) => super::import_catalogue::dangerous_imports(imports, catalogue),
+    }
+}
+
+/// Imported functions as (name as shown, function name) pairs, eg ("KERNEL32.dll!WinExec", "WinExec").
+pub fn binary_imports(gobj: &goblin::Object) -> Option<Vec<(String, String)>> {
+    match gobj {
+        goblin::Object::Elf(elf) => Some(elf_imports(elf)),
         goblin::Object::PE(pe) => {
-            std::unimplemented!()
+            Some(pe.imports.iter().map(|import| (format!("{}!{}", import.dll, import.name), import.name.to_string())).collect())
+        }
+        _ => None,
+    }
+}
+
+/// Undefined dynamic symbols of an ELF object as (name as shown, function name) pairs, sorted.
+pub fn elf_imports(elf: &goblin::elf::Elf) -> Vec<(String, String)> {
+    let mut names: Vec<String> = elf.dynsyms.iter()
+        .filter(|sym| sym.st_shndx == 0 && sym.st_name != 0)
+        .filter_map(|sym| elf.dynstrtab.get_at(sym.st_name))
+        .map(|name| name.to_string())
+        .collect();
+    names.sort();
+    names.dedup();
+    names.into_iter().map(|name| (name.clone(), name)).collect()
+}
+
+/// Which library loaded which, with each library's own hardening; ELF only, PE has no transitive search to simulate.
+pub fn dependency_tree(path: &std::path::Path, gobj: &goblin::Object, sim: &crate::DynResult<LinkSimulation>, catalogue: &crate::DynResult<ImportCatalogue>) -> Section {
+    const TITLE: &str = "Dependency Tree";
+    match gobj {
+        goblin::Object::Elf(elf) => {
+            match (sim, catalogue) {
+                (Ok(sim), Ok(catalogue)) => super::dependency_tree::dependency_tree(path, elf, sim, catalogue),
+                (Err(e), _) => Section::unavailable(TITLE, format!("Dynamic linking simulation failed: {}", e)),
+                (_, Err(e)) => Section::unavailable(TITLE, format!("Could not load import catalogue: {}", e)),
+            }
+        }
+        _ => {
+            Section::unavailable(TITLE, "Only ELF load sets can be simulated, DLLs are located by the Windows loader")
+        }
+    }
+}
+
+/// Symbols more than one object in the load set defines, and which definition wins.
+pub fn symbol_interposition(path: &std::path::Path, gobj: &goblin::Object, sim: &crate::DynResult<LinkSimulation>, args: &crate::args::Args) -> Section {
+    const TITLE: &str = "Symbol Interposition";
+    match gobj {
+        goblin::Object::Elf(_) => {
+            let binary = path.file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_default();
+            match sim {
+                Ok(sim) => super::interposition::symbol_interposition(&binary, sim),
+                Err(e) => Section::unavailable(TITLE, format!("Dynamic linking simulation failed: {}", e)),
+            }
+        }
+        _ => {
+            Section::unavailable(TITLE, "Only ELF load sets can be simulated, DLLs are located by the Windows loader")
         }
+    }
+}
+
+pub fn binary_hardening(path: &std::path::Path, gobj: &goblin::Object, bytes: &[u8], args: &crate::args::Args) -> Section {
+    match gobj {
+        goblin::Object::Elf(elf) => super::hardening::elf_hardening(path, elf, bytes),
+        goblin::Object::PE(pe) => super::hardening::pe_hardening(path, pe),
         _ => {
-            std::unimplemented!()
+            Section::unavailable("Binary Hardening", "TODO Implement support in binary_hardening for this object format")
         }
     }
 }
 
-pub fn print_referenced_libraries(prefix: &str, path: &std::path::Path, gobj: &goblin::Object, args: &crate::args::Args) {
+pub fn referenced_libraries(path: &std::path::Path, gobj: &goblin::Object, sim: &crate::DynResult<LinkSimulation>, args: &crate::args::Args) -> Section {
+    const TITLE: &str = "Shared Libraries";
+    // Hashing the whole load set is slow and only the SBOM renderers use the hashes
+    let hash = args.output_format().is_sbom();
     match gobj {
         goblin::Object::Elf(elf) => {
-            println!("{}= = = = Shared Libraries = = = =", prefix);
+            let mut section = Section::new(TITLE);
 
-            let dynamic_libs = elf.dynamic.as_ref().map(|v| v.get_libraries(&elf.dynstrtab)).unwrap_or_else(|| vec![]);
+            let dynamic_libs = elf.dynamic.as_ref().map(|v| v.get_libraries(&elf.dynstrtab)).unwrap_or_default();
 
-            let (lib_funcs, symbols_not_found) = match super::elf_lib_lookup::simulate_dynamic_linking(path, None, args) {
-                Ok(lf) => lf,
+            let failed = LinkSimulation::default();
+            let sim = match sim {
+                Ok(sim) => sim,
                 Err(e) => {
-                    eprintln!("{:?}", e);
-                    (std::collections::HashMap::new(), Vec::new())
+                    section.entries.push(Entry::new(format!("Dynamic linking simulation failed: {}", e), ReportStyle::Detailed));
+                    &failed
                 }
             };
 
-
-            if dynamic_libs.len() < 1 {
-                println!("{}NO LIBRARIES REFERENCED IN elf.dynstrtab", prefix);
+            if dynamic_libs.is_empty() {
+                section.summary = Some("NO LIBRARIES REFERENCED IN elf.dynstrtab".into());
             }
             else {
+                let minimum = minimum_versions(&sim.required_versions);
+                let glibc = minimum.iter().find_map(|(_, versions)| versions.iter().find(|v| v.starts_with("GLIBC_2.")));
+                section.summary = Some(match glibc {
+                    Some(glibc) => format!("{} libraries referenced, needs glibc {}", dynamic_libs.len(), glibc.trim_start_matches("GLIBC_")),
+                    None => format!("{} libraries referenced", dynamic_libs.len()),
+                });
                 for lib in dynamic_libs.iter() {
-                    let lib = format!("{}", lib);
-                    println!("{} - {}", prefix, lib);
-                    if let Some(funcs) = lib_funcs.get(&lib) {
+                    let lib = lib.to_string();
+                    let resolved = sim.libraries.iter().find(|l| l.soname == lib);
+                    let mut text = match resolved {
+                        Some(resolved) => format!("{} => {} ({})", lib, resolved.path.display(), resolved.found_by.as_str()),
+                        None => lib.clone(),
+                    };
+                    if let Some(versions) = minimum.get(&lib) {
+                        text = format!("{}, needs {}", text, versions.join(", "));
+                    }
+                    let mut lib_entry = Entry::new(text, ReportStyle::Normal);
+                    if let Some(versions) = sim.required_versions.get(&lib) {
+                        lib_entry.push(Entry::new(format!("versions required: {}", versions.join(", ")), ReportStyle::Detailed));
+                    }
+                    if let Some(funcs) = sim.lib_funcs.get(&lib) {
                         for func in funcs.iter() {
-                            println!("{}   - {}", prefix, func);
+                            let text = match sim.symbol_versions.get(func) {
+                                Some(version) => format!("{} ({})", func, version),
+                                None => func.clone(),
+                            };
+                            lib_entry.push(Entry::new(text, ReportStyle::Normal));
                         }
                     }
+                    section.entries.push(lib_entry);
                 }
             }
+            for mismatch in sim.version_mismatches.iter() {
+                section.entries.push(Entry::new(format!("version mismatch: {}", mismatch), ReportStyle::Normal));
+            }
+
+            for resolved in sim.libraries.iter().filter(|l| l.needed_by.is_some()) {
+                section.entries.push(Entry::new(format!("{} => {} ({}, needed by {})", resolved.soname, resolved.path.display(),
+                    resolved.found_by.as_str(), resolved.needed_by.as_deref().unwrap_or("")), ReportStyle::Detailed));
+            }
+            for soname in sim.libraries_not_found.iter() {
+                section.entries.push(Entry::new(format!("could not locate '{}' in library search paths", soname), ReportStyle::Detailed));
+            }
+            for warning in sim.warnings.iter() {
+                section.entries.push(Entry::new(format!("warning: {}", warning), ReportStyle::Detailed));
+            }
 
-            if args.style >= crate::args::ReportStyle::Detailed {
-                println!("{} {} symbols/functions were not found in ANY shared libraries:", prefix, symbols_not_found.len());
-                for not_found_name in symbols_not_found.iter() {
-                    println!("{}   - {}", prefix, not_found_name);
+            let (fatal, harmless): (Vec<_>, Vec<_>) = sim.unresolved_symbols.iter().partition(|s| s.fatal);
+            if !fatal.is_empty() {
+                let mut not_found = Entry::new(format!("{} symbols/functions were not found in ANY shared libraries:", fatal.len()), ReportStyle::Normal);
+                for symbol in fatal.iter() {
+                    not_found.push(Entry::new(format!("{} ({} {})", symbol.name, symbol.binding, symbol.symbol_type), ReportStyle::Normal));
                 }
+                section.entries.push(not_found);
+            }
+            if !harmless.is_empty() {
+                let mut weak = Entry::new(format!("{} weak symbols are unresolved and bind to 0:", harmless.len()), ReportStyle::Detailed);
+                for symbol in harmless.iter() {
+                    let text = match &symbol.optional {
+                        Some(why) => format!("{} ({}, optional: {})", symbol.name, symbol.symbol_type, why),
+                        None => format!("{} ({})", symbol.name, symbol.symbol_type),
+                    };
+                    weak.push(Entry::new(text, ReportStyle::Detailed));
+                }
+                section.entries.push(weak);
+            }
+
+            section.data = Some(SectionData::SharedLibraries {
+                libraries: dynamic_libs.iter().map(|l| l.to_string()).collect(),
+                functions: sim.lib_funcs.clone(),
+                symbols_not_found: sim.symbols_not_found.clone(),
+                unresolved_symbols: sim.unresolved_symbols.clone(),
+                libraries_not_found: sim.libraries_not_found.clone(),
+                components: elf_library_components(sim, hash),
+                required_versions: sim.required_versions.clone(),
+            });
+
+            if !sim.version_mismatches.is_empty() {
+                section.findings.push(Finding {
+                    rule_id: "CNF-LINK-002".into(),
+                    title: "Required symbol versions missing".into(),
+                    message: format!("The dynamic loader will refuse to run this binary against the libraries found: {}", sim.version_mismatches.join("; ")),
+                    severity: Severity::Medium,
+                    category: Category::Dependencies,
+                    location: None,
+                    detail: ReportStyle::Terse,
+                });
             }
 
+            // Weak references are allowed to stay unresolved, only strong ones stop the loader
+            if !fatal.is_empty() {
+                let names: Vec<&str> = fatal.iter().map(|s| s.name.as_str()).collect();
+                section.findings.push(Finding {
+                    rule_id: "CNF-LINK-001".into(),
+                    title: "Unresolved imports".into(),
+                    message: format!("{} imported symbols are not exported by any library in the load order, ld.so will fail with \"undefined symbol\": {}",
+                        fatal.len(), names.join(", ")),
+                    severity: Severity::Medium,
+                    category: Category::Dependencies,
+                    location: None,
+                    detail: ReportStyle::Terse,
+                });
+            }
+
+            section
         }
         goblin::Object::PE(pe) => {
-            println!("{}= = = = Shared Libraries = = = =", prefix);
+            let mut section = Section::new(TITLE);
 
-            let import_libs: Vec<String> = pe.import_data.as_ref().map(|v| (&v.import_data).into_iter().map(|id| id.name.to_string()).collect() ).unwrap_or_else(|| vec![]);
-            let mut lib_funcs: std::collections::HashMap<String, Vec<String>> = std::collections::HashMap::new();
+            let import_libs: Vec<String> = pe.import_data.as_ref().map(|v| v.import_data.iter().map(|id| id.name.to_string()).collect() ).unwrap_or_default();
+            let mut lib_funcs: std::collections::BTreeMap<String, Vec<String>> = std::collections::BTreeMap::new();
 
             if let Some(import_datas) = &pe.import_data {
                 for import_data in import_datas.import_data.iter() {
@@ -86,28 +308,125 @@ pub fn print_referenced_libraries(prefix: &str, path: &std::path::Path, gobj: &g
                             funcs.push(silte_to_string(ilt));
                         }
                     }
+                    funcs.sort();
                     lib_funcs.insert(import_data.name.to_string(), funcs);
                 }
             }
 
-            if import_libs.len() < 1 {
-                println!("{}NO LIBRARIES REFERENCED IN pe.import_data", prefix);
+            if import_libs.is_empty() {
+                section.summary = Some("NO LIBRARIES REFERENCED IN pe.import_data".into());
             }
             else {
+                section.summary = Some(format!("{} libraries referenced", import_libs.len()));
                 for lib in import_libs.iter() {
-                    println!("{} - {}", prefix, lib);
+                    let mut lib_entry = Entry::new(lib.as_str(), ReportStyle::Normal);
                     if let Some(funcs) = lib_funcs.get(lib) {
                         for func in funcs.iter() {
-                            println!("{}   - {}", prefix, func);
+                            lib_entry.push(Entry::new(func.as_str(), ReportStyle::Normal));
                         }
                     }
+                    section.entries.push(lib_entry);
                 }
             }
+
+            section.data = Some(SectionData::SharedLibraries {
+                components: pe_library_components(path, &import_libs, hash),
+                libraries: import_libs,
+                functions: lib_funcs,
+                symbols_not_found: Vec::new(),
+                unresolved_symbols: Vec::new(),
+                libraries_not_found: Vec::new(),
+                required_versions: std::collections::BTreeMap::new(),
+            });
+
+            section
         }
         _ => {
-            println!("{} TODO Implement support in print_referenced_libraries for gobj={:?}", prefix, gobj);
+            Section::unavailable(TITLE, "TODO Implement support in referenced_libraries for this object format")
+        }
+    }
+}
+
+/// Highest version needed per library and version family, eg libc.so.6 -> ["GLIBC_2.34"],
+/// libstdc++.so.6 -> ["CXXABI_1.3.9", "GLIBCXX_3.4.29"]. Unnumbered versions like GLIBC_PRIVATE are left out.
+fn minimum_versions(required: &std::collections::BTreeMap<String, Vec<String>>) -> std::collections::BTreeMap<String, Vec<String>> {
+    let mut minimum = std::collections::BTreeMap::new();
+    for (soname, versions) in required.iter() {
+        let mut highest: std::collections::BTreeMap<&str, (Vec<u64>, &String)> = std::collections::BTreeMap::new();
+        for version in versions.iter() {
+            if let Some((family, number)) = super::elf_lib_lookup::split_version(version)
+                && highest.get(family).is_none_or(|(best, _)| number > *best) {
+                highest.insert(family, (number, version));
+            }
+        }
+        if !highest.is_empty() {
+            minimum.insert(soname.clone(), highest.into_values().map(|(_, v)| v.clone()).collect());
+        }
+    }
+    minimum
+}
+
+fn elf_library_components(sim: &super::elf_lib_lookup::LinkSimulation, hash: bool) -> Vec<LibraryComponent> {
+    let mut components: Vec<LibraryComponent> = sim.libraries.iter().map(|lib| LibraryComponent {
+        name: lib.soname.clone(),
+        path: Some(lib.path.display().to_string()),
+        sha256: if hash { crate::report::sha256_of_file(&lib.path) } else { None },
+        version: version_of_library_file(&lib.path),
+        depends_on: lib.needed.clone(),
+    }).collect();
+    for soname in sim.libraries_not_found.iter() {
+        components.push(LibraryComponent {
+            name: soname.clone(),
+            path: None,
+            sha256: None,
+            version: version_from_file_name(soname),
+            depends_on: Vec::new(),
+        });
+    }
+    components
+}
+
+/// The Windows loader checks the application directory first, which is the only place
+/// we can look for DLLs on a non-Windows host; everything else is listed without a path.
+fn pe_library_components(path: &std::path::Path, import_libs: &[String], hash: bool) -> Vec<LibraryComponent> {
+    let app_dir = path.parent().unwrap_or(std::path::Path::new("."));
+    let dir_entries: Vec<std::path::PathBuf> = std::fs::read_dir(app_dir)
+        .map(|rd| rd.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+        .unwrap_or_default();
+    import_libs.iter().map(|name| {
+        // DLL names are case-insensitive
+        let found = dir_entries.iter().find(|p| p.file_name().is_some_and(|f| f.to_string_lossy().eq_ignore_ascii_case(name)));
+        let depends_on = found
+            .and_then(|p| std::fs::read(p).ok())
+            .and_then(|bytes| goblin::pe::PE::parse(&bytes).ok().map(|pe| pe.libraries.iter().map(|l| l.to_string()).collect()))
+            .unwrap_or_default();
+        LibraryComponent {
+            name: name.clone(),
+            path: found.map(|p| p.display().to_string()),
+            sha256: found.filter(|_| hash).and_then(|p| crate::report::sha256_of_file(p)),
+            version: None,
+            depends_on,
         }
+    }).collect()
+}
+
+/// Sonames are usually symlinks to the fully versioned file, so look through them first.
+fn version_of_library_file(path: &std::path::Path) -> Option<String> {
+    let real = std::fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+    let name = real.file_name()?.to_string_lossy().to_string();
+    version_from_file_name(&name)
+        .or_else(|| version_from_file_name(&path.file_name()?.to_string_lossy()))
+}
+
+/// "libstdc++.so.6.0.33" -> "6.0.33", "libfoo-1.2.so" -> "1.2", "libc.so.6" -> "6"
+fn version_from_file_name(name: &str) -> Option<String> {
+    let is_version = |s: &str| s.starts_with(|c: char| c.is_ascii_digit()) && s.chars().all(|c| c.is_ascii_digit() || c == '.');
+    if let Some((_, version)) = name.split_once(".so.") && is_version(version) {
+        return Some(version.to_string());
     }
+    let stem = name.strip_suffix(".so")?;
+    let (_, version) = stem.rsplit_once('-')?;
+    if is_version(version) { Some(version.to_string()) } else { None }
 }
 
 fn silte_to_string(silte: &goblin::pe::import::SyntheticImportLookupTableEntry) -> String {
@@ -120,5 +439,3 @@ fn silte_to_string(silte: &goblin::pe::import::SyntheticImportLookupTableEntry)
         }
     }
 }
-
-
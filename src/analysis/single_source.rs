@@ -1,30 +1,58 @@
 
-pub fn analyze_single_source(path: &std::path::Path, args: &crate::args::Args) -> crate::DynResult<()> {
+use crate::args::ReportStyle;
+use crate::report::{Entry, Metric, NutritionFacts, Provenance, Section, SectionData};
+
+pub fn analyze_single_source(path: &std::path::Path, args: &crate::args::Args) -> crate::DynResult<NutritionFacts> {
     let language_detection = hyperpolyglot::detect(path).map_err(|e| crate::tracked_err!(e))?;
-    let lang = language_detection.map(|v| v.language()).unwrap_or_else(|| "Unknown").to_lowercase();
+    let lang = language_detection.map(|v| v.language()).unwrap_or("Unknown").to_lowercase();
 
-    if args.style >= crate::args::ReportStyle::Detailed {
-        println!("language_detection = {:?} lang = {:?}", language_detection, lang);
-        println!("TODO analyze_single_source {:?}", path);
-        print_all_metrics_for_file(path)?;
-    }
-    else {
-        println!("TODO pass '--style detailed' or greater for in-development outputs.");
-    }
+    let mut facts = NutritionFacts::new(Provenance::for_file(path, "single_source"));
+
+    let mut lang_section = Section::new("Source Language");
+    lang_section.summary = Some(lang.clone());
+    lang_section.entries.push(Entry::new(format!("language_detection = {:?}", language_detection), ReportStyle::Detailed));
+    facts.push(lang_section);
 
+    facts.push(code_metrics(path));
 
-    Ok(())
+    if super::source_checks::is_c_family(&lang) {
+        facts.push(super::source_checks::format_string_checks(path));
+    }
+
+    Ok(facts)
 }
 
 use std::error::Error;
 use std::path::Path;
 
-use rust_code_analysis::{action, get_language_for_file, read_file_with_eol, LANG, Metrics, MetricsCfg};
+use rust_code_analysis::{get_function_spaces, get_language_for_file, read_file_with_eol, FuncSpace, LANG};
 
-/// Read `path`, detect language and print/dump all metrics to stdout using rust-code-analysis.
-///
-/// Returns `Ok(())` on success, or an error boxed as `Box<dyn Error>`.
-pub fn print_all_metrics_for_file(path: &Path) -> Result<(), Box<dyn Error>> {
+/// Read `path`, detect language and collect all metrics rust-code-analysis computes for the file.
+pub fn code_metrics(path: &Path) -> Section {
+    const TITLE: &str = "Code Metrics";
+    match function_spaces_for_file(path) {
+        Ok(space) => {
+            let mut section = Section::new(TITLE);
+            let m = &space.metrics;
+            section.summary = Some(format!(
+                "{} SLOC, {} functions, cyclomatic complexity {}",
+                m.loc.sloc(), m.nom.functions_sum(), m.cyclomatic.cyclomatic_sum()
+            ));
+            section.metrics = metrics_of_space(&space);
+            for sub_space in space.spaces.iter() {
+                section.entries.push(space_entry(sub_space));
+            }
+            if let Ok(spaces) = serde_json::to_value(&space) {
+                section.data = Some(SectionData::CodeMetrics { spaces });
+            }
+            section
+        }
+        Err(e) => Section::unavailable(TITLE, format!("{}", e)),
+    }
+}
+
+/// Runs rust-code-analysis over `path` and returns the top-level (whole file) space.
+pub fn function_spaces_for_file(path: &Path) -> Result<FuncSpace, Box<dyn Error>> {
     // 1) detect language from file extension
     let lang: LANG = get_language_for_file(path)
         .ok_or_else(|| format!("could not detect language for file {:?}", path))?;
@@ -33,15 +61,51 @@ pub fn print_all_metrics_for_file(path: &Path) -> Result<(), Box<dyn Error>> {
     let source_vec = read_file_with_eol(path)?
         .ok_or_else(|| format!("file {:?} is empty or could not be read", path))?;
 
-    // 3) prepare MetricsCfg (the action API expects a cfg that contains the path)
-    let cfg = MetricsCfg {
-        path: path.to_path_buf(),
-    };
+    // 3) compute metrics for the whole file and every nested function space
+    let space = get_function_spaces(&lang, source_vec, path, None)
+        .ok_or_else(|| format!("metrics computation failed for {:?}", path))?;
+
+    Ok(space)
+}
 
-    // 4) call action::<Metrics> which runs the metrics callback and (per crate examples) dumps metrics
-    //    The return type of action::<Metrics> is `Result<(), rust_code_analysis::Error>`
-    action::<Metrics>(&lang, source_vec, &cfg.path.clone(), None, cfg)
-        .map_err(|e| Box::<dyn Error>::from(format!("metrics action failed: {}", e)))?;
+/// Flattens the metrics of one space; the headline numbers are Normal, the rest Detailed.
+fn metrics_of_space(space: &FuncSpace) -> Vec<Metric> {
+    let m = &space.metrics;
+    vec![
+        Metric::new("sloc", m.loc.sloc(), ReportStyle::Normal),
+        Metric::new("ploc", m.loc.ploc(), ReportStyle::Detailed),
+        Metric::new("lloc", m.loc.lloc(), ReportStyle::Detailed),
+        Metric::new("cloc", m.loc.cloc(), ReportStyle::Detailed),
+        Metric::new("blank", m.loc.blank(), ReportStyle::Detailed),
+        Metric::new("functions", m.nom.functions_sum(), ReportStyle::Normal),
+        Metric::new("closures", m.nom.closures_sum(), ReportStyle::Detailed),
+        Metric::new("cyclomatic_sum", m.cyclomatic.cyclomatic_sum(), ReportStyle::Normal),
+        Metric::new("cyclomatic_average", m.cyclomatic.cyclomatic_average(), ReportStyle::Detailed),
+        Metric::new("cyclomatic_max", m.cyclomatic.cyclomatic_max(), ReportStyle::Detailed),
+        Metric::new("cognitive_sum", m.cognitive.cognitive_sum(), ReportStyle::Normal),
+        Metric::new("cognitive_max", m.cognitive.cognitive_max(), ReportStyle::Detailed),
+        Metric::new("nargs_average", m.nargs.nargs_average(), ReportStyle::Detailed),
+        Metric::new("nexits_sum", m.nexits.exit_sum(), ReportStyle::Detailed),
+        Metric::new("halstead_volume", m.halstead.volume(), ReportStyle::Detailed),
+        Metric::new("halstead_difficulty", m.halstead.difficulty(), ReportStyle::Detailed),
+        Metric::new("halstead_effort", m.halstead.effort(), ReportStyle::Detailed),
+        Metric::new("halstead_bugs", m.halstead.bugs(), ReportStyle::Detailed),
+        Metric::new("mi_original", m.mi.mi_original(), ReportStyle::Detailed),
+        Metric::new("mi_sei", m.mi.mi_sei(), ReportStyle::Detailed),
+        Metric::new("mi_visual_studio", m.mi.mi_visual_studio(), ReportStyle::Normal),
+    ]
+}
 
-    Ok(())
+/// Per-function breakdown, only shown in Overflowing reports.
+fn space_entry(space: &FuncSpace) -> Entry {
+    let m = &space.metrics;
+    let mut entry = Entry::new(format!(
+        "{} (lines {}-{}): sloc {}, cyclomatic {}, cognitive {}",
+        space.name.as_deref().unwrap_or("<anonymous>"), space.start_line, space.end_line,
+        m.loc.sloc(), m.cyclomatic.cyclomatic_sum(), m.cognitive.cognitive_sum()
+    ), ReportStyle::Overflowing);
+    for sub_space in space.spaces.iter() {
+        entry.push(space_entry(sub_space));
+    }
+    entry
 }
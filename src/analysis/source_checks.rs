@@ -0,0 +1,368 @@
+
+// Lightweight lexical checks over C-family source.
+// These do not parse the language; they look for printf-family calls and inspect the
+// format string literal and the argument text matched to each conversion.
+// Comments are blanked out first and calls are only looked for outside string and char literals.
+
+use crate::args::ReportStyle;
+use crate::report::{Category, Finding, Location, Section, Severity};
+
+/// printf-family functions, the index of their format argument and whether the values come
+/// from a va_list argument instead of the call's own arguments.
+const PRINTF_FAMILY: &[(&str, usize, bool)] = &[
+    ("printf", 0, false),
+    ("vprintf", 0, true),
+    ("fprintf", 1, false),
+    ("vfprintf", 1, true),
+    ("dprintf", 1, false),
+    ("vdprintf", 1, true),
+    ("sprintf", 1, false),
+    ("vsprintf", 1, true),
+    ("snprintf", 2, false),
+    ("vsnprintf", 2, true),
+    ("syslog", 1, false),
+    ("vsyslog", 1, true),
+];
+
+/// Names which, when they appear in an argument, mark it as attacker-controlled.
+const TAINTED_NAMES: &[&str] = &["argv", "getenv", "optarg"];
+
+pub fn is_c_family(lang: &str) -> bool {
+    matches!(lang, "c" | "c++" | "objective-c" | "objective-c++")
+}
+
+pub fn format_string_checks(path: &std::path::Path) -> Section {
+    const TITLE: &str = "Source Findings";
+    let source = match std::fs::read_to_string(path) {
+        Ok(s) => s,
+        Err(e) => return Section::unavailable(TITLE, format!("{}", e)),
+    };
+    let mut section = Section::new(TITLE);
+    section.findings = format_string_findings(path, &source);
+    section.summary = Some(format!("{} format string issues", section.findings.len()));
+    section
+}
+
+/// Finds `%s` conversions without a precision in printf-family calls.
+/// An unbounded `%s` reading argv/getenv/optarg is High, any other unbounded `%s` is Low.
+/// The v*printf functions print their caller's arguments through a va_list, which can't be traced, so those are Low.
+pub fn format_string_findings(path: &std::path::Path, source: &str) -> Vec<Finding> {
+    let mut findings = Vec::new();
+    // Same byte offsets as `source`: `uncommented` keeps literals for the arguments, `code` is what calls are searched in
+    let uncommented = blank_out(source, false);
+    let code = blank_out(source, true);
+    let bytes = code.as_bytes();
+    for (func, fmt_index, va_list) in PRINTF_FAMILY.iter() {
+        let mut search_from = 0;
+        while let Some(rel) = code[search_from..].find(func) {
+            let start = search_from + rel;
+            search_from = start + func.len();
+            // Whole identifiers only: "printf" must not match inside "snprintf" or "printf_s"
+            let before_ok = start == 0 || !is_ident_byte(bytes[start - 1]);
+            let after = code[search_from..].trim_start();
+            if !before_ok || !after.starts_with('(') {
+                continue;
+            }
+            let open = code.len() - after.len();
+            let Some(args) = split_call_args(&uncommented[open + 1..]) else {
+                continue;
+            };
+            let Some(format) = args.get(*fmt_index).and_then(|a| string_literal(a)) else {
+                continue;
+            };
+            let (line, column) = line_col(source, start);
+            for (conversion_index, spec) in conversions(&format).into_iter().enumerate() {
+                if spec != "s" {
+                    continue;
+                }
+                // A va_list is one argument however many conversions read from it
+                let arg_index = fmt_index + 1 + if *va_list { 0 } else { conversion_index };
+                let arg = args.get(arg_index).map(|a| a.trim()).unwrap_or("");
+                let tainted = !va_list && TAINTED_NAMES.iter().any(|n| contains_ident(arg, n));
+                let (rule_id, severity, title) = if tainted {
+                    ("CNF-SRC-001", Severity::High, "Unbounded %s of attacker-controlled input")
+                } else {
+                    ("CNF-SRC-002", Severity::Low, "Unbounded %s")
+                };
+                let printed = if *va_list { format!("a string from the va_list `{}`", arg) } else { format!("`{}`", arg) };
+                findings.push(Finding {
+                    rule_id: rule_id.into(),
+                    title: title.into(),
+                    message: format!("{}() prints {} with an unbounded %s; use a precision such as %.*s", func, printed),
+                    severity,
+                    category: Category::MemorySafety,
+                    location: Some(Location {
+                        file: path.to_path_buf(),
+                        line: Some(line),
+                        column: Some(column),
+                        ..Default::default()
+                    }),
+                    detail: if tainted { ReportStyle::Terse } else { ReportStyle::Normal },
+                });
+            }
+        }
+    }
+    findings.sort_by_key(|f| f.location.as_ref().and_then(|l| l.line));
+    findings
+}
+
+/// `source` with comments, and if `literals` the contents of string and char literals, replaced by
+/// spaces. Newlines and every other byte stay where they are, so offsets and line numbers still match.
+fn blank_out(source: &str, literals: bool) -> String {
+    enum State {
+        Code,
+        LineComment,
+        BlockComment,
+        Literal(u8),
+    }
+    let bytes = source.as_bytes();
+    let mut out = bytes.to_vec();
+    let mut state = State::Code;
+    let mut i = 0;
+    while i < bytes.len() {
+        let b = bytes[i];
+        let next = bytes.get(i + 1).copied();
+        let blank = match state {
+            State::Code => match (b, next) {
+                (b'/', Some(b'/')) => {
+                    state = State::LineComment;
+                    true
+                }
+                (b'/', Some(b'*')) => {
+                    state = State::BlockComment;
+                    out[i] = b' ';
+                    i += 1; // so the '*' can't also close it
+                    true
+                }
+                (b'"' | b'\'', _) => {
+                    state = State::Literal(b);
+                    false
+                }
+                _ => false,
+            },
+            State::LineComment => {
+                if b == b'\n' {
+                    state = State::Code;
+                }
+                b != b'\n'
+            }
+            State::BlockComment => {
+                if b == b'*' && next == Some(b'/') {
+                    out[i] = b' ';
+                    i += 1;
+                    state = State::Code;
+                }
+                b != b'\n'
+            }
+            State::Literal(quote) => {
+                if b == quote || b == b'\n' {
+                    // An unterminated literal ends at the line
+                    state = State::Code;
+                    false
+                } else {
+                    if b == b'\\' && next.is_some_and(|n| n != b'\n') {
+                        if literals {
+                            out[i] = b' ';
+                        }
+                        i += 1;
+                    }
+                    literals
+                }
+            }
+        };
+        if blank {
+            out[i] = b' ';
+        }
+        i += 1;
+    }
+    // Only ASCII bytes or whole UTF-8 sequences were replaced, so this doesn't fail
+    String::from_utf8(out).unwrap_or_else(|_| source.to_string())
+}
+
+fn is_ident_byte(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'_'
+}
+
+fn contains_ident(text: &str, ident: &str) -> bool {
+    let bytes = text.as_bytes();
+    text.match_indices(ident).any(|(i, _)| {
+        let end = i + ident.len();
+        (i == 0 || !is_ident_byte(bytes[i - 1])) && (end == bytes.len() || !is_ident_byte(bytes[end]))
+    })
+}
+
+/// Splits the text after a call's opening paren into top-level arguments.
+/// Returns None if the closing paren is never found.
+fn split_call_args(text: &str) -> Option<Vec<String>> {
+    let mut args = Vec::new();
+    let mut current = String::new();
+    let mut depth = 0usize;
+    let mut in_string = false;
+    let mut in_char = false;
+    let mut escaped = false;
+    for c in text.chars() {
+        if in_string || in_char {
+            current.push(c);
+            if escaped {
+                escaped = false;
+            } else if c == '\\' {
+                escaped = true;
+            } else if (in_string && c == '"') || (in_char && c == '\'') {
+                in_string = false;
+                in_char = false;
+            }
+            continue;
+        }
+        match c {
+            '"' => { in_string = true; current.push(c); }
+            '\'' => { in_char = true; current.push(c); }
+            '(' | '[' | '{' => { depth += 1; current.push(c); }
+            ')' if depth == 0 => {
+                args.push(current);
+                return Some(args);
+            }
+            ')' | ']' | '}' => { depth = depth.saturating_sub(1); current.push(c); }
+            ',' if depth == 0 => args.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    None
+}
+
+/// Concatenates the string literal(s) making up `arg`, or None if it is not a literal.
+fn string_literal(arg: &str) -> Option<String> {
+    let arg = arg.trim();
+    if !arg.starts_with('"') {
+        return None;
+    }
+    let mut out = String::new();
+    let mut in_string = false;
+    let mut escaped = false;
+    for c in arg.chars() {
+        if !in_string {
+            if c == '"' {
+                in_string = true;
+            }
+            continue;
+        }
+        if escaped {
+            out.push('\\');
+            out.push(c);
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            in_string = false;
+        } else {
+            out.push(c);
+        }
+    }
+    Some(out)
+}
+
+/// Conversion specifiers in `format` which consume an argument, in order.
+/// An unbounded string conversion is returned as "s"; `%.Ns` and `%.*s` are returned as ".s".
+/// `*` widths/precisions consume an argument of their own and are returned as "*".
+fn conversions(format: &str) -> Vec<String> {
+    let mut out = Vec::new();
+    let chars: Vec<char> = format.chars().collect();
+    let mut i = 0;
+    while i < chars.len() {
+        if chars[i] != '%' {
+            i += 1;
+            continue;
+        }
+        i += 1;
+        if i < chars.len() && chars[i] == '%' {
+            i += 1;
+            continue;
+        }
+        let mut has_precision = false;
+        while i < chars.len() && "-+ #0123456789.*hlLqjzt'".contains(chars[i]) {
+            if chars[i] == '.' {
+                has_precision = true;
+            }
+            if chars[i] == '*' {
+                out.push("*".to_string());
+            }
+            i += 1;
+        }
+        if i < chars.len() {
+            let spec = chars[i];
+            if spec == 's' && !has_precision {
+                out.push("s".to_string());
+            } else if spec == 's' {
+                out.push(".s".to_string());
+            } else {
+                out.push(spec.to_string());
+            }
+            i += 1;
+        }
+    }
+    out
+}
+
+/// 1-based line and column of byte offset `pos`.
+pub(crate) fn line_col(source: &str, pos: usize) -> (usize, usize) {
+    let before = &source[..pos];
+    let line = before.matches('\n').count() + 1;
+    let column = pos - before.rfind('\n').map(|i| i + 1).unwrap_or(0) + 1;
+    (line, column)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::*;
+
+    /// (line, column, rule_id, severity) of each finding, in source order.
+    fn findings(source: &str) -> Vec<(usize, usize, String, Severity)> {
+        format_string_findings(Path::new("test.c"), source).into_iter().map(|f| {
+            let location = f.location.unwrap();
+            (location.line.unwrap(), location.column.unwrap(), f.rule_id, f.severity)
+        }).collect()
+    }
+
+    #[test]
+    fn unsafe_sample_flags_both_unbounded_printfs() {
+        let found = findings(include_str!("../../samples/c_unsafe_a.c"));
+        assert_eq!(found, vec![
+            (5, 5, "CNF-SRC-002".to_string(), Severity::Low),
+            (10, 5, "CNF-SRC-001".to_string(), Severity::High),
+        ]);
+        assert!(findings(include_str!("../../samples/c_safe_a.c")).is_empty());
+    }
+
+    #[test]
+    fn comments_and_string_literals_are_not_calls() {
+        let source = r#"
+// printf("%s", argv[1]);
+/* sprintf(buf, "%s",
+   getenv("HOME")); */
+const char *usage = "printf(\"%s\", argv[1])";
+int main(int argc, char **argv) {
+    printf("%s\n", /* argv */ name);
+    printf("%d %s\n", '"', argv[0]); // printf("%s", x)
+}
+"#;
+        let found = findings(source);
+        assert_eq!(found, vec![
+            (7, 5, "CNF-SRC-002".to_string(), Severity::Low),
+            (8, 5, "CNF-SRC-001".to_string(), Severity::High),
+        ]);
+    }
+
+    #[test]
+    fn va_list_is_reported_once_and_never_tainted() {
+        let source = r#"
+void log_args(const char *fmt, va_list argv_list) {
+    vfprintf(stderr, "%s: %s\n", argv_list);
+}
+"#;
+        let found = format_string_findings(Path::new("test.c"), source);
+        assert_eq!(found.len(), 2);
+        assert!(found.iter().all(|f| f.rule_id == "CNF-SRC-002" && f.severity == Severity::Low));
+        assert!(found[0].message.contains("a string from the va_list `argv_list`"), "{}", found[0].message);
+    }
+}
@@ -0,0 +1,56 @@
+
+// Binaries for the analysis tests, built from inline C with the host's cc so every fixture is
+// readable source plus the flags that make it interesting rather than a checked-in blob.
+
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+/// A scratch directory for one test, removed again when dropped.
+pub struct FixtureDir {
+    path: PathBuf,
+}
+
+impl FixtureDir {
+    pub fn new(test: &str) -> Self {
+        let path = std::env::temp_dir().join(format!("cnf-test-{}-{}", std::process::id(), test));
+        let _ = std::fs::remove_dir_all(&path);
+        std::fs::create_dir_all(&path).unwrap();
+        FixtureDir { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Writes `contents` to `name` inside the directory, creating parent directories.
+    pub fn write(&self, name: &str, contents: impl AsRef<[u8]>) -> PathBuf {
+        let path = self.path.join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(&path, contents).unwrap();
+        path
+    }
+
+    /// Compiles `source` into `name` with `cc <flags>`, run from inside the directory so flags can
+    /// use relative paths (`-L.`). Panics with the compiler's output if it fails.
+    pub fn compile(&self, name: &str, source: &str, flags: &[&str]) -> PathBuf {
+        let source_name = format!("{}.c", name.replace('/', "_"));
+        self.write(&source_name, source);
+        let output = self.path.join(name);
+        std::fs::create_dir_all(output.parent().unwrap()).unwrap();
+        let result = Command::new("cc")
+            .current_dir(&self.path)
+            .arg("-o").arg(&output)
+            .arg(&source_name)
+            .args(flags)
+            .output()
+            .expect("the ELF tests need a C compiler, cc, on PATH");
+        assert!(result.status.success(), "cc {} failed:\n{}", flags.join(" "), String::from_utf8_lossy(&result.stderr));
+        output
+    }
+}
+
+impl Drop for FixtureDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.path);
+    }
+}
@@ -0,0 +1,763 @@
+
+// Analysis of a single HTTP/S URL.
+// The page is fetched once; security headers and cookies come from the final response after
+// redirects, mixed content and third-party scripts from a lexical scan of the HTML body.
+
+use std::collections::BTreeMap;
+
+use crate::args::ReportStyle;
+use crate::report::{Category, Entry, Finding, Location, NutritionFacts, Provenance, Section, Severity};
+
+/// Only this much of the body is read; the checks only need the markup near the top anyway.
+const BODY_LIMIT: u64 = 16 * 1024 * 1024;
+
+/// HSTS max-age below 180 days is too short to be preloaded or to survive infrequent visits.
+const HSTS_MIN_MAX_AGE: u64 = 15_552_000;
+
+/// What we fetched; everything after `fetch_page` works off this alone.
+#[derive(Debug, Clone)]
+pub struct WebPage {
+    pub requested_url: String,
+    /// URL after following redirects
+    pub url: String,
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+pub fn analyze_url(url: &uris::Uri, args: &crate::args::Args) -> crate::DynResult<NutritionFacts> {
+    let page = fetch_page(&url.to_string())?;
+    Ok(analyze_page(&page))
+}
+
+pub fn fetch_page(url: &str) -> crate::DynResult<WebPage> {
+    use std::io::Read;
+    use ureq::ResponseExt;
+    let agent: ureq::Agent = ureq::Agent::config_builder()
+        // A 404 or 500 page still has headers worth reporting
+        .http_status_as_error(false)
+        .timeout_global(Some(std::time::Duration::from_secs(30)))
+        .user_agent(format!("cyber-nutrition-facts/{}", env!("CARGO_PKG_VERSION")))
+        .build()
+        .into();
+    let mut response = agent.get(url).call().map_err(|e| crate::tracked_err!(format!("Could not fetch {}: {}", url, e)))?;
+    let final_url = response.get_uri().to_string();
+    let status = response.status().as_u16();
+    let headers = response.headers().iter()
+        .map(|(name, value)| (name.as_str().to_string(), String::from_utf8_lossy(value.as_bytes()).to_string()))
+        .collect();
+    // .limit() would fail the whole fetch on a larger page, take() just stops reading
+    let mut body = Vec::new();
+    response.body_mut().as_reader().take(BODY_LIMIT).read_to_end(&mut body)?;
+    Ok(WebPage {
+        requested_url: url.to_string(),
+        url: final_url,
+        status,
+        headers,
+        body,
+    })
+}
+
+pub fn analyze_page(page: &WebPage) -> NutritionFacts {
+    let mut facts = NutritionFacts::new(Provenance::for_url(&page.requested_url, &page.body));
+    facts.provenance.mime = header(page, "content-type").map(|v| v.to_string());
+    let html = String::from_utf8_lossy(&page.body);
+    let page_uri = uris::Uri::parse(page.url.as_str()).ok();
+    let secure_context = page_uri.as_ref().is_some_and(is_https);
+
+    facts.push(response_section(page, secure_context));
+    facts.push(security_headers_section(page, secure_context));
+    facts.push(cookies_section(page, secure_context));
+    match page_uri {
+        Some(page_uri) => facts.push(page_content_section(page, &page_uri, secure_context, &html)),
+        None => facts.push(Section::unavailable("Page Content", format!("could not parse the final URL {}", page.url))),
+    }
+    facts
+}
+
+fn response_section(page: &WebPage, secure_context: bool) -> Section {
+    let mut section = Section::new("HTTP Response");
+    section.summary = Some(format!("HTTP {} from {}", page.status, page.url));
+    if page.url != page.requested_url {
+        section.entries.push(Entry::new(format!("redirected from {}", page.requested_url), ReportStyle::Normal));
+    }
+    let mut headers = Entry::new(format!("{} response headers", page.headers.len()), ReportStyle::Detailed);
+    for (name, value) in page.headers.iter() {
+        headers.push(Entry::new(format!("{}: {}", name, value), ReportStyle::Detailed));
+    }
+    section.entries.push(headers);
+
+    if !secure_context {
+        section.findings.push(web_finding(
+            "CNF-WEB-000", "Page served over plain HTTP", Severity::Medium, Category::Hardening,
+            format!("{} is not served over HTTPS, so its content and cookies can be read and modified in transit", page.url),
+        ));
+    }
+    section
+}
+
+fn security_headers_section(page: &WebPage, secure_context: bool) -> Section {
+    let mut section = Section::new("Security Headers");
+    let mut present = 0;
+    let csp = header(page, "content-security-policy");
+
+    // Strict-Transport-Security
+    match (header(page, "strict-transport-security"), secure_context) {
+        (_, false) => {
+            section.entries.push(Entry::new("Strict-Transport-Security: not applicable, browsers ignore it over plain HTTP", ReportStyle::Normal));
+        }
+        (Some(value), true) => {
+            present += 1;
+            section.entries.push(Entry::new(format!("Strict-Transport-Security: {}", value), ReportStyle::Normal));
+            let max_age = directive_value(value, "max-age").and_then(|v| v.trim_matches('"').parse::<u64>().ok()).unwrap_or(0);
+            if max_age < HSTS_MIN_MAX_AGE {
+                section.findings.push(web_finding(
+                    "CNF-WEB-002", "Short HSTS max-age", Severity::Low, Category::Hardening,
+                    format!("Strict-Transport-Security max-age is {} seconds, use at least {}", max_age, HSTS_MIN_MAX_AGE),
+                ));
+            }
+        }
+        (None, true) => {
+            section.entries.push(Entry::new("Strict-Transport-Security: MISSING", ReportStyle::Normal));
+            section.findings.push(web_finding(
+                "CNF-WEB-001", "Missing Strict-Transport-Security", Severity::Medium, Category::Hardening,
+                "No Strict-Transport-Security header, so the first visit can be downgraded to plain HTTP".into(),
+            ));
+        }
+    }
+
+    // Content-Security-Policy
+    match csp {
+        Some(value) => {
+            present += 1;
+            section.entries.push(Entry::new(format!("Content-Security-Policy: {}", value), ReportStyle::Normal));
+            let script_sources = directive_value(value, "script-src").or_else(|| directive_value(value, "default-src"));
+            match script_sources {
+                Some(sources) => {
+                    let weak: Vec<&str> = ["'unsafe-inline'", "'unsafe-eval'", "*", "data:", "http:", "https:"].into_iter()
+                        .filter(|w| sources.split_whitespace().any(|s| s.eq_ignore_ascii_case(w)))
+                        .collect();
+                    if !weak.is_empty() {
+                        section.findings.push(web_finding(
+                            "CNF-WEB-004", "Weak Content-Security-Policy", Severity::Low, Category::Hardening,
+                            format!("script sources allow {}", weak.join(", ")),
+                        ));
+                    }
+                }
+                None => {
+                    section.findings.push(web_finding(
+                        "CNF-WEB-004", "Weak Content-Security-Policy", Severity::Low, Category::Hardening,
+                        "Content-Security-Policy has neither script-src nor default-src, so scripts are unrestricted".into(),
+                    ));
+                }
+            }
+        }
+        None => {
+            section.entries.push(Entry::new("Content-Security-Policy: MISSING", ReportStyle::Normal));
+            section.findings.push(web_finding(
+                "CNF-WEB-003", "Missing Content-Security-Policy", Severity::Medium, Category::Hardening,
+                "No Content-Security-Policy header, so injected scripts run with the page's privileges".into(),
+            ));
+        }
+    }
+
+    // X-Frame-Options, or its CSP replacement frame-ancestors
+    let frame_ancestors = csp.and_then(|v| directive_value(v, "frame-ancestors"));
+    match header(page, "x-frame-options") {
+        Some(value) => {
+            present += 1;
+            section.entries.push(Entry::new(format!("X-Frame-Options: {}", value), ReportStyle::Normal));
+            let value = value.trim();
+            if !value.eq_ignore_ascii_case("deny") && !value.eq_ignore_ascii_case("sameorigin") && frame_ancestors.is_none() {
+                section.findings.push(web_finding(
+                    "CNF-WEB-005", "Missing clickjacking protection", Severity::Low, Category::Hardening,
+                    format!("X-Frame-Options '{}' is not DENY or SAMEORIGIN and browsers ignore it", value),
+                ));
+            }
+        }
+        None => {
+            section.entries.push(Entry::new("X-Frame-Options: MISSING", ReportStyle::Normal));
+            if frame_ancestors.is_none() {
+                section.findings.push(web_finding(
+                    "CNF-WEB-005", "Missing clickjacking protection", Severity::Medium, Category::Hardening,
+                    "Neither X-Frame-Options nor a CSP frame-ancestors directive, so the page can be framed by any site".into(),
+                ));
+            }
+        }
+    }
+
+    // Referrer-Policy
+    match header(page, "referrer-policy") {
+        Some(value) => {
+            present += 1;
+            section.entries.push(Entry::new(format!("Referrer-Policy: {}", value), ReportStyle::Normal));
+            // The last policy a browser understands wins
+            let effective = value.split(',').map(|p| p.trim().to_lowercase()).next_back().unwrap_or_default();
+            if effective == "unsafe-url" || effective == "no-referrer-when-downgrade" {
+                section.findings.push(web_finding(
+                    "CNF-WEB-006", "Leaky Referrer-Policy", Severity::Low, Category::Hardening,
+                    format!("Referrer-Policy '{}' sends full URLs, including paths and query strings, to other origins", effective),
+                ));
+            }
+        }
+        None => {
+            section.entries.push(Entry::new("Referrer-Policy: MISSING", ReportStyle::Normal));
+            section.findings.push(web_finding(
+                "CNF-WEB-006", "Missing Referrer-Policy", Severity::Low, Category::Hardening,
+                "No Referrer-Policy header; older browsers send full URLs to other origins".into(),
+            ));
+        }
+    }
+
+    // Permissions-Policy
+    match header(page, "permissions-policy") {
+        Some(value) => {
+            present += 1;
+            section.entries.push(Entry::new(format!("Permissions-Policy: {}", value), ReportStyle::Normal));
+        }
+        None => {
+            section.entries.push(Entry::new("Permissions-Policy: MISSING", ReportStyle::Normal));
+            section.findings.push(web_finding(
+                "CNF-WEB-007", "Missing Permissions-Policy", Severity::Low, Category::Hardening,
+                "No Permissions-Policy header, so embedded content may request camera, microphone, geolocation, ...".into(),
+            ));
+        }
+    }
+
+    section.summary = Some(format!("{} of 5 security headers present", present));
+    section
+}
+
+fn cookies_section(page: &WebPage, secure_context: bool) -> Section {
+    let mut section = Section::new("Cookies");
+    let cookies: Vec<&str> = headers(page, "set-cookie").collect();
+    section.summary = Some(format!("{} cookies set", cookies.len()));
+
+    for raw in cookies {
+        let mut parts = raw.split(';');
+        let name = parts.next().and_then(|nv| nv.split_once('=')).map(|(n, _)| n.trim()).unwrap_or(raw).to_string();
+        let mut secure = false;
+        let mut http_only = false;
+        let mut same_site: Option<String> = None;
+        for attribute in parts {
+            let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+            match key.trim().to_lowercase().as_str() {
+                "secure" => secure = true,
+                "httponly" => http_only = true,
+                "samesite" => same_site = Some(value.trim().to_lowercase()),
+                _ => {}
+            }
+        }
+
+        let mut flags = Vec::new();
+        if secure { flags.push("Secure".to_string()); }
+        if http_only { flags.push("HttpOnly".to_string()); }
+        if let Some(same_site) = &same_site { flags.push(format!("SameSite={}", same_site)); }
+        let mut entry = Entry::new(format!("{}: {}", name, if flags.is_empty() { "no flags".to_string() } else { flags.join(", ") }), ReportStyle::Normal);
+        entry.push(Entry::new(format!("Set-Cookie: {}", raw), ReportStyle::Detailed));
+        section.entries.push(entry);
+
+        if !secure {
+            section.findings.push(web_finding(
+                "CNF-WEB-010", "Cookie without Secure", if secure_context { Severity::Medium } else { Severity::Low }, Category::Hardening,
+                format!("cookie '{}' is not marked Secure and will also be sent over plain HTTP", name),
+            ));
+        }
+        if !http_only {
+            section.findings.push(web_finding(
+                "CNF-WEB-011", "Cookie without HttpOnly", Severity::Low, Category::Hardening,
+                format!("cookie '{}' is not marked HttpOnly and can be read by scripts", name),
+            ));
+        }
+        match same_site.as_deref() {
+            None => section.findings.push(web_finding(
+                "CNF-WEB-012", "Cookie without SameSite", Severity::Low, Category::Hardening,
+                format!("cookie '{}' has no SameSite attribute", name),
+            )),
+            Some("none") if !secure => section.findings.push(web_finding(
+                "CNF-WEB-012", "Cookie without SameSite", Severity::Medium, Category::Hardening,
+                format!("cookie '{}' is SameSite=None without Secure, which browsers reject or send cross-site over HTTP", name),
+            )),
+            _ => {}
+        }
+    }
+    section
+}
+
+/// How a subresource is used decides how bad loading it over plain HTTP is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ResourceKind {
+    /// Can run code or restyle the page: script, stylesheet, iframe, object, embed
+    Active,
+    /// Can only be displayed: img, audio, video
+    Passive,
+    FormAction,
+}
+
+fn page_content_section(page: &WebPage, page_uri: &uris::Uri, secure_context: bool, html: &str) -> Section {
+    let mut section = Section::new("Page Content");
+    let page_origin = origin(page_uri);
+    let location = |offset: usize| {
+        let (line, column) = super::source_checks::line_col(html, offset);
+        Some(Location { file: page.url.clone().into(), line: Some(line), column: Some(column), ..Default::default() })
+    };
+
+    let mut scripts = 0;
+    let mut inline_scripts = 0;
+    let mut third_party: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut mixed = Entry::new("", ReportStyle::Normal);
+
+    for tag in html_tags(html) {
+        let Some((kind, reference)) = subresource(&tag) else {
+            if tag.name == "script" {
+                scripts += 1;
+                inline_scripts += 1;
+            }
+            continue;
+        };
+        let Ok(reference_uri) = uris::Uri::parse(reference.trim()) else {
+            continue;
+        };
+        let resolved = page_uri.resolve(&reference_uri);
+        let scheme = resolved.scheme().unwrap_or("").to_lowercase();
+        if scheme != "http" && scheme != "https" {
+            // data:, blob:, javascript:, ... are never mixed content and have no origin
+            continue;
+        }
+        let resolved_text = resolved.to_string();
+
+        if tag.name == "script" {
+            scripts += 1;
+            if let Some(script_origin) = origin(&resolved)
+                && Some(&script_origin) != page_origin.as_ref()
+            {
+                third_party.entry(script_origin.clone()).or_default().push(resolved_text.clone());
+                if tag.attr("integrity").is_none() {
+                    section.findings.push(Finding {
+                        rule_id: "CNF-WEB-030".into(),
+                        title: "Third-party script without Subresource Integrity".into(),
+                        message: format!("{} is loaded from {} without an integrity attribute", resolved_text, script_origin),
+                        severity: Severity::Low,
+                        category: Category::Dependencies,
+                        location: location(tag.offset),
+                        detail: ReportStyle::Normal,
+                    });
+                }
+            }
+        }
+
+        if secure_context && !is_potentially_trustworthy(&resolved) {
+            mixed.push(Entry::new(format!("<{}> {}", tag.name, resolved_text), ReportStyle::Normal));
+            let (rule_id, title, severity) = match kind {
+                ResourceKind::Active => ("CNF-WEB-020", "Active mixed content", Severity::High),
+                ResourceKind::Passive => ("CNF-WEB-021", "Passive mixed content", Severity::Low),
+                ResourceKind::FormAction => ("CNF-WEB-022", "Form submits over plain HTTP", Severity::Medium),
+            };
+            section.findings.push(Finding {
+                rule_id: rule_id.into(),
+                title: title.into(),
+                message: format!("<{}> on a secure page loads {} over plain HTTP", tag.name, resolved_text),
+                severity,
+                category: Category::Hardening,
+                location: location(tag.offset),
+                detail: ReportStyle::Terse,
+            });
+        }
+    }
+
+    section.summary = Some(format!(
+        "{} scripts ({} inline) from {} third-party origins, {} mixed-content references",
+        scripts, inline_scripts, third_party.len(), mixed.children.len()
+    ));
+    if !third_party.is_empty() {
+        let mut origins = Entry::new(format!("{} third-party script origins:", third_party.len()), ReportStyle::Normal);
+        for (script_origin, urls) in third_party {
+            let mut entry = Entry::new(script_origin, ReportStyle::Normal);
+            for url in urls {
+                entry.push(Entry::new(url, ReportStyle::Detailed));
+            }
+            origins.push(entry);
+        }
+        section.entries.push(origins);
+    }
+    if !mixed.children.is_empty() {
+        mixed.text = format!("{} resources loaded over plain HTTP:", mixed.children.len());
+        section.entries.push(mixed);
+    }
+    section
+}
+
+/// The URL a tag loads and how it is used, or None if the tag loads nothing.
+fn subresource(tag: &HtmlTag) -> Option<(ResourceKind, &str)> {
+    match tag.name.as_str() {
+        "script" | "iframe" | "frame" | "embed" => tag.attr("src").map(|s| (ResourceKind::Active, s)),
+        "object" => tag.attr("data").map(|s| (ResourceKind::Active, s)),
+        "link" => {
+            let rel = tag.attr("rel").unwrap_or("").to_lowercase();
+            let active = rel.split_whitespace().any(|r| matches!(r, "stylesheet" | "preload" | "modulepreload" | "import"));
+            let kind = if active { ResourceKind::Active } else { ResourceKind::Passive };
+            tag.attr("href").map(|s| (kind, s))
+        }
+        "img" | "audio" | "video" | "source" | "track" => tag.attr("src").map(|s| (ResourceKind::Passive, s)),
+        "form" => tag.attr("action").map(|s| (ResourceKind::FormAction, s)),
+        _ => None,
+    }
+}
+
+/// Browsers only honor HSTS and block mixed content on pages actually served over HTTPS,
+/// a plain-HTTP page on loopback included.
+fn is_https(uri: &uris::Uri) -> bool {
+    uri.scheme().is_some_and(|scheme| scheme.eq_ignore_ascii_case("https"))
+}
+
+/// Subresources from https, wss, loopback and *.localhost URLs are not mixed content, browsers
+/// treat them as potentially trustworthy (https://www.w3.org/TR/secure-contexts/#is-origin-trustworthy).
+fn is_potentially_trustworthy(uri: &uris::Uri) -> bool {
+    let scheme = uri.scheme().unwrap_or("").to_lowercase();
+    if scheme == "https" || scheme == "wss" {
+        return true;
+    }
+    let host = uri.host_to_string().ok().flatten().unwrap_or_default().to_lowercase();
+    host == "localhost" || host.ends_with(".localhost") || host.starts_with("127.") || host == "::1" || host == "[::1]"
+}
+
+/// "scheme://host[:port]" with default ports dropped, or None for URLs without a host.
+fn origin(uri: &uris::Uri) -> Option<String> {
+    let scheme = uri.scheme()?.to_lowercase();
+    let host = uri.host_to_string().ok().flatten()?.to_lowercase();
+    match uri.port() {
+        Some(80) if scheme == "http" => Some(format!("{}://{}", scheme, host)),
+        Some(443) if scheme == "https" => Some(format!("{}://{}", scheme, host)),
+        Some(port) => Some(format!("{}://{}:{}", scheme, host, port)),
+        None => Some(format!("{}://{}", scheme, host)),
+    }
+}
+
+fn header<'a>(page: &'a WebPage, name: &str) -> Option<&'a str> {
+    page.headers.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+}
+
+fn headers<'a>(page: &'a WebPage, name: &'a str) -> impl Iterator<Item = &'a str> {
+    page.headers.iter().filter(move |(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, v)| v.as_str())
+}
+
+/// Value of `name` in a "; "-separated directive list, as used by CSP and HSTS.
+fn directive_value<'a>(header: &'a str, name: &str) -> Option<&'a str> {
+    header.split(';')
+        .map(|d| d.trim())
+        .find_map(|d| {
+            let (key, value) = d.split_once(|c: char| c == '=' || c.is_whitespace()).unwrap_or((d, ""));
+            if key.eq_ignore_ascii_case(name) { Some(value.trim()) } else { None }
+        })
+}
+
+fn web_finding(rule_id: &str, title: &str, severity: Severity, category: Category, message: String) -> Finding {
+    Finding {
+        rule_id: rule_id.into(),
+        title: title.into(),
+        message,
+        severity,
+        category,
+        location: None,
+        detail: if severity >= Severity::Medium { ReportStyle::Terse } else { ReportStyle::Normal },
+    }
+}
+
+/// A start tag found by `html_tags`.
+#[derive(Debug, Clone)]
+struct HtmlTag {
+    /// Lowercase tag name
+    name: String,
+    /// Lowercase attribute names with their (entity-decoded) values
+    attrs: Vec<(String, String)>,
+    /// Byte offset of the '<'
+    offset: usize,
+}
+
+impl HtmlTag {
+    fn attr(&self, name: &str) -> Option<&str> {
+        self.attrs.iter().find(|(n, _)| n == name).map(|(_, v)| v.as_str())
+    }
+}
+
+/// Every start tag in `html`, in document order.
+/// Like the C checks this is lexical: comments are skipped and the raw text of <script> and <style>
+/// is not searched for tags, which is all the precision the subresource checks need.
+fn html_tags(html: &str) -> Vec<HtmlTag> {
+    let bytes = html.as_bytes();
+    let lower = html.to_ascii_lowercase();
+    let mut tags = Vec::new();
+    let mut i = 0;
+    while let Some(rel) = html[i..].find('<') {
+        let start = i + rel;
+        i = start + 1;
+        if html[start..].starts_with("<!--") {
+            i = html[start..].find("-->").map(|e| start + e + 3).unwrap_or(html.len());
+            continue;
+        }
+        if !bytes.get(i).is_some_and(|b| b.is_ascii_alphabetic()) {
+            continue;
+        }
+        let name_end = html[i..].find(|c: char| !(c.is_ascii_alphanumeric() || c == '-')).map(|e| i + e).unwrap_or(html.len());
+        let name = lower[i..name_end].to_string();
+        let (attrs, end) = parse_attributes(html, name_end);
+        i = end;
+        if name == "script" || name == "style" {
+            let close = format!("</{}", name);
+            i = lower[i..].find(&close).map(|e| i + e).unwrap_or(html.len());
+        }
+        tags.push(HtmlTag { name, attrs, offset: start });
+    }
+    tags
+}
+
+/// Parses attributes starting at `i` up to the closing '>'; returns them and the offset after the '>'.
+fn parse_attributes(html: &str, mut i: usize) -> (Vec<(String, String)>, usize) {
+    let bytes = html.as_bytes();
+    let mut attrs = Vec::new();
+    loop {
+        while i < bytes.len() && (bytes[i].is_ascii_whitespace() || bytes[i] == b'/') {
+            i += 1;
+        }
+        if i >= bytes.len() {
+            return (attrs, i);
+        }
+        if bytes[i] == b'>' {
+            return (attrs, i + 1);
+        }
+        let name_start = i;
+        while i < bytes.len() && !bytes[i].is_ascii_whitespace() && !matches!(bytes[i], b'=' | b'>' | b'/') {
+            i += 1;
+        }
+        let name = html[name_start..i].to_ascii_lowercase();
+        while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+            i += 1;
+        }
+        let mut value = String::new();
+        if i < bytes.len() && bytes[i] == b'=' {
+            i += 1;
+            while i < bytes.len() && bytes[i].is_ascii_whitespace() {
+                i += 1;
+            }
+            if i < bytes.len() && (bytes[i] == b'"' || bytes[i] == b'\'') {
+                let quote = bytes[i];
+                let value_start = i + 1;
+                i = value_start;
+                while i < bytes.len() && bytes[i] != quote {
+                    i += 1;
+                }
+                value = html[value_start..i].to_string();
+                i = (i + 1).min(bytes.len());
+            } else {
+                let value_start = i;
+                while i < bytes.len() && !bytes[i].is_ascii_whitespace() && bytes[i] != b'>' {
+                    i += 1;
+                }
+                value = html[value_start..i].to_string();
+            }
+        }
+        attrs.push((name, decode_entities(&value)));
+    }
+}
+
+fn decode_entities(text: &str) -> String {
+    text.replace("&quot;", "\"").replace("&#39;", "'").replace("&lt;", "<").replace("&gt;", ">").replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    use super::*;
+
+    const SECURE_HTML: &str = include_str!("../../samples/web/secure.html");
+    const INSECURE_HTML: &str = include_str!("../../samples/web/insecure.html");
+
+    /// The headers serve-web-samples.py sends with secure.html
+    const SECURE_HEADERS: &[(&str, &str)] = &[
+        ("Strict-Transport-Security", "max-age=31536000; includeSubDomains"),
+        ("Content-Security-Policy", "default-src 'self'; script-src 'self' https://cdn.jsdelivr.net; frame-ancestors 'none'"),
+        ("X-Frame-Options", "DENY"),
+        ("Referrer-Policy", "strict-origin-when-cross-origin"),
+        ("Permissions-Policy", "camera=(), microphone=(), geolocation=()"),
+        ("Set-Cookie", "session=abc123; Path=/; Secure; HttpOnly; SameSite=Lax"),
+    ];
+
+    fn page(url: &str, headers: &[(&str, &str)], html: &str) -> WebPage {
+        WebPage {
+            requested_url: url.into(),
+            url: url.into(),
+            status: 200,
+            headers: headers.iter().map(|(n, v)| (n.to_string(), v.to_string())).collect(),
+            body: html.as_bytes().to_vec(),
+        }
+    }
+
+    fn findings<'a>(facts: &'a NutritionFacts, section: &str) -> Vec<&'a Finding> {
+        facts.sections.iter().filter(|s| s.title == section).flat_map(|s| s.findings.iter()).collect()
+    }
+
+    fn rule_ids(facts: &NutritionFacts, section: &str) -> Vec<String> {
+        findings(facts, section).iter().map(|f| f.rule_id.clone()).collect()
+    }
+
+    fn entries(facts: &NutritionFacts, section: &str) -> Vec<String> {
+        fn walk(entry: &Entry, out: &mut Vec<String>) {
+            out.push(entry.text.clone());
+            entry.children.iter().for_each(|c| walk(c, out));
+        }
+        let mut out = Vec::new();
+        facts.sections.iter().filter(|s| s.title == section).flat_map(|s| s.entries.iter()).for_each(|e| walk(e, &mut out));
+        out
+    }
+
+    #[test]
+    fn hardened_page_has_no_findings() {
+        let facts = analyze_page(&page("https://example.com/", SECURE_HEADERS, SECURE_HTML));
+        assert_eq!(facts.findings().count(), 0, "{:?}", facts.findings().collect::<Vec<_>>());
+        let headers = facts.sections.iter().find(|s| s.title == "Security Headers").unwrap();
+        assert_eq!(headers.summary.as_deref(), Some("5 of 5 security headers present"));
+    }
+
+    #[test]
+    fn missing_headers_on_https() {
+        let facts = analyze_page(&page("https://example.com/", &[], SECURE_HTML));
+        assert_eq!(rule_ids(&facts, "Security Headers"), ["CNF-WEB-001", "CNF-WEB-003", "CNF-WEB-005", "CNF-WEB-006", "CNF-WEB-007"]);
+        assert!(rule_ids(&facts, "HTTP Response").is_empty());
+    }
+
+    #[test]
+    fn weak_header_values() {
+        let headers = [
+            ("Strict-Transport-Security", "max-age=300"),
+            ("Content-Security-Policy", "script-src 'self' 'unsafe-inline' *"),
+            ("X-Frame-Options", "ALLOW-FROM https://example.com"),
+            ("Referrer-Policy", "no-referrer, unsafe-url"),
+            ("Permissions-Policy", "camera=()"),
+        ];
+        let facts = analyze_page(&page("https://example.com/", &headers, SECURE_HTML));
+        assert_eq!(rule_ids(&facts, "Security Headers"), ["CNF-WEB-002", "CNF-WEB-004", "CNF-WEB-005", "CNF-WEB-006"]);
+        let csp = findings(&facts, "Security Headers").into_iter().find(|f| f.rule_id == "CNF-WEB-004").unwrap();
+        assert!(csp.message.contains("'unsafe-inline'") && csp.message.contains('*'), "{}", csp.message);
+    }
+
+    #[test]
+    fn frame_ancestors_replaces_x_frame_options() {
+        let headers = [("Content-Security-Policy", "default-src 'self'; frame-ancestors 'none'")];
+        let facts = analyze_page(&page("https://example.com/", &headers, SECURE_HTML));
+        assert!(!rule_ids(&facts, "Security Headers").contains(&"CNF-WEB-005".to_string()));
+    }
+
+    #[test]
+    fn plain_http_on_loopback_is_not_a_secure_context() {
+        let facts = analyze_page(&page("http://127.0.0.1:8000/insecure", SECURE_HEADERS, INSECURE_HTML));
+        assert_eq!(rule_ids(&facts, "HTTP Response"), ["CNF-WEB-000"]);
+        let headers = rule_ids(&facts, "Security Headers");
+        assert!(!headers.contains(&"CNF-WEB-001".to_string()) && !headers.contains(&"CNF-WEB-002".to_string()), "{:?}", headers);
+        assert!(entries(&facts, "Security Headers").iter().any(|e| e.starts_with("Strict-Transport-Security: not applicable")));
+        // Everything is plain HTTP already, nothing is mixed
+        assert!(rule_ids(&facts, "Page Content").iter().all(|id| id == "CNF-WEB-030"));
+    }
+
+    #[test]
+    fn cookie_flags() {
+        let headers = [
+            ("Set-Cookie", "session=abc123; Path=/"),
+            ("Set-Cookie", "tracking=xyz; SameSite=None"),
+            ("Set-Cookie", "good=1; Secure; HttpOnly; SameSite=Strict"),
+        ];
+        let facts = analyze_page(&page("https://example.com/", &headers, SECURE_HTML));
+        let cookies = findings(&facts, "Cookies");
+        let for_cookie = |name: &str| -> Vec<(&str, Severity)> {
+            cookies.iter().filter(|f| f.message.contains(&format!("'{}'", name))).map(|f| (f.rule_id.as_str(), f.severity)).collect()
+        };
+        assert_eq!(for_cookie("session"), [("CNF-WEB-010", Severity::Medium), ("CNF-WEB-011", Severity::Low), ("CNF-WEB-012", Severity::Low)]);
+        assert_eq!(for_cookie("tracking"), [("CNF-WEB-010", Severity::Medium), ("CNF-WEB-011", Severity::Low), ("CNF-WEB-012", Severity::Medium)]);
+        assert!(for_cookie("good").is_empty());
+        assert!(entries(&facts, "Cookies").contains(&"good: Secure, HttpOnly, SameSite=strict".to_string()));
+
+        // Without HTTPS a missing Secure flag is the least of the cookie's problems
+        let facts = analyze_page(&page("http://example.com/", &headers[..1], SECURE_HTML));
+        assert_eq!(findings(&facts, "Cookies")[0].severity, Severity::Low);
+    }
+
+    #[test]
+    fn mixed_content() {
+        let facts = analyze_page(&page("https://example.com/", SECURE_HEADERS, INSECURE_HTML));
+        let mut mixed: Vec<(String, Option<usize>)> = findings(&facts, "Page Content").iter()
+            .filter(|f| f.rule_id.starts_with("CNF-WEB-02"))
+            .map(|f| (f.rule_id.clone(), f.location.as_ref().and_then(|l| l.line)))
+            .collect();
+        mixed.sort();
+        // stylesheet, script, iframe; img; form. The commented-out script and the tag inside
+        // document.write() are not real elements
+        assert_eq!(mixed, [
+            ("CNF-WEB-020".to_string(), Some(6)),
+            ("CNF-WEB-020".to_string(), Some(7)),
+            ("CNF-WEB-020".to_string(), Some(18)),
+            ("CNF-WEB-021".to_string(), Some(14)),
+            ("CNF-WEB-022".to_string(), Some(15)),
+        ]);
+        assert!(!entries(&facts, "Page Content").iter().any(|e| e.contains("commented-out") || e.contains("not-a-tag")));
+
+        // Loopback subresources are trustworthy, https and relative ones are not mixed at all
+        let html = r#"<script src="http://localhost:3000/dev.js"></script><img src="/logo.png"><img src="https://example.com/a.png">"#;
+        let facts = analyze_page(&page("https://example.com/", SECURE_HEADERS, html));
+        assert!(!rule_ids(&facts, "Page Content").iter().any(|id| id.starts_with("CNF-WEB-02")));
+    }
+
+    #[test]
+    fn third_party_scripts() {
+        let facts = analyze_page(&page("https://example.com/", SECURE_HEADERS, INSECURE_HTML));
+        let content = facts.sections.iter().find(|s| s.title == "Page Content").unwrap();
+        assert_eq!(content.summary.as_deref(), Some("3 scripts (1 inline) from 2 third-party origins, 5 mixed-content references"));
+        let texts = entries(&facts, "Page Content");
+        assert!(texts.contains(&"http://cdn.example.com".to_string()) && texts.contains(&"https://analytics.example.net".to_string()), "{:?}", texts);
+        assert_eq!(rule_ids(&facts, "Page Content").iter().filter(|id| *id == "CNF-WEB-030").count(), 2);
+
+        // Same-origin scripts are not third-party, and an integrity attribute satisfies SRI
+        let facts = analyze_page(&page("https://example.com/", SECURE_HEADERS, SECURE_HTML));
+        let content = facts.sections.iter().find(|s| s.title == "Page Content").unwrap();
+        assert_eq!(content.summary.as_deref(), Some("2 scripts (0 inline) from 1 third-party origins, 0 mixed-content references"));
+    }
+
+    /// Serves one canned response on a loopback port and returns its URL.
+    fn serve_once(head: String, body: Vec<u8>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/sample", listener.local_addr().unwrap());
+        std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut request = [0u8; 4096];
+            let _ = stream.read(&mut request);
+            // The client may hang up once it has read enough
+            let _ = stream.write_all(head.as_bytes());
+            let _ = stream.write_all(&body);
+        });
+        url
+    }
+
+    #[test]
+    fn fetch_from_local_server() {
+        let body = INSECURE_HTML.as_bytes().to_vec();
+        let head = format!("HTTP/1.1 404 Not Found\r\nContent-Type: text/html\r\nContent-Length: {}\r\nSet-Cookie: session=abc123; Path=/\r\nConnection: close\r\n\r\n", body.len());
+        let url = serve_once(head, body);
+        let page = fetch_page(&url).unwrap();
+        assert_eq!(page.status, 404);
+        assert_eq!(page.url, url);
+        assert_eq!(page.body, INSECURE_HTML.as_bytes());
+        assert_eq!(header(&page, "set-cookie"), Some("session=abc123; Path=/"));
+
+        let facts = analyze_page(&page);
+        assert_eq!(rule_ids(&facts, "HTTP Response"), ["CNF-WEB-000"]);
+        assert_eq!(rule_ids(&facts, "Cookies"), ["CNF-WEB-010", "CNF-WEB-011", "CNF-WEB-012"]);
+    }
+
+    #[test]
+    fn large_bodies_are_truncated() {
+        let body = vec![b'a'; BODY_LIMIT as usize + 4096];
+        let head = format!("HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", body.len());
+        let page = fetch_page(&serve_once(head, body)).unwrap();
+        assert_eq!(page.body.len() as u64, BODY_LIMIT);
+    }
+}
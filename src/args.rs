@@ -6,16 +6,46 @@
     about = "A source-code, executable-binary, and web-url security information gathering and reporting utility."
 )]
 pub struct Args {
-    /// Data to be analyzed. Supported types of data are: Single Source-Code file, Single PE32, PE32+ .exe binary, single ELF binary, and a web HTTP/S URL.
+    /// Data to be analyzed. Supported types of data are: Single Source-Code file, a folder of source code and binaries, Single PE32, PE32+ .exe binary, single ELF binary, and a web HTTP/S URL.
     pub input: AnalysisInput,
 
-    /// File path where the output .pdf report will be placed. If none is provided, data is written to STDOUT.
+    /// File path where the output report will be placed. If none is provided, data is written to STDOUT.
     #[arg(short, long)]
     pub output_report: Option<std::path::PathBuf>,
 
+    /// Output Format. Valid OutputFormats are [text, pdf, json, sarif, html, markdown, cyclonedx, spdx, dot, graphml, callgraph-json]. Defaults to pdf when --output-report is given, otherwise text. cyclonedx, spdx, dot, graphml and callgraph-json describe a single binary and are rejected for folders.
+    #[arg(short, long)]
+    pub format: Option<OutputFormat>,
+
     /// Report Style. Valid ReportStyles are [t|terse, n|normal, d|detailed, o|overflowing]. Pass "--style help" to list all options.
     #[arg(short, long, default_value = "normal")]
     pub style: ReportStyle,
+
+    /// When analyzing a folder, also descend into vendored and build directories (vendor, third_party, node_modules, target, build, ...) which are skipped by default.
+    #[arg(long)]
+    pub include_vendored: bool,
+
+    /// Function to trace call paths to from a binary's entry point and exports, eg "--sink strcpy,system". Repeatable; replaces the default, every unsafe-string and command-execution function in the import catalogue.
+    #[arg(long = "sink", value_name = "FUNCTION", value_delimiter = ',')]
+    pub sinks: Vec<String>,
+
+    /// TOML file extending the built-in dangerous import catalogue: one [category] table per category, mapping function names to a risk note. An empty note removes a built-in entry.
+    #[arg(long, value_name = "FILE")]
+    pub import_catalogue: Option<std::path::PathBuf>,
+
+    /// Root directory of the system a binary belongs to, eg an extracted firmware image or container filesystem. ELF libraries, DT_RPATH/DT_RUNPATH, $ORIGIN, ld.so.conf and ld.so.cache are all resolved inside it; LD_LIBRARY_PATH is ignored.
+    #[arg(long, value_name = "DIR")]
+    pub sysroot: Option<std::path::PathBuf>,
+}
+
+impl Args {
+    pub fn output_format(&self) -> OutputFormat {
+        match (&self.format, &self.output_report) {
+            (Some(format), _) => format.clone(),
+            (None, Some(_)) => OutputFormat::Pdf,
+            (None, None) => OutputFormat::Text,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -52,21 +82,19 @@ impl std::str::FromStr for AnalysisInput {
         }
 
         // Paths: if the path parses and points to an existing item, prefer that as it's likely what the user intended.
-        if let Ok(ref path) = r_path {
-            if path.exists() {
-                return Ok(AnalysisInput::from(path.as_path()));
-            }
+        if let Ok(ref path) = r_path && path.exists() {
+            return Ok(AnalysisInput::from(path.as_path()));
         }
         
         // Regular parse hierarchy; most-detailed formats to least-detailed
         if let Ok(command) = r_command {
-            return Ok(AnalysisInput::Command(command));
+            Ok(AnalysisInput::Command(command))
         }
         else if let Ok(uri) = r_uri {
             Ok(AnalysisInput::Url(uri))
         }
         else if let Ok(path) = r_path {
-            return Ok(AnalysisInput::from(path));
+            Ok(AnalysisInput::from(path))
         }
         else {
             let mut all_err_msgs = String::with_capacity(1024);
@@ -132,6 +160,80 @@ impl std::convert::From<&std::path::Path> for AnalysisInput {
 
 
 #[derive(Debug, Clone, Eq, PartialEq)]
+pub enum OutputFormat {
+    Text,
+    Pdf,
+    Json,
+    Sarif,
+    Html,
+    Markdown,
+    CycloneDx,
+    Spdx,
+    /// Internal call graph as Graphviz DOT
+    Dot,
+    GraphMl,
+    /// Internal call graph as a JSON adjacency list
+    CallGraphJson,
+}
+
+impl OutputFormat {
+    /// Formats which list each library's hash.
+    pub fn is_sbom(&self) -> bool {
+        matches!(self, OutputFormat::CycloneDx | OutputFormat::Spdx)
+    }
+
+    /// SBOMs and call graphs describe exactly one binary, there is nothing to render them from for a folder.
+    pub fn is_per_binary(&self) -> bool {
+        matches!(self, OutputFormat::CycloneDx | OutputFormat::Spdx | OutputFormat::Dot | OutputFormat::GraphMl | OutputFormat::CallGraphJson)
+    }
+}
+
+impl std::str::FromStr for OutputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.to_lowercase();
+        if s == "text" || s == "txt" {
+            Ok(OutputFormat::Text)
+        }
+        else if s == "pdf" {
+            Ok(OutputFormat::Pdf)
+        }
+        else if s == "json" {
+            Ok(OutputFormat::Json)
+        }
+        else if s == "sarif" {
+            Ok(OutputFormat::Sarif)
+        }
+        else if s == "html" || s == "htm" {
+            Ok(OutputFormat::Html)
+        }
+        else if s == "markdown" || s == "md" {
+            Ok(OutputFormat::Markdown)
+        }
+        else if s == "cyclonedx" || s == "cdx" {
+            Ok(OutputFormat::CycloneDx)
+        }
+        else if s == "spdx" {
+            Ok(OutputFormat::Spdx)
+        }
+        else if s == "dot" || s == "gv" {
+            Ok(OutputFormat::Dot)
+        }
+        else if s == "graphml" {
+            Ok(OutputFormat::GraphMl)
+        }
+        else if s == "callgraph-json" || s == "callgraph" {
+            Ok(OutputFormat::CallGraphJson)
+        }
+        else {
+            Err(format!("Unknown OutputFormat: '{s}'. Valid OutputFormats are [text, pdf, json, sarif, html, markdown, cyclonedx, spdx, dot, graphml, callgraph-json]"))
+        }
+    }
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, serde::Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ReportStyle {
     Terse,
     Normal,
@@ -189,15 +291,15 @@ impl std::str::FromStr for ReportStyle {
 
             style_detail_lines.push_str("   - ");
             style_detail_lines.push_str(ReportStyle::Terse.named_description().as_str());
-            style_detail_lines.push_str("\n");
+            style_detail_lines.push('\n');
             style_detail_lines.push_str("   - ");
 
             style_detail_lines.push_str(ReportStyle::Normal.named_description().as_str());
-            style_detail_lines.push_str("\n");
+            style_detail_lines.push('\n');
 
             style_detail_lines.push_str("   - ");
             style_detail_lines.push_str(ReportStyle::Detailed.named_description().as_str());
-            style_detail_lines.push_str("\n");
+            style_detail_lines.push('\n');
 
             style_detail_lines.push_str("   - ");
             style_detail_lines.push_str(ReportStyle::Overflowing.named_description().as_str());
@@ -215,7 +317,7 @@ impl Ord for ReportStyle {
 }
 impl PartialOrd for ReportStyle {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        Some(self.cmp(&other))
+        Some(self.cmp(other))
     }
 }
 
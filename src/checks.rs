@@ -1,6 +1,6 @@
 
 // This list tracks the external tools that cyber-nutrition-facts relies on.
-const REQUIRED_BINS_DESCRIPTIONS: &[(&'static str, &'static str)] = &[
+const REQUIRED_BINS_DESCRIPTIONS: &[(&str, &str)] = &[
     ("qemu-system-x86_64", "This is used to run Docker within a Windows Userspace app"),
 ];
 
@@ -21,7 +21,7 @@ pub fn check_setup(args: &crate::args::Args) -> crate::DynResult<()> {
 }
 
 pub fn which_with_extensions(binary_name: &str) -> which::Result<std::path::PathBuf> {
-    const EXTS: &[&'static str] = &["", ".exe", ".com"];
+    const EXTS: &[&str] = &["", ".exe", ".com"];
     for ext in EXTS.iter() {
         if let Ok(binary_path) = which::which(format!("{}{}", binary_name, ext)) {
             return Ok(binary_path);
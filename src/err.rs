@@ -29,7 +29,7 @@ impl Error for WithLocationErr {
 #[macro_export]
 macro_rules! tracked_err {
     ($err:expr) => {
-        crate::err::WithLocationErr {
+        $crate::err::WithLocationErr {
             file: file!(),
             line: line!(),
             source: ($err).into(),
@@ -0,0 +1,187 @@
+
+use std::fmt::Write;
+
+use serde_json::{json, Map, Value};
+
+use super::*;
+
+/// Render the internal call graph as Graphviz DOT (`dot -Tsvg`).
+/// Roots are drawn with a double border, imports as grey ellipses, indirect calls dashed; calls
+/// whose target is unknown all point at one "(unresolved)" node.
+pub fn render_dot(facts: &NutritionFacts) -> crate::DynResult<String> {
+    let graph = call_graph(facts)?;
+    let mut out = String::with_capacity(4096);
+    let _ = writeln!(out, "digraph \"{}\" {{", dot_escape(&facts.provenance.input));
+    out.push_str("  node [shape=box, fontname=\"monospace\"];\n");
+
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let label: Vec<String> = node_label(node).iter().map(|l| dot_escape(l)).collect();
+        let mut attrs = vec![format!("label=\"{}\"", label.join("\\n"))];
+        if node.is_import() {
+            attrs.push("shape=ellipse, style=filled, fillcolor=lightgrey".into());
+        }
+        if graph.roots.contains(&i) {
+            attrs.push("peripheries=2".into());
+        }
+        let _ = writeln!(out, "  \"{}\" [{}];", dot_escape(&node.id()), attrs.join(", "));
+    }
+    if graph.edges.iter().any(|e| e.to.is_none()) {
+        out.push_str("  \"(unresolved)\" [shape=plaintext];\n");
+    }
+
+    for edge in graph.edges.iter() {
+        let to = edge.to.map(|t| graph.nodes[t].id()).unwrap_or_else(|| "(unresolved)".into());
+        let style = match edge.kind {
+            CallKind::Direct => "",
+            CallKind::Indirect => ", style=dashed",
+            CallKind::TailCall => ", style=bold",
+        };
+        let _ = writeln!(out, "  \"{}\" -> \"{}\" [label=\"0x{:x}\"{}];", dot_escape(&graph.nodes[edge.from].id()), dot_escape(&to), edge.call_site, style);
+    }
+    out.push_str("}\n");
+    Ok(out)
+}
+
+/// Render the internal call graph as GraphML, with node name/address/size/extent and edge
+/// call_site/kind as typed attributes.
+pub fn render_graphml(facts: &NutritionFacts) -> crate::DynResult<String> {
+    let graph = call_graph(facts)?;
+    let mut out = String::with_capacity(4096);
+    out.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    out.push_str("<graphml xmlns=\"http://graphml.graphdrawing.org/xmlns\" xmlns:xsi=\"http://www.w3.org/2001/XMLSchema-instance\" xsi:schemaLocation=\"http://graphml.graphdrawing.org/xmlns http://graphml.graphdrawing.org/xmlns/1.0/graphml.xsd\">\n");
+    for (id, domain, name, kind) in [
+        ("d0", "node", "name", "string"),
+        ("d1", "node", "address", "string"),
+        ("d2", "node", "size", "long"),
+        ("d3", "node", "extent", "string"),
+        ("d4", "node", "root", "boolean"),
+        ("d5", "edge", "call_site", "string"),
+        ("d6", "edge", "kind", "string"),
+    ] {
+        let _ = writeln!(out, "  <key id=\"{}\" for=\"{}\" attr.name=\"{}\" attr.type=\"{}\"/>", id, domain, name, kind);
+    }
+    let _ = writeln!(out, "  <graph id=\"{}\" edgedefault=\"directed\">", xml_escape(&facts.provenance.input));
+
+    for (i, node) in graph.nodes.iter().enumerate() {
+        let _ = writeln!(out, "    <node id=\"{}\">", xml_escape(&node.id()));
+        if let Some(name) = &node.name {
+            let _ = writeln!(out, "      <data key=\"d0\">{}</data>", xml_escape(name));
+        }
+        if let Some(address) = node.address {
+            let _ = writeln!(out, "      <data key=\"d1\">0x{:x}</data>", address);
+        }
+        if let Some(size) = node.size {
+            let _ = writeln!(out, "      <data key=\"d2\">{}</data>", size);
+        }
+        let _ = writeln!(out, "      <data key=\"d3\">{}</data>", xml_escape(&node.extent));
+        let _ = writeln!(out, "      <data key=\"d4\">{}</data>", graph.roots.contains(&i));
+        out.push_str("    </node>\n");
+    }
+    if graph.edges.iter().any(|e| e.to.is_none()) {
+        out.push_str("    <node id=\"(unresolved)\"/>\n");
+    }
+
+    for (i, edge) in graph.edges.iter().enumerate() {
+        let to = edge.to.map(|t| graph.nodes[t].id()).unwrap_or_else(|| "(unresolved)".into());
+        let _ = writeln!(out, "    <edge id=\"e{}\" source=\"{}\" target=\"{}\">", i, xml_escape(&graph.nodes[edge.from].id()), xml_escape(&to));
+        let _ = writeln!(out, "      <data key=\"d5\">0x{:x}</data>", edge.call_site);
+        let _ = writeln!(out, "      <data key=\"d6\">{}</data>", call_kind(edge.kind));
+        out.push_str("    </edge>\n");
+    }
+    out.push_str("  </graph>\n</graphml>\n");
+    Ok(out)
+}
+
+/// Render the internal call graph as JSON: node attributes keyed by id, plus an adjacency list
+/// mapping every node id to its outgoing calls. Unresolved indirect calls have `"to": null`.
+pub fn render_call_graph_json(facts: &NutritionFacts) -> crate::DynResult<String> {
+    let graph = call_graph(facts)?;
+
+    let mut nodes = Map::new();
+    let mut adjacency = Map::new();
+    for node in graph.nodes.iter() {
+        nodes.insert(node.id(), json!({
+            "name": node.name,
+            "address": node.address,
+            "size": node.size,
+            "extent": node.extent,
+            "import": node.is_import(),
+        }));
+        adjacency.insert(node.id(), json!([]));
+    }
+    for edge in graph.edges.iter() {
+        let call = json!({
+            "to": edge.to.map(|t| graph.nodes[t].id()),
+            "call_site": edge.call_site,
+            "kind": call_kind(edge.kind),
+        });
+        if let Some(Value::Array(calls)) = adjacency.get_mut(&graph.nodes[edge.from].id()) {
+            calls.push(call);
+        }
+    }
+
+    let doc = json!({
+        "input": facts.provenance.input,
+        "sha256": facts.provenance.sha256,
+        "roots": graph.roots.iter().map(|r| graph.nodes[*r].id()).collect::<Vec<_>>(),
+        "nodes": nodes,
+        "adjacency": adjacency,
+    });
+    let mut out = serde_json::to_string_pretty(&doc)?;
+    out.push('\n');
+    Ok(out)
+}
+
+/// The call graph of the report's call graph section.
+fn call_graph(facts: &NutritionFacts) -> crate::DynResult<&CallGraph> {
+    let found = facts.sections.iter().find_map(|s| match &s.data {
+        Some(SectionData::CallGraph(graph)) => Some(graph),
+        _ => None,
+    });
+    match found {
+        Some(graph) => Ok(graph),
+        None => Err(Box::new(crate::tracked_err!(format!("Call graph output needs a binary whose call graph could be built, {} has none", facts.provenance.input)))),
+    }
+}
+
+/// ["main", "0x1149", "52 bytes (st_size)"]
+fn node_label(node: &CallGraphNode) -> Vec<String> {
+    let mut lines = Vec::new();
+    if let Some(name) = &node.name {
+        lines.push(name.clone());
+    }
+    if let Some(address) = node.address {
+        lines.push(format!("0x{:x}", address));
+    }
+    if let Some(size) = node.size {
+        lines.push(format!("{} bytes ({})", size, node.extent));
+    }
+    lines
+}
+
+fn call_kind(kind: CallKind) -> &'static str {
+    match kind {
+        CallKind::Direct => "direct",
+        CallKind::Indirect => "indirect",
+        CallKind::TailCall => "tail_call",
+    }
+}
+
+fn dot_escape(text: &str) -> String {
+    text.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn xml_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&apos;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
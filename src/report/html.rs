@@ -0,0 +1,195 @@
+
+use std::fmt::Write;
+
+use super::*;
+
+// Everything is inlined so the report can be mailed around and opened offline.
+const HTML_CSS: &str = r#"
+body { font-family: Helvetica, Arial, sans-serif; margin: 2em auto; max-width: 60em; color: #111; }
+.label { border: 2px solid #000; padding: 0.4em 0.8em; width: 26em; margin-bottom: 2em; }
+.label h1 { font-size: 2.4em; margin: 0; font-weight: 900; }
+.label .pair { display: flex; justify-content: space-between; gap: 1em; }
+.label .pair .value { overflow: hidden; text-overflow: ellipsis; white-space: nowrap; direction: rtl; }
+.label .bold { font-weight: bold; }
+.label hr { border: 0; border-top: 1px solid #000; margin: 0.2em 0; }
+.label hr.medium { border-top-width: 4px; }
+.label hr.thick { border-top-width: 10px; }
+.label .bar { height: 0.5em; background: #ddd; }
+.label .bar div { height: 100%; background: #333; }
+.label .footnote { font-size: 0.75em; }
+.toolbar { margin-bottom: 1em; }
+.toolbar button.active { font-weight: bold; }
+section { margin-bottom: 1.5em; }
+section h2 { border-bottom: 2px solid #000; }
+.missing { color: #a00; font-weight: bold; }
+ul.tree { list-style: none; padding-left: 1.2em; font-family: monospace; }
+ul.tree summary { cursor: pointer; }
+table { border-collapse: collapse; }
+td, th { border: 1px solid #ccc; padding: 0.2em 0.5em; text-align: left; }
+.sev-critical, .sev-high { color: #a00; font-weight: bold; }
+.sev-medium { color: #a60; }
+[hidden] { display: none !important; }
+"#;
+
+const HTML_JS: &str = r#"
+function setStyle(level) {
+  document.querySelectorAll('[data-detail]').forEach(function (el) {
+    el.hidden = parseInt(el.dataset.detail, 10) > level;
+  });
+  document.querySelectorAll('details[data-open-at]').forEach(function (el) {
+    el.open = parseInt(el.dataset.openAt, 10) <= level;
+  });
+  document.querySelectorAll('.toolbar button').forEach(function (b) {
+    b.classList.toggle('active', parseInt(b.dataset.level, 10) === level);
+  });
+}
+document.addEventListener('DOMContentLoaded', function () {
+  setStyle(parseInt(document.body.dataset.style, 10));
+});
+"#;
+
+/// Render `facts` as a single self-contained HTML page.
+/// The page contains everything up to Overflowing; `style` only picks what starts out expanded,
+/// and the toolbar switches between styles without re-running the analysis.
+pub fn render_html(facts: &NutritionFacts, style: &ReportStyle) -> String {
+    let label = Label::from_facts(facts);
+    let mut out = String::with_capacity(16 * 1024);
+    let title = format!("Cyber Nutrition Facts: {}", facts.provenance.input);
+
+    let _ = writeln!(out, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">");
+    let _ = writeln!(out, "<title>{}</title>", escape(&title));
+    let _ = writeln!(out, "<style>{}</style>\n<script>{}</script>\n</head>", HTML_CSS, HTML_JS);
+    let _ = writeln!(out, "<body data-style=\"{}\">", style.enum_index());
+
+    write_label(&mut out, &label);
+
+    out.push_str("<div class=\"toolbar\">Detail: ");
+    for s in [ReportStyle::Terse, ReportStyle::Normal, ReportStyle::Detailed, ReportStyle::Overflowing] {
+        let _ = write!(out, "<button data-level=\"{0}\" onclick=\"setStyle({0})\">{1:?}</button> ", s.enum_index(), s);
+    }
+    out.push_str("</div>\n");
+
+    let p = &facts.provenance;
+    let _ = write!(out, "<p data-detail=\"{}\">Input <code>{}</code>, analyzer {}, cyber-nutrition-facts {}",
+        ReportStyle::Detailed.enum_index(), escape(&p.input), escape(&p.analyzer), escape(&p.tool_version));
+    if let Some(sha256) = &p.sha256 {
+        let _ = write!(out, "<br>SHA-256 <code>{}</code>", sha256);
+    }
+    out.push_str("</p>\n");
+
+    for section in facts.sections.iter() {
+        write_section(&mut out, section);
+    }
+
+    out.push_str("</body>\n</html>\n");
+    out
+}
+
+fn write_label(out: &mut String, label: &Label) {
+    out.push_str("<div class=\"label\">\n");
+    for row in label.rows.iter() {
+        match row {
+            LabelRow::Title(title) => {
+                let _ = writeln!(out, "<h1>{}</h1>", escape(title));
+            }
+            LabelRow::Rule(weight) => {
+                let class = match weight {
+                    RuleWeight::Thin => "thin",
+                    RuleWeight::Medium => "medium",
+                    RuleWeight::Thick => "thick",
+                };
+                let _ = writeln!(out, "<hr class=\"{}\">", class);
+            }
+            LabelRow::Pair { name, value, bold } => {
+                let _ = writeln!(out, "<div class=\"pair{}\"><span>{}</span><span class=\"value\" title=\"{2}\">{2}</span></div>",
+                    if *bold { " bold" } else { "" }, escape(name), escape(value));
+            }
+            LabelRow::Bar { name, note, percent } => {
+                let _ = writeln!(out, "<div class=\"pair\"><span><b>{}</b> {}</span><b>{}%</b></div>", escape(name), escape(note), percent);
+                let _ = writeln!(out, "<div class=\"bar\"><div style=\"width: {}%\"></div></div>", (*percent).min(100));
+            }
+            LabelRow::Footnote(text) => {
+                let _ = writeln!(out, "<div class=\"footnote\">{}</div>", escape(text));
+            }
+        }
+    }
+    out.push_str("</div>\n");
+}
+
+fn write_section(out: &mut String, section: &Section) {
+    // Terse omits missing data entirely, so an unavailable section starts at Normal
+    let section_detail = match section.status {
+        SectionStatus::Available => section.detail.clone(),
+        SectionStatus::Unavailable(_) => section.detail.clone().max(ReportStyle::Normal),
+    };
+    let _ = writeln!(out, "<section data-detail=\"{}\">\n<h2>{}</h2>", section_detail.enum_index(), escape(&section.title));
+    if let SectionStatus::Unavailable(reason) = &section.status {
+        let _ = writeln!(out, "<p class=\"missing\">MISSING/UNKNOWN: {}</p>", escape(reason));
+    }
+    if let Some(summary) = &section.summary {
+        let _ = writeln!(out, "<p>{}</p>", escape(summary));
+    }
+
+    if !section.entries.is_empty() {
+        out.push_str("<ul class=\"tree\">\n");
+        for entry in section.entries.iter() {
+            write_entry(out, entry, true);
+        }
+        out.push_str("</ul>\n");
+    }
+
+    if !section.metrics.is_empty() {
+        out.push_str("<table>\n<tr><th>Metric</th><th>Value</th></tr>\n");
+        for metric in section.metrics.iter() {
+            let _ = writeln!(out, "<tr data-detail=\"{}\"><td>{}</td><td>{:.2}</td></tr>",
+                metric.detail.enum_index(), escape(&metric.name), metric.value);
+        }
+        out.push_str("</table>\n");
+    }
+
+    if !section.findings.is_empty() {
+        out.push_str("<table>\n<tr><th>Severity</th><th>Rule</th><th>Finding</th><th>Location</th></tr>\n");
+        for finding in section.findings.iter() {
+            let location = finding.location.as_ref().map(|l| l.describe()).unwrap_or_default();
+            let _ = writeln!(out, "<tr data-detail=\"{}\"><td class=\"sev-{}\">{}</td><td>{}</td><td><b>{}</b>: {}</td><td><code>{}</code></td></tr>",
+                finding.detail.enum_index(), finding.severity.as_str().to_lowercase(), finding.severity.as_str(),
+                escape(&finding.rule_id), escape(&finding.title), escape(&finding.message), escape(&location));
+        }
+        out.push_str("</table>\n");
+    }
+
+    out.push_str("</section>\n");
+}
+
+/// Entries with children become <details>, open when the first child is visible at the page's style.
+/// Only top-level entries are hidden by style; anything nested stays reachable by expanding its parent.
+fn write_entry(out: &mut String, entry: &Entry, top_level: bool) {
+    let detail = entry.detail.enum_index();
+    let hide = if top_level { format!(" data-detail=\"{}\"", detail) } else { String::new() };
+    if entry.children.is_empty() {
+        let _ = writeln!(out, "<li{}>{}</li>", hide, escape(&entry.text));
+        return;
+    }
+    let open_at = entry.children.iter().map(|c| c.detail.enum_index()).min().unwrap_or(detail);
+    let _ = writeln!(out, "<li{}><details data-open-at=\"{}\"><summary>{}</summary>\n<ul class=\"tree\">",
+        hide, open_at, escape(&entry.text));
+    for child in entry.children.iter() {
+        write_entry(out, child, false);
+    }
+    out.push_str("</ul></details></li>\n");
+}
+
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\'' => out.push_str("&#39;"),
+            _ => out.push(c),
+        }
+    }
+    out
+}
@@ -0,0 +1,317 @@
+
+use serde::Serialize;
+
+use super::*;
+
+/// Version of schemas/report.schema.json which `render_json` output conforms to.
+/// Bump the major version (and JSON_SCHEMA_ID) for any change which removes or retypes a field,
+/// the minor version when adding fields.
+pub const JSON_SCHEMA_VERSION: &str = "1.0.0";
+pub const JSON_SCHEMA_ID: &str = "cyber-nutrition-facts/report/v1";
+
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    schema: &'static str,
+    schema_version: &'static str,
+    style: &'a ReportStyle,
+    provenance: &'a Provenance,
+    label: JsonLabel,
+    sections: &'a [Section],
+}
+
+#[derive(Serialize)]
+struct JsonLabel {
+    calories: u32,
+    daily_value_budget: u32,
+    categories: Vec<JsonCategoryScore>,
+    /// Catalogue category -> number of risky imports
+    dangerous_imports: BTreeMap<String, usize>,
+}
+
+#[derive(Serialize)]
+struct JsonCategoryScore {
+    category: Category,
+    points: u32,
+    findings: usize,
+    percent_daily_value: u32,
+}
+
+/// Render `facts` as a JSON document described by schemas/report.schema.json.
+/// Entries, findings and metrics honor `style`; section `data` payloads are always complete.
+pub fn render_json(facts: &NutritionFacts, style: &ReportStyle) -> crate::DynResult<String> {
+    let scores = RiskScores::from_facts(facts);
+    let filtered = facts.filtered(style);
+    let report = JsonReport {
+        schema: JSON_SCHEMA_ID,
+        schema_version: JSON_SCHEMA_VERSION,
+        style,
+        provenance: &filtered.provenance,
+        label: JsonLabel {
+            calories: scores.calories,
+            daily_value_budget: DAILY_VALUE_BUDGET,
+            categories: scores.categories.iter().map(|c| JsonCategoryScore {
+                category: c.category,
+                points: c.points,
+                findings: c.findings,
+                percent_daily_value: c.percent_daily_value(),
+            }).collect(),
+            dangerous_imports: facts.dangerous_import_counts(),
+        },
+        sections: &filtered.sections,
+    };
+    let mut out = serde_json::to_string_pretty(&report)?;
+    out.push('\n');
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{Value, json};
+
+    use super::*;
+
+    const SCHEMA: &str = include_str!("../../schemas/report.schema.json");
+
+    /// Checks `value` against the subset of JSON Schema that schemas/report.schema.json uses
+    /// (type, const, enum, pattern, required, properties, additionalProperties, items, minItems,
+    /// minimum, oneOf and local $refs), collecting a message per violation.
+    fn validate(root: &Value, schema: &Value, value: &Value, at: &str, errors: &mut Vec<String>) {
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let name = reference.trim_start_matches("#/$defs/");
+            return validate(root, &root["$defs"][name], value, at, errors);
+        }
+        if let Some(types) = schema.get("type") {
+            let types: Vec<&str> = match types {
+                Value::String(t) => vec![t.as_str()],
+                Value::Array(ts) => ts.iter().filter_map(Value::as_str).collect(),
+                _ => Vec::new(),
+            };
+            let matches = types.iter().any(|t| match *t {
+                "object" => value.is_object(),
+                "array" => value.is_array(),
+                "string" => value.is_string(),
+                "integer" => value.is_u64() || value.is_i64(),
+                "number" => value.is_number(),
+                "boolean" => value.is_boolean(),
+                "null" => value.is_null(),
+                _ => false,
+            });
+            if !matches {
+                errors.push(format!("{}: {} is not of type {:?}", at, value, types));
+                return;
+            }
+        }
+        if let Some(expected) = schema.get("const") && value != expected {
+            errors.push(format!("{}: {} is not {}", at, value, expected));
+        }
+        if let Some(options) = schema.get("enum").and_then(Value::as_array) && !options.contains(value) {
+            errors.push(format!("{}: {} is not one of {:?}", at, value, options));
+        }
+        if let Some(pattern) = schema.get("pattern").and_then(Value::as_str)
+            && let Some(text) = value.as_str()
+            && !regex::Regex::new(pattern).expect("schema patterns are valid regexes").is_match(text) {
+            errors.push(format!("{}: {:?} does not match {}", at, text, pattern));
+        }
+        if let Some(minimum) = schema.get("minimum").and_then(Value::as_f64)
+            && let Some(number) = value.as_f64()
+            && number < minimum {
+            errors.push(format!("{}: {} is below {}", at, number, minimum));
+        }
+        if let Some(variants) = schema.get("oneOf").and_then(Value::as_array) {
+            let passing = variants.iter().filter(|variant| {
+                let mut variant_errors = Vec::new();
+                validate(root, variant, value, at, &mut variant_errors);
+                variant_errors.is_empty()
+            }).count();
+            if passing != 1 {
+                errors.push(format!("{}: matches {} of the oneOf variants", at, passing));
+            }
+        }
+        if let Some(object) = value.as_object() {
+            for name in schema.get("required").and_then(Value::as_array).into_iter().flatten().filter_map(Value::as_str) {
+                if !object.contains_key(name) {
+                    errors.push(format!("{}: missing required field {}", at, name));
+                }
+            }
+            let properties = schema.get("properties").and_then(Value::as_object);
+            for (name, field) in object.iter() {
+                match properties.and_then(|p| p.get(name)) {
+                    Some(property) => validate(root, property, field, &format!("{}.{}", at, name), errors),
+                    None => if let Some(additional) = schema.get("additionalProperties") && additional.is_object() {
+                        validate(root, additional, field, &format!("{}.{}", at, name), errors);
+                    },
+                }
+            }
+        }
+        if let Some(array) = value.as_array() {
+            if let Some(min) = schema.get("minItems").and_then(Value::as_u64) && (array.len() as u64) < min {
+                errors.push(format!("{}: fewer than {} items", at, min));
+            }
+            if let Some(items) = schema.get("items") {
+                for (i, item) in array.iter().enumerate() {
+                    validate(root, items, item, &format!("{}[{}]", at, i), errors);
+                }
+            }
+        }
+    }
+
+    fn schema_errors(rendered: &str) -> Vec<String> {
+        let schema: Value = serde_json::from_str(SCHEMA).expect("schema is valid JSON");
+        let report: Value = serde_json::from_str(rendered).expect("report is valid JSON");
+        let mut errors = Vec::new();
+        validate(&schema, &schema, &report, "$", &mut errors);
+        errors
+    }
+
+    /// A report with one section per SectionData kind, so every payload goes through the schema.
+    fn sample_facts() -> NutritionFacts {
+        let mut facts = NutritionFacts::new(Provenance {
+            input: "/usr/bin/sample".into(),
+            analyzer: "single_binary".into(),
+            mime: Some("application/x-executable".into()),
+            file_size: Some(4096),
+            sha256: Some("0".repeat(64)),
+            tool_version: env!("CARGO_PKG_VERSION").into(),
+        });
+
+        let mut libraries = Section::new("Shared Libraries");
+        libraries.summary = Some("1 libraries referenced".into());
+        let mut entry = Entry::new("libc.so.6 => /lib/libc.so.6 (ld.so.cache)", ReportStyle::Normal);
+        entry.push(Entry::new("strcpy (GLIBC_2.2.5)", ReportStyle::Detailed));
+        libraries.entries.push(entry);
+        libraries.findings.push(Finding {
+            rule_id: "CNF-LINK-001".into(),
+            title: "Unresolved imports".into(),
+            message: "1 imported symbols are not exported by any library in the load order".into(),
+            severity: Severity::Medium,
+            category: Category::Dependencies,
+            location: Some(Location { file: "/usr/bin/sample".into(), address: Some(0x1149), ..Default::default() }),
+            detail: ReportStyle::Terse,
+        });
+        libraries.metrics.push(Metric { name: "libraries".into(), value: 1.0, detail: ReportStyle::Normal });
+        libraries.data = Some(SectionData::SharedLibraries {
+            libraries: vec!["libc.so.6".into()],
+            functions: BTreeMap::from([("libc.so.6".into(), vec!["strcpy".into()])]),
+            symbols_not_found: vec!["helper".into()],
+            unresolved_symbols: vec![UnresolvedSymbol {
+                name: "helper".into(),
+                binding: "global".into(),
+                symbol_type: "func".into(),
+                optional: None,
+                fatal: true,
+            }],
+            libraries_not_found: Vec::new(),
+            components: vec![LibraryComponent {
+                name: "libc.so.6".into(),
+                path: Some("/lib/libc.so.6".into()),
+                sha256: None,
+                version: None,
+                depends_on: Vec::new(),
+            }],
+            required_versions: BTreeMap::from([("libc.so.6".into(), vec!["GLIBC_2.2.5".into()])]),
+        });
+        facts.push(libraries);
+
+        let mut metrics = Section::new("Code Metrics");
+        metrics.data = Some(SectionData::CodeMetrics { spaces: json!({ "name": "sample.c", "spaces": [] }) });
+        facts.push(metrics);
+
+        let mut graph = Section::new("Internal Function Call Graph");
+        graph.data = Some(SectionData::CallGraph(CallGraph {
+            nodes: vec![
+                CallGraphNode { name: Some("main".into()), address: Some(0x1149), size: Some(32), extent: "st_size".into() },
+                CallGraphNode { name: Some("strcpy@libc.so.6".into()), address: None, size: None, extent: "import".into() },
+            ],
+            edges: vec![
+                CallGraphEdge { from: 0, to: Some(1), call_site: 0x1150, kind: CallKind::Direct },
+                CallGraphEdge { from: 0, to: None, call_site: 0x1158, kind: CallKind::Indirect },
+            ],
+            roots: vec![0],
+        }));
+        facts.push(graph);
+
+        let mut imports = Section::new("Dangerous Imports");
+        imports.data = Some(SectionData::DangerousImports {
+            categories: BTreeMap::from([("unsafe-string".into(), vec!["strcpy".into()])]),
+        });
+        facts.push(imports);
+
+        let mut tree = Section::new("Dependency Tree");
+        tree.data = Some(SectionData::DependencyTree {
+            libraries: vec![DependencyNode {
+                soname: "libc.so.6".into(),
+                path: Some("/lib/libc.so.6".into()),
+                found_by: Some("ld.so.cache".into()),
+                needed_by: vec!["sample".into()],
+                needed: vec!["ld-linux-x86-64.so.2".into()],
+                hardening: Some("full RELRO, PIC shared object, NX, canary, CET".into()),
+                dangerous_imports: BTreeMap::new(),
+                risk_points: 0,
+            }],
+        });
+        facts.push(tree);
+
+        let mut interposition = Section::new("Symbol Interposition");
+        interposition.data = Some(SectionData::SymbolInterposition {
+            symbols: vec![InterposedSymbol {
+                symbol: "malloc".into(),
+                providers: vec!["libmymalloc.so".into(), "libc.so.6".into()],
+                sensitive: true,
+            }],
+        });
+        facts.push(interposition);
+
+        let mut file = Section::new("src/helper.so");
+        file.data = Some(SectionData::FileReport {
+            sections: BTreeMap::from([(
+                "Dangerous Imports".into(),
+                SectionData::DangerousImports { categories: BTreeMap::from([("unsafe-string".into(), vec!["sprintf".into()])]) },
+            )]),
+        });
+        facts.push(file);
+
+        facts.push(Section::unavailable("Source Code Metrics", "Not a source file"));
+        facts
+    }
+
+    #[test]
+    fn report_matches_schema_at_every_style() {
+        let facts = sample_facts();
+        for style in [ReportStyle::Terse, ReportStyle::Normal, ReportStyle::Detailed, ReportStyle::Overflowing] {
+            let rendered = render_json(&facts, &style).unwrap();
+            let errors = schema_errors(&rendered);
+            assert!(errors.is_empty(), "{:?} report violates the schema:\n{}", style, errors.join("\n"));
+        }
+    }
+
+    #[test]
+    fn report_round_trips_with_schema_version() {
+        let facts = sample_facts();
+        let report: Value = serde_json::from_str(&render_json(&facts, &ReportStyle::Detailed).unwrap()).unwrap();
+        assert_eq!(report["schema"], JSON_SCHEMA_ID);
+        assert_eq!(report["schema_version"], JSON_SCHEMA_VERSION);
+        let schema: Value = serde_json::from_str(SCHEMA).unwrap();
+        assert_eq!(schema["$id"], JSON_SCHEMA_ID);
+
+        let sections = report["sections"].as_array().unwrap();
+        assert_eq!(sections.len(), facts.sections.len());
+        let kinds: Vec<&str> = sections.iter().filter_map(|s| s["data"]["kind"].as_str()).collect();
+        assert_eq!(kinds, ["shared_libraries", "code_metrics", "call_graph", "dangerous_imports", "dependency_tree", "symbol_interposition", "file_report"]);
+        assert_eq!(sections[0]["findings"][0]["rule_id"], "CNF-LINK-001");
+        // One from the binary's own section, one from the folded file's
+        assert_eq!(report["label"]["dangerous_imports"]["unsafe-string"], 2);
+    }
+
+    #[test]
+    fn validator_rejects_missing_fields() {
+        let facts = sample_facts();
+        let mut report: Value = serde_json::from_str(&render_json(&facts, &ReportStyle::Normal).unwrap()).unwrap();
+        report["sections"][0]["data"].as_object_mut().unwrap().remove("libraries");
+        report.as_object_mut().unwrap().remove("label");
+        report["provenance"]["sha256"] = json!("not a hash");
+        let errors = schema_errors(&report.to_string());
+        assert!(errors.iter().any(|e| e.contains("missing required field label")), "{:?}", errors);
+        assert!(errors.iter().any(|e| e.starts_with("$.provenance.sha256") && e.contains("does not match")), "{:?}", errors);
+        assert!(errors.iter().any(|e| e.starts_with("$.sections[0].data")), "{:?}", errors);
+    }
+}
@@ -0,0 +1,298 @@
+
+// The "Nutrition Facts" label.
+// Label::from_facts() scores a report and produces a backend-independent list of rows;
+// render_label_text() draws it for terminals and pdf.rs draws the same rows onto page one.
+
+use std::fmt::Write;
+
+use super::*;
+
+/// Each category has a budget of this many risk points; spending all of it is 100% daily value.
+pub const DAILY_VALUE_BUDGET: u32 = 100;
+
+/// Categories shown as "% Daily Value" rows, top to bottom.
+pub const LABEL_CATEGORIES: &[Category] = &[
+    Category::MemorySafety,
+    Category::Hardening,
+    Category::Dependencies,
+    Category::Complexity,
+];
+
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub rows: Vec<LabelRow>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum LabelRow {
+    Title(String),
+    Rule(RuleWeight),
+    /// Left-aligned name, right-aligned value
+    Pair { name: String, value: String, bold: bool },
+    /// A "% Daily Value" line; `percent` may exceed 100
+    Bar { name: String, note: String, percent: u32 },
+    Footnote(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RuleWeight {
+    Thin,
+    Medium,
+    Thick,
+}
+
+/// Risk points per category plus the overall "calories".
+#[derive(Debug, Clone, Default)]
+pub struct RiskScores {
+    pub calories: u32,
+    pub categories: Vec<CategoryScore>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CategoryScore {
+    pub category: Category,
+    pub points: u32,
+    pub findings: usize,
+}
+
+impl Severity {
+    /// Risk points one finding of this severity costs.
+    pub fn risk_points(&self) -> u32 {
+        match self {
+            Severity::Info => 0,
+            Severity::Low => 5,
+            Severity::Medium => 15,
+            Severity::High => 30,
+            Severity::Critical => 50,
+        }
+    }
+}
+
+impl CategoryScore {
+    pub fn percent_daily_value(&self) -> u32 {
+        self.points * 100 / DAILY_VALUE_BUDGET
+    }
+}
+
+impl RiskScores {
+    /// Scores every finding in `facts`, regardless of the style it will be rendered at.
+    pub fn from_facts(facts: &NutritionFacts) -> RiskScores {
+        let mut categories: Vec<CategoryScore> = LABEL_CATEGORIES.iter()
+            .map(|c| CategoryScore { category: *c, points: 0, findings: 0 })
+            .collect();
+        let mut calories = 0;
+        for finding in facts.findings() {
+            let points = finding.severity.risk_points();
+            calories += points;
+            if let Some(score) = categories.iter_mut().find(|s| s.category == finding.category) {
+                score.points += points;
+                score.findings += 1;
+            }
+        }
+
+        // Source complexity has no findings of its own; charge cyclomatic complexity per 100 LOC.
+        if let (Some(sloc), Some(cyclomatic)) = (facts.metric("sloc"), facts.metric("cyclomatic_sum"))
+            && sloc > 0.0 {
+            let points = (cyclomatic * 100.0 / sloc).round() as u32;
+            calories += points;
+            if let Some(score) = categories.iter_mut().find(|s| s.category == Category::Complexity) {
+                score.points += points;
+            }
+        }
+
+        RiskScores { calories, categories }
+    }
+}
+
+impl Label {
+    pub fn from_facts(facts: &NutritionFacts) -> Label {
+        let scores = RiskScores::from_facts(facts);
+        let mut rows = vec![
+            LabelRow::Title("Nutrition Facts".into()),
+            LabelRow::Pair { name: "Input".into(), value: facts.provenance.input.clone(), bold: false },
+        ];
+        if let Some(serving) = serving_size(facts) {
+            rows.push(LabelRow::Pair { name: "Serving size".into(), value: serving, bold: true });
+        }
+        rows.push(LabelRow::Rule(RuleWeight::Thick));
+        rows.push(LabelRow::Pair { name: "Calories".into(), value: scores.calories.to_string(), bold: true });
+        rows.push(LabelRow::Rule(RuleWeight::Medium));
+        rows.push(LabelRow::Pair { name: String::new(), value: "% Daily Value*".into(), bold: true });
+        for score in scores.categories.iter() {
+            rows.push(LabelRow::Rule(RuleWeight::Thin));
+            rows.push(LabelRow::Bar {
+                name: score.category.as_str().into(),
+                note: format!("{} findings", score.findings),
+                percent: score.percent_daily_value(),
+            });
+        }
+        let imports = facts.dangerous_import_counts();
+        if !imports.is_empty() {
+            rows.push(LabelRow::Rule(RuleWeight::Medium));
+            rows.push(LabelRow::Pair { name: "Dangerous imports".into(), value: imports.values().sum::<usize>().to_string(), bold: true });
+            for (category, count) in imports.iter() {
+                rows.push(LabelRow::Rule(RuleWeight::Thin));
+                rows.push(LabelRow::Pair { name: format!("  {}", category), value: count.to_string(), bold: false });
+            }
+        }
+        rows.push(LabelRow::Rule(RuleWeight::Thick));
+        rows.push(LabelRow::Footnote(format!(
+            "* The % Daily Value is the share of a {} point risk budget per category a serving of this input spends.",
+            DAILY_VALUE_BUDGET
+        )));
+        Label { rows }
+    }
+}
+
+/// File size for binaries, lines of code for sources.
+fn serving_size(facts: &NutritionFacts) -> Option<String> {
+    match (facts.metric("sloc"), facts.provenance.file_size) {
+        (Some(sloc), Some(size)) => Some(format!("{} lines ({} bytes)", sloc, size)),
+        (Some(sloc), None) => Some(format!("{} lines", sloc)),
+        (None, Some(size)) => Some(format!("{} bytes", size)),
+        (None, None) => None,
+    }
+}
+
+impl NutritionFacts {
+    /// First metric named `name` in any section.
+    pub fn metric(&self, name: &str) -> Option<f64> {
+        self.sections.iter()
+            .flat_map(|s| s.metrics.iter())
+            .find(|m| m.name == name)
+            .map(|m| m.value)
+    }
+
+    /// Catalogue category -> number of imports in it, over every Dangerous Imports section,
+    /// including those of the files in a folder report.
+    pub fn dangerous_import_counts(&self) -> BTreeMap<String, usize> {
+        fn count(data: &SectionData, counts: &mut BTreeMap<String, usize>) {
+            match data {
+                SectionData::DangerousImports { categories } => {
+                    for (category, functions) in categories.iter() {
+                        *counts.entry(category.clone()).or_default() += functions.len();
+                    }
+                }
+                SectionData::FileReport { sections } => sections.values().for_each(|inner| count(inner, counts)),
+                _ => {}
+            }
+        }
+        let mut counts: BTreeMap<String, usize> = BTreeMap::new();
+        for data in self.sections.iter().filter_map(|s| s.data.as_ref()) {
+            count(data, &mut counts);
+        }
+        counts
+    }
+}
+
+const LABEL_WIDTH: usize = 48;
+const BAR_WIDTH: usize = 10;
+
+/// Draws `label` inside a box of box-drawing characters.
+/// With `ansi` set the title and bold pairs are emboldened and bars are colored by severity.
+pub fn render_label_text(label: &Label, ansi: bool) -> String {
+    let inner = LABEL_WIDTH - 4;
+    let bold = |s: &str| if ansi { format!("\x1b[1m{}\x1b[0m", s) } else { s.to_string() };
+    let mut out = String::with_capacity(2048);
+    let _ = writeln!(out, "┌{}┐", "─".repeat(LABEL_WIDTH - 2));
+    for row in label.rows.iter() {
+        match row {
+            LabelRow::Title(title) => {
+                let _ = writeln!(out, "│ {} │", bold(&pad(title, inner)));
+            }
+            LabelRow::Rule(weight) => {
+                let c = match weight {
+                    RuleWeight::Thin => "─",
+                    RuleWeight::Medium => "━",
+                    RuleWeight::Thick => "█",
+                };
+                let _ = writeln!(out, "│ {} │", c.repeat(inner));
+            }
+            LabelRow::Pair { name, value, bold: is_bold } => {
+                let room = inner.saturating_sub(name.chars().count() + 1);
+                let value = truncate_left(value, room);
+                let line = format!("{} {:>room$}", name, value, room = room);
+                let line = if *is_bold { bold(&line) } else { line };
+                let _ = writeln!(out, "│ {} │", line);
+            }
+            LabelRow::Bar { name, note, percent } => {
+                let filled = (*percent as usize).min(100) * BAR_WIDTH / 100;
+                let bar = format!("{}{}", "■".repeat(filled), "·".repeat(BAR_WIDTH - filled));
+                let bar = if ansi { format!("{}{}\x1b[0m", percent_color(*percent), bar) } else { bar };
+                let left = format!("{} {}", name, note);
+                let pct = format!("{:>4}", format!("{}%", percent));
+                let room = inner.saturating_sub(BAR_WIDTH + pct.len() + 2);
+                let _ = writeln!(out, "│ {} {} {} │", pad(&left, room), bar, pct);
+            }
+            LabelRow::Footnote(text) => {
+                for line in word_wrap(text, inner) {
+                    let _ = writeln!(out, "│ {} │", pad(&line, inner));
+                }
+            }
+        }
+    }
+    let _ = writeln!(out, "└{}┘", "─".repeat(LABEL_WIDTH - 2));
+    out
+}
+
+fn percent_color(percent: u32) -> &'static str {
+    match percent {
+        0..=24 => "\x1b[32m",
+        25..=74 => "\x1b[33m",
+        _ => "\x1b[31m",
+    }
+}
+
+fn pad(s: &str, width: usize) -> String {
+    let s: String = s.chars().take(width).collect();
+    format!("{:<width$}", s, width = width)
+}
+
+/// Long values are usually paths; keep the end, which is the informative part.
+/// Widths too narrow for the ellipsis just cut the end off.
+fn truncate_left(s: &str, width: usize) -> String {
+    let n = s.chars().count();
+    if n <= width {
+        s.to_string()
+    }
+    else if width < 3 {
+        s.chars().skip(n - width).collect()
+    }
+    else {
+        let keep: String = s.chars().skip(n - width + 3).collect();
+        format!("...{}", keep)
+    }
+}
+
+pub(super) fn word_wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        if !line.is_empty() && line.chars().count() + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    if !line.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncate_left_never_exceeds_width() {
+        assert_eq!(truncate_left("/usr/lib/libc.so.6", 30), "/usr/lib/libc.so.6");
+        assert_eq!(truncate_left("/usr/lib/libc.so.6", 10), "...bc.so.6");
+        for width in 0..5 {
+            assert!(truncate_left("/usr/lib/libc.so.6", width).chars().count() <= width, "width {}", width);
+        }
+        assert_eq!(truncate_left("abcdef", 2), "ef");
+    }
+}
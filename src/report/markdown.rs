@@ -0,0 +1,217 @@
+
+use std::fmt::Write;
+
+use super::*;
+
+/// Render `facts` as GitHub-flavored Markdown for pull-request comments and wikis.
+/// Everything up to Normal is inline; anything only visible at Detailed/Overflowing goes
+/// into collapsed `<details>` blocks so the comment stays short.
+/// The output contains no timestamps and lists libraries/symbols sorted, so two runs diff cleanly.
+pub fn render_markdown(facts: &NutritionFacts, style: &ReportStyle) -> String {
+    let inline_style = style.clone().min(ReportStyle::Normal);
+    let mut out = String::with_capacity(4096);
+
+    let _ = writeln!(out, "## Cyber Nutrition Facts: `{}`\n", code_escape(&facts.provenance.input));
+
+    // Summary table
+    let _ = writeln!(out, "| Section | Status | Summary |\n| --- | --- | --- |");
+    for section in facts.sections.iter().filter_map(|s| s.filtered(style)) {
+        let status = match &section.status {
+            SectionStatus::Available => "ok".to_string(),
+            SectionStatus::Unavailable(reason) => format!("MISSING/UNKNOWN: {}", reason),
+        };
+        let _ = writeln!(out, "| {} | {} | {} |", escape(&section.title), escape(&status), escape(section.summary.as_deref().unwrap_or("")));
+    }
+    out.push('\n');
+
+    write_label(&mut out, &Label::from_facts(facts));
+
+    if *style >= ReportStyle::Detailed {
+        let p = &facts.provenance;
+        out.push_str("<details><summary>Provenance</summary>\n\n");
+        let _ = writeln!(out, "| | |\n| --- | --- |");
+        let _ = writeln!(out, "| Input | `{}` |", code_escape(&p.input));
+        let _ = writeln!(out, "| Analyzer | {} |", escape(&p.analyzer));
+        if let Some(mime) = &p.mime {
+            let _ = writeln!(out, "| MIME | {} |", escape(mime));
+        }
+        if let Some(size) = p.file_size {
+            let _ = writeln!(out, "| Size | {} bytes |", size);
+        }
+        if let Some(sha256) = &p.sha256 {
+            let _ = writeln!(out, "| SHA-256 | `{}` |", sha256);
+        }
+        let _ = writeln!(out, "| Tool version | {} |", escape(&p.tool_version));
+        out.push_str("\n</details>\n\n");
+    }
+
+    for section in facts.sections.iter() {
+        let Some(full) = section.filtered(style) else {
+            continue;
+        };
+        // A section only shown from Detailed up has nothing inline; all of it goes into <details>
+        let inline = section.filtered(&inline_style)
+            .unwrap_or_else(|| Section { entries: Vec::new(), metrics: Vec::new(), ..full.clone() });
+        write_section(&mut out, &full, &inline);
+    }
+
+    out
+}
+
+/// The label as a two-column table; rules and the title are dropped, bars become percentages.
+fn write_label(out: &mut String, label: &Label) {
+    let _ = writeln!(out, "| Nutrition Facts | |\n| --- | ---: |");
+    let mut footnotes = Vec::new();
+    for row in label.rows.iter() {
+        match row {
+            LabelRow::Title(_) | LabelRow::Rule(_) => {}
+            LabelRow::Pair { name, value, bold } => {
+                if *bold {
+                    let _ = writeln!(out, "| {} | {} |", strong(name), strong(value));
+                } else {
+                    let _ = writeln!(out, "| {} | {} |", escape(name), escape(value));
+                }
+            }
+            LabelRow::Bar { name, note, percent } => {
+                let _ = writeln!(out, "| {} {} | {}% |", strong(name), escape(note), percent);
+            }
+            LabelRow::Footnote(text) => footnotes.push(text.clone()),
+        }
+    }
+    out.push('\n');
+    for text in footnotes {
+        let _ = writeln!(out, "<sub>{}</sub>\n", escape(&text));
+    }
+}
+
+/// `full` is the section at the requested style, `inline` the same section at Normal or below.
+fn write_section(out: &mut String, full: &Section, inline: &Section) {
+    let _ = writeln!(out, "### {}\n", escape(&full.title));
+    if let SectionStatus::Unavailable(reason) = &full.status {
+        let _ = writeln!(out, "**MISSING/UNKNOWN:** {}\n", escape(reason));
+    }
+    if let Some(summary) = &full.summary {
+        let _ = writeln!(out, "{}\n", escape(summary));
+    }
+
+    if let Some(SectionData::SharedLibraries { libraries, functions, .. }) = &full.data
+        && !libraries.is_empty()
+    {
+        let mut sorted: Vec<&String> = libraries.iter().collect();
+        sorted.sort();
+        let _ = writeln!(out, "| Library | Imported functions |\n| --- | ---: |");
+        for lib in sorted {
+            let _ = writeln!(out, "| `{}` | {} |", code_escape(lib), functions.get(lib).map(|f| f.len()).unwrap_or(0));
+        }
+        out.push('\n');
+    }
+
+    write_entries(out, &inline.entries);
+    let inline_count: usize = inline.entries.iter().map(|e| e.count()).sum();
+    let full_count: usize = full.entries.iter().map(|e| e.count()).sum();
+    if full_count > inline_count {
+        let _ = writeln!(out, "<details><summary>All {} entries</summary>\n", full_count);
+        write_entries(out, &full.entries);
+        out.push_str("</details>\n\n");
+    }
+
+    if !inline.metrics.is_empty() {
+        write_metrics(out, &inline.metrics);
+    }
+    if full.metrics.len() > inline.metrics.len() {
+        let _ = writeln!(out, "<details><summary>All {} metrics</summary>\n", full.metrics.len());
+        write_metrics(out, &full.metrics);
+        out.push_str("</details>\n\n");
+    }
+
+    if !full.findings.is_empty() {
+        let _ = writeln!(out, "| Severity | Rule | Finding | Location |\n| --- | --- | --- | --- |");
+        for finding in full.findings.iter() {
+            let location = finding.location.as_ref().map(|l| format!("`{}`", code_escape(&l.describe()))).unwrap_or_default();
+            let _ = writeln!(out, "| {} | {} | **{}**: {} | {} |",
+                finding.severity.as_str(), escape(&finding.rule_id), escape(&finding.title), escape(&finding.message), location);
+        }
+        out.push('\n');
+    }
+}
+
+fn write_entries(out: &mut String, entries: &[Entry]) {
+    if entries.is_empty() {
+        return;
+    }
+    for entry in entries.iter() {
+        write_entry(out, entry, 0);
+    }
+    out.push('\n');
+}
+
+fn write_entry(out: &mut String, entry: &Entry, depth: usize) {
+    let _ = writeln!(out, "{}- {}", "  ".repeat(depth), escape(&entry.text));
+    for child in entry.children.iter() {
+        write_entry(out, child, depth + 1);
+    }
+}
+
+fn write_metrics(out: &mut String, metrics: &[Metric]) {
+    let _ = writeln!(out, "| Metric | Value |\n| --- | ---: |");
+    for metric in metrics.iter() {
+        let _ = writeln!(out, "| {} | {:.2} |", escape(&metric.name), metric.value);
+    }
+    out.push('\n');
+}
+
+/// Escapes characters Markdown would otherwise treat as formatting or raw HTML.
+fn escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' | '`' | '*' | '_' | '[' | ']' | '#' | '|' | '~' => {
+                out.push('\\');
+                out.push(c);
+            }
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '&' => out.push_str("&amp;"),
+            '\r' | '\n' => out.push(' '),
+            _ => out.push(c),
+        }
+    }
+    out
+}
+
+/// `**text**`, or nothing for empty text since `****` renders literally.
+fn strong(text: &str) -> String {
+    if text.is_empty() { String::new() } else { format!("**{}**", escape(text)) }
+}
+
+/// Text inside a `code span`: backticks can't be escaped there, and `|` still splits table cells.
+fn code_escape(text: &str) -> String {
+    text.replace('`', "'").replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn facts_with_detailed_section() -> NutritionFacts {
+        let mut facts = NutritionFacts::new(Provenance::for_url("https://example.com/", b""));
+        let mut section = Section::new("Unresolved Symbols");
+        section.detail = ReportStyle::Detailed;
+        section.summary = Some("2 weak symbols unresolved".into());
+        section.entries.push(Entry::new("__gmon_start__ (weak)", ReportStyle::Detailed));
+        facts.push(section);
+        facts
+    }
+
+    #[test]
+    fn detailed_sections_render_at_detailed_style() {
+        let facts = facts_with_detailed_section();
+        let detailed = render_markdown(&facts, &ReportStyle::Detailed);
+        assert!(detailed.contains("### Unresolved Symbols"), "{}", detailed);
+        assert!(detailed.contains("<details><summary>All 1 entries</summary>"), "{}", detailed);
+        assert!(detailed.contains("\\_\\_gmon\\_start\\_\\_ (weak)"), "{}", detailed);
+
+        let normal = render_markdown(&facts, &ReportStyle::Normal);
+        assert!(!normal.contains("Unresolved Symbols"), "{}", normal);
+    }
+}
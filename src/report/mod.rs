@@ -0,0 +1,532 @@
+
+// The report tree every analyzer in crate::analysis returns.
+// Analyzers record everything they see and tag each node with the lowest ReportStyle
+// it should be visible at; renderers call NutritionFacts::filtered() and never make
+// their own decisions about what is "detailed enough" to show.
+
+mod label;
+mod text;
+mod pdf;
+mod json;
+mod sarif;
+mod html;
+mod markdown;
+mod sbom;
+mod graph;
+
+pub use label::*;
+pub use text::*;
+pub use pdf::*;
+pub use json::*;
+pub use sarif::*;
+pub use html::*;
+pub use markdown::*;
+pub use sbom::*;
+pub use graph::*;
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use crate::args::{OutputFormat, ReportStyle};
+
+/// Renders `facts` in the format and style selected on the command line,
+/// writing to --output-report when given and STDOUT otherwise.
+pub fn write_report(facts: &NutritionFacts, args: &crate::args::Args) -> crate::DynResult<()> {
+    let rendered = match args.output_format() {
+        OutputFormat::Text => {
+            use std::io::IsTerminal;
+            // Escape codes only make sense on a terminal, never in a --output-report file
+            let color = args.output_report.is_none() && std::io::stdout().is_terminal();
+            render_text(facts, &args.style, color)
+        }
+        OutputFormat::Json => render_json(facts, &args.style)?,
+        OutputFormat::Sarif => render_sarif(facts)?,
+        OutputFormat::Html => render_html(facts, &args.style),
+        OutputFormat::Markdown => render_markdown(facts, &args.style),
+        OutputFormat::CycloneDx => render_cyclonedx(facts)?,
+        OutputFormat::Spdx => render_spdx(facts)?,
+        OutputFormat::Dot => render_dot(facts)?,
+        OutputFormat::GraphMl => render_graphml(facts)?,
+        OutputFormat::CallGraphJson => render_call_graph_json(facts)?,
+        OutputFormat::Pdf => {
+            let report_path = args.output_report.as_ref()
+                .ok_or_else(|| crate::tracked_err!("PDF reports must be written to a file, pass --output-report <file.pdf>"))?;
+            return write_pdf(facts, &args.style, report_path);
+        }
+    };
+    match args.output_report {
+        Some(ref report_path) => std::fs::write(report_path, rendered)?,
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+/// Everything we learned about a single analysis input.
+#[derive(Debug, Clone, Serialize)]
+pub struct NutritionFacts {
+    pub provenance: Provenance,
+    pub sections: Vec<Section>,
+}
+
+/// Where the data in a report came from.
+#[derive(Debug, Clone, Serialize)]
+pub struct Provenance {
+    /// The path or URL exactly as it was analyzed
+    pub input: String,
+    /// Which analyzer in crate::analysis produced the report, eg "single_binary"
+    pub analyzer: String,
+    pub mime: Option<String>,
+    pub file_size: Option<u64>,
+    /// Lowercase hex SHA-256 of the input file
+    pub sha256: Option<String>,
+    pub tool_version: String,
+}
+
+/// One top-level block of the report, eg "Shared Libraries" or "Internal Function Call Graph".
+#[derive(Debug, Clone, Serialize)]
+pub struct Section {
+    pub title: String,
+    /// One-line summary; this is all a Terse report shows for the section.
+    pub summary: Option<String>,
+    pub status: SectionStatus,
+    pub detail: ReportStyle,
+    pub entries: Vec<Entry>,
+    pub findings: Vec<Finding>,
+    pub metrics: Vec<Metric>,
+    /// Structured copy of what `entries` describes, for machine-readable renderers.
+    /// Never filtered by ReportStyle.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<SectionData>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "state", content = "reason", rename_all = "snake_case")]
+pub enum SectionStatus {
+    Available,
+    /// The analyzer could not gather this data; the String says why.
+    /// Rendered as MISSING/UNKNOWN at Normal and above, omitted entirely at Terse.
+    Unavailable(String),
+}
+
+/// A line of output with optional nested lines beneath it.
+#[derive(Debug, Clone, Serialize)]
+pub struct Entry {
+    pub text: String,
+    pub detail: ReportStyle,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub children: Vec<Entry>,
+}
+
+/// Typed payloads attached to sections; see `Section::data`.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SectionData {
+    SharedLibraries {
+        /// Libraries in the order the binary declares them (DT_NEEDED or PE import directory)
+        libraries: Vec<String>,
+        /// Library -> imported functions it provides
+        functions: BTreeMap<String, Vec<String>>,
+        symbols_not_found: Vec<String>,
+        /// The same symbols classified, so weak references like __gmon_start__ can be told from real failures
+        unresolved_symbols: Vec<UnresolvedSymbol>,
+        libraries_not_found: Vec<String>,
+        /// Every library we know of, direct or transitive, with its own dependencies; feeds the SBOM renderers
+        components: Vec<LibraryComponent>,
+        /// Library -> symbol versions required from it, eg "libc.so.6" -> ["GLIBC_2.2.5", "GLIBC_2.34"]; empty for PE
+        required_versions: BTreeMap<String, Vec<String>>,
+    },
+    CodeMetrics {
+        /// rust-code-analysis FuncSpace tree, serialized as-is
+        spaces: serde_json::Value,
+    },
+    CallGraph(CallGraph),
+    DangerousImports {
+        /// Catalogue category, eg "command-execution" -> the imports in it
+        categories: BTreeMap<String, Vec<String>>,
+    },
+    DependencyTree {
+        /// Every library in the load set, in load order, then the ones which could not be found
+        libraries: Vec<DependencyNode>,
+    },
+    SymbolInterposition {
+        /// Symbols defined by more than one object, in the order of their first definition
+        symbols: Vec<InterposedSymbol>,
+    },
+    /// One file of a folder report, see analysis::folder
+    FileReport {
+        /// Title of the file's own section -> its data
+        sections: BTreeMap<String, SectionData>,
+    },
+}
+
+/// A shared library as it appears in an SBOM.
+#[derive(Debug, Clone, Serialize)]
+pub struct LibraryComponent {
+    /// Soname or DLL name, unique within one report
+    pub name: String,
+    /// Where the library was found on disk, None if it could not be located
+    pub path: Option<String>,
+    /// Only computed for --format cyclonedx/spdx
+    pub sha256: Option<String>,
+    /// Parsed from the file name, eg "6.0.33" for libstdc++.so.6.0.33
+    pub version: Option<String>,
+    /// Names of the components this one loads
+    pub depends_on: Vec<String>,
+}
+
+/// One shared object in a binary's load set; `needed_by` and `needed` are the tree's edges.
+#[derive(Debug, Clone, Serialize)]
+pub struct DependencyNode {
+    pub soname: String,
+    /// None if the library could not be located
+    pub path: Option<String>,
+    /// Search rule which located it, eg "DT_RUNPATH" or "ld.so.cache"
+    pub found_by: Option<String>,
+    /// Every object with this library in its DT_NEEDED; the binary itself by file name
+    pub needed_by: Vec<String>,
+    pub needed: Vec<String>,
+    /// Hardening summary, eg "partial RELRO, PIE, NX, no canary, no CET"
+    pub hardening: Option<String>,
+    /// Catalogue category -> this library's own dangerous imports
+    pub dangerous_imports: BTreeMap<String, Vec<String>>,
+    /// Risk points of its hardening findings; the highest is the weakest link
+    pub risk_points: u32,
+}
+
+/// An import no library in the load order exports.
+#[derive(Debug, Clone, Serialize)]
+pub struct UnresolvedSymbol {
+    pub name: String,
+    /// "global", "weak" or "unique"
+    pub binding: String,
+    /// "func", "object", "tls", "ifunc", "notype" or "other"
+    pub symbol_type: String,
+    /// Why the symbol is normally absent, for the toolchain hooks which are
+    pub optional: Option<String>,
+    /// A strong reference, ld.so stops with "undefined symbol"; weak ones just resolve to 0
+    pub fatal: bool,
+}
+
+/// A symbol more than one object in the load set defines.
+#[derive(Debug, Clone, Serialize)]
+pub struct InterposedSymbol {
+    pub symbol: String,
+    /// Defining objects in global scope order, the first one wins; the binary itself by file name
+    pub providers: Vec<String>,
+    /// A security-sensitive function whose winning definition is not the system library's
+    pub sensitive: bool,
+}
+
+/// Functions of a binary and the calls between them, as found by disassembling it.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct CallGraph {
+    pub nodes: Vec<CallGraphNode>,
+    /// In the order the call sites were found
+    pub edges: Vec<CallGraphEdge>,
+    /// Indices into `nodes` the walk started from: the entry point, then any exports
+    pub roots: Vec<usize>,
+}
+
+/// A function inside the binary, or an import it calls.
+#[derive(Debug, Clone, Serialize)]
+pub struct CallGraphNode {
+    /// Symbol, export or import name, eg "main" or "printf@libc.so.6"; None for anonymous functions
+    pub name: Option<String>,
+    /// Start address; None for imports, which live in another file
+    pub address: Option<u64>,
+    pub size: Option<u64>,
+    /// Where `size` came from: "st_size", ".eh_frame", ".pdata" or "inferred"; "import" for imports
+    pub extent: String,
+}
+
+/// One call site.
+#[derive(Debug, Clone, Serialize)]
+pub struct CallGraphEdge {
+    pub from: usize,
+    /// None for indirect calls whose target could not be resolved statically
+    pub to: Option<usize>,
+    /// Address of the call (or tail-call jump) instruction
+    pub call_site: u64,
+    pub kind: CallKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CallKind {
+    /// Immediate target, eg `call 0x1160` or `bl printf@plt`
+    Direct,
+    /// Through a register or memory, eg `call [rip+0x2fe2]`
+    Indirect,
+    /// A jump to the start of another function
+    TailCall,
+}
+
+impl CallGraphNode {
+    /// "0x1149" for functions, "printf@libc.so.6" for imports: unique within one graph, used as the id by the exporters.
+    pub fn id(&self) -> String {
+        match (&self.name, self.address) {
+            (_, Some(address)) => format!("0x{:x}", address),
+            (Some(name), None) => name.clone(),
+            (None, None) => "?".into(),
+        }
+    }
+
+    pub fn is_import(&self) -> bool {
+        self.address.is_none()
+    }
+}
+
+/// Something a reader should act on, eg an unresolved import or a missing hardening flag.
+#[derive(Debug, Clone, Serialize)]
+pub struct Finding {
+    /// Stable identifier for this kind of finding, eg "CNF-LINK-001"
+    pub rule_id: String,
+    pub title: String,
+    pub message: String,
+    pub severity: Severity,
+    pub category: Category,
+    pub location: Option<Location>,
+    pub detail: ReportStyle,
+}
+
+// Info and Critical are part of the JSON schema but no check emits them yet
+#[allow(dead_code)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Info,
+    Low,
+    Medium,
+    High,
+    Critical,
+}
+
+/// The label groups findings into these; see the "% daily value" rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Category {
+    MemorySafety,
+    Hardening,
+    Dependencies,
+    Complexity,
+    General,
+}
+
+/// Source findings fill in line/column, binary findings fill in offset/address.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Location {
+    pub file: std::path::PathBuf,
+    pub line: Option<usize>,
+    pub column: Option<usize>,
+    pub offset: Option<u64>,
+    pub address: Option<u64>,
+}
+
+/// A single named number, eg "sloc" or "cyclomatic_sum".
+#[derive(Debug, Clone, Serialize)]
+pub struct Metric {
+    pub name: String,
+    pub value: f64,
+    pub detail: ReportStyle,
+}
+
+impl NutritionFacts {
+    pub fn new(provenance: Provenance) -> Self {
+        NutritionFacts {
+            provenance,
+            sections: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, section: Section) {
+        self.sections.push(section);
+    }
+
+    pub fn findings(&self) -> impl Iterator<Item = &Finding> {
+        self.sections.iter().flat_map(|s| s.findings.iter())
+    }
+
+    /// Returns a copy holding only what should be visible at `style`.
+    /// Every renderer calls this first.
+    pub fn filtered(&self, style: &ReportStyle) -> NutritionFacts {
+        NutritionFacts {
+            provenance: self.provenance.clone(),
+            sections: self.sections.iter().filter_map(|s| s.filtered(style)).collect(),
+        }
+    }
+}
+
+impl Provenance {
+    pub fn for_file(path: &std::path::Path, analyzer: &str) -> Self {
+        Provenance {
+            input: path.display().to_string(),
+            analyzer: analyzer.to_string(),
+            mime: None,
+            file_size: std::fs::metadata(path).ok().map(|m| m.len()),
+            sha256: sha256_of_file(path),
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    /// The hash and size are of the response body, as fetched.
+    pub fn for_url(url: &str, body: &[u8]) -> Self {
+        use sha2::Digest;
+        Provenance {
+            input: url.to_string(),
+            analyzer: "url".to_string(),
+            mime: None,
+            file_size: Some(body.len() as u64),
+            sha256: Some(format!("{:x}", sha2::Sha256::digest(body))),
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+
+    /// Folders have no single hash; `total_bytes` is the size of every file which was analyzed.
+    pub fn for_folder(path: &std::path::Path, total_bytes: u64) -> Self {
+        Provenance {
+            input: path.display().to_string(),
+            analyzer: "folder".to_string(),
+            mime: None,
+            file_size: Some(total_bytes),
+            sha256: None,
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+        }
+    }
+}
+
+pub fn sha256_of_file(path: &std::path::Path) -> Option<String> {
+    use sha2::Digest;
+    let mut file = std::fs::File::open(path).ok()?;
+    let mut hasher = sha2::Sha256::new();
+    std::io::copy(&mut file, &mut hasher).ok()?;
+    Some(format!("{:x}", hasher.finalize()))
+}
+
+impl Section {
+    pub fn new(title: impl Into<String>) -> Self {
+        Section {
+            title: title.into(),
+            summary: None,
+            status: SectionStatus::Available,
+            detail: ReportStyle::Terse,
+            entries: Vec::new(),
+            findings: Vec::new(),
+            metrics: Vec::new(),
+            data: None,
+        }
+    }
+
+    pub fn unavailable(title: impl Into<String>, reason: impl Into<String>) -> Self {
+        let mut section = Section::new(title);
+        section.status = SectionStatus::Unavailable(reason.into());
+        section
+    }
+
+    pub fn is_available(&self) -> bool {
+        self.status == SectionStatus::Available
+    }
+
+    fn filtered(&self, style: &ReportStyle) -> Option<Section> {
+        if self.detail > *style {
+            return None;
+        }
+        if !self.is_available() && *style == ReportStyle::Terse {
+            return None;
+        }
+        Some(Section {
+            title: self.title.clone(),
+            summary: self.summary.clone(),
+            status: self.status.clone(),
+            detail: self.detail.clone(),
+            entries: filter_entries(&self.entries, style),
+            findings: self.findings.iter().filter(|f| f.detail <= *style).cloned().collect(),
+            metrics: self.metrics.iter().filter(|m| m.detail <= *style).cloned().collect(),
+            data: self.data.clone(),
+        })
+    }
+}
+
+fn filter_entries(entries: &[Entry], style: &ReportStyle) -> Vec<Entry> {
+    entries.iter()
+        .filter(|e| e.detail <= *style)
+        .map(|e| Entry {
+            text: e.text.clone(),
+            detail: e.detail.clone(),
+            children: filter_entries(&e.children, style),
+        })
+        .collect()
+}
+
+impl Entry {
+    pub fn new(text: impl Into<String>, detail: ReportStyle) -> Self {
+        Entry {
+            text: text.into(),
+            detail,
+            children: Vec::new(),
+        }
+    }
+
+    pub fn push(&mut self, child: Entry) {
+        self.children.push(child);
+    }
+
+    /// Number of entries in this subtree, including self.
+    pub fn count(&self) -> usize {
+        1 + self.children.iter().map(|c| c.count()).sum::<usize>()
+    }
+}
+
+impl Severity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Severity::Info => "INFO",
+            Severity::Low => "LOW",
+            Severity::Medium => "MEDIUM",
+            Severity::High => "HIGH",
+            Severity::Critical => "CRITICAL",
+        }
+    }
+}
+
+impl Category {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Category::MemorySafety => "Memory Safety",
+            Category::Hardening => "Hardening",
+            Category::Dependencies => "Dependencies",
+            Category::Complexity => "Complexity",
+            Category::General => "General",
+        }
+    }
+}
+
+impl Location {
+    /// "file:line:column" for source locations, "file+0xoffset (VA 0xaddress)" for binary ones.
+    pub fn describe(&self) -> String {
+        let mut out = self.file.display().to_string();
+        if let Some(line) = self.line {
+            out.push_str(&format!(":{}", line));
+            if let Some(column) = self.column {
+                out.push_str(&format!(":{}", column));
+            }
+        }
+        if let Some(offset) = self.offset {
+            out.push_str(&format!("+0x{:x}", offset));
+        }
+        if let Some(address) = self.address {
+            out.push_str(&format!(" (VA 0x{:x})", address));
+        }
+        out
+    }
+}
+
+impl Metric {
+    pub fn new(name: impl Into<String>, value: f64, detail: ReportStyle) -> Self {
+        Metric {
+            name: name.into(),
+            value,
+            detail,
+        }
+    }
+}
@@ -0,0 +1,287 @@
+
+use std::fs::File;
+use std::io::BufWriter;
+
+use printpdf::*;
+use printpdf::indices::{PdfLayerIndex, PdfPageIndex};
+
+use super::*;
+
+const PAGE_W: f64 = 210.0; // A4, mm
+const PAGE_H: f64 = 297.0;
+const MARGIN: f64 = 15.0;
+const HEADER_H: f64 = 12.0;
+const FOOTER_H: f64 = 12.0;
+const INDENT_MM: f64 = 4.0;
+const PT_TO_MM: f64 = 0.3528;
+
+/// Render `facts` to a multi-page A4 PDF at `path`.
+/// Page one holds the label, following pages hold every section visible at `style`.
+pub fn write_pdf(facts: &NutritionFacts, style: &ReportStyle, path: &std::path::Path) -> crate::DynResult<()> {
+    // Scores come from the unfiltered report so the label doesn't change with --style
+    let label = Label::from_facts(facts);
+    let facts = facts.filtered(style);
+    let mut w = PdfWriter::new(&facts)?;
+
+    write_label_page(&mut w, &label);
+
+    w.new_page();
+    for section in facts.sections.iter() {
+        write_section(&mut w, section);
+    }
+
+    w.finish(&facts, path)
+}
+
+/// Draws the rows of `label` as a boxed label using the full content width.
+fn write_label_page(w: &mut PdfWriter, label: &Label) {
+    let top = w.y;
+    for row in label.rows.iter() {
+        match row {
+            LabelRow::Title(title) => w.text(title, Face::Bold, 26.0, 0),
+            LabelRow::Rule(weight) => w.rule(match weight {
+                RuleWeight::Thin => 0.3,
+                RuleWeight::Medium => 1.2,
+                RuleWeight::Thick => 4.0,
+            }),
+            LabelRow::Pair { name, value, bold } => {
+                let (face, size) = if *bold { (Face::Bold, 13.0) } else { (Face::Regular, 10.0) };
+                w.pair(name, value, face, size);
+            }
+            LabelRow::Bar { name, note, percent } => {
+                w.pair(&format!("{}  {}", name, note), &format!("{}%", percent), Face::Bold, 11.0);
+                w.bar(*percent);
+            }
+            LabelRow::Footnote(text) => w.text(text, Face::Regular, 8.0, 0),
+        }
+    }
+    let bottom = w.y - 1.0;
+    w.frame(top + 2.0, bottom);
+}
+
+fn write_section(w: &mut PdfWriter, section: &Section) {
+    w.gap(3.0);
+    w.text(&section.title, Face::Bold, 14.0, 0);
+    w.rule(0.5);
+    if let SectionStatus::Unavailable(reason) = &section.status {
+        w.text(&format!("MISSING/UNKNOWN: {}", reason), Face::Regular, 10.0, 0);
+    }
+    if let Some(summary) = &section.summary {
+        w.text(summary, Face::Regular, 10.0, 0);
+    }
+    for entry in section.entries.iter() {
+        write_entry(w, entry, 0);
+    }
+    if !section.metrics.is_empty() {
+        w.gap(2.0);
+        w.text("Metrics", Face::Bold, 11.0, 0);
+        for metric in section.metrics.iter() {
+            w.text(&format!("{:<24} {:.2}", metric.name, metric.value), Face::Mono, 9.0, 1);
+        }
+    }
+    if !section.findings.is_empty() {
+        w.gap(2.0);
+        w.text("Findings", Face::Bold, 11.0, 0);
+        for finding in section.findings.iter() {
+            w.text(&format!("[{}] {}: {}", finding.severity.as_str(), finding.title, finding.message), Face::Regular, 10.0, 1);
+            if let Some(location) = &finding.location {
+                w.text(&format!("at {}", location.describe()), Face::Mono, 8.0, 2);
+            }
+        }
+    }
+}
+
+fn write_entry(w: &mut PdfWriter, entry: &Entry, depth: usize) {
+    w.text(&entry.text, Face::Mono, 9.0, depth);
+    for child in entry.children.iter() {
+        write_entry(w, child, depth + 1);
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Face {
+    Regular,
+    Bold,
+    Mono,
+}
+
+/// Cursor over a growing PdfDocument which starts a new page whenever the current one fills up.
+struct PdfWriter {
+    doc: PdfDocumentReference,
+    pages: Vec<(PdfPageIndex, PdfLayerIndex)>,
+    layer: PdfLayerReference,
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    mono: IndirectFontRef,
+    /// Baseline of the next line, in mm from the bottom of the page
+    y: f64,
+}
+
+impl PdfWriter {
+    fn new(facts: &NutritionFacts) -> crate::DynResult<PdfWriter> {
+        let title = format!("Cyber Nutrition Facts: {}", facts.provenance.input);
+        let (doc, page, layer) = PdfDocument::new(title, Mm(PAGE_W), Mm(PAGE_H), "Layer 1");
+        let regular = doc.add_builtin_font(BuiltinFont::Helvetica)?;
+        let bold = doc.add_builtin_font(BuiltinFont::HelveticaBold)?;
+        let mono = doc.add_builtin_font(BuiltinFont::Courier)?;
+        let layer_ref = doc.get_page(page).get_layer(layer);
+        Ok(PdfWriter {
+            doc,
+            pages: vec![(page, layer)],
+            layer: layer_ref,
+            regular,
+            bold,
+            mono,
+            y: PAGE_H - MARGIN - HEADER_H,
+        })
+    }
+
+    fn new_page(&mut self) {
+        let (page, layer) = self.doc.add_page(Mm(PAGE_W), Mm(PAGE_H), "Layer 1");
+        self.layer = self.doc.get_page(page).get_layer(layer);
+        self.pages.push((page, layer));
+        self.y = PAGE_H - MARGIN - HEADER_H;
+    }
+
+    fn ensure_space(&mut self, height: f64) {
+        if self.y - height < MARGIN + FOOTER_H {
+            self.new_page();
+        }
+    }
+
+    fn font(&self, face: Face) -> &IndirectFontRef {
+        match face {
+            Face::Regular => &self.regular,
+            Face::Bold => &self.bold,
+            Face::Mono => &self.mono,
+        }
+    }
+
+    /// Writes `text`, wrapping it to the page width; continuation lines are indented one extra level.
+    fn text(&mut self, text: &str, face: Face, size: f64, depth: usize) {
+        let line_h = size * PT_TO_MM * 1.3;
+        let x = MARGIN + INDENT_MM * depth as f64;
+        // Average glyph width; Courier is exactly 0.6em, Helvetica averages a little over 0.5em
+        let glyph_w = size * PT_TO_MM * match face { Face::Mono => 0.6, _ => 0.55 };
+        let max_chars = (((PAGE_W - MARGIN - x - INDENT_MM) / glyph_w) as usize).max(8);
+        let text = pdf_safe(text);
+        for (i, chunk) in wrap(&text, max_chars).into_iter().enumerate() {
+            self.ensure_space(line_h);
+            self.y -= line_h;
+            let x = if i == 0 { x } else { x + INDENT_MM };
+            self.layer.use_text(chunk, size, Mm(x), Mm(self.y), self.font(face));
+        }
+    }
+
+    /// One line with `name` on the left and `value` right-aligned; `value` is not wrapped.
+    fn pair(&mut self, name: &str, value: &str, face: Face, size: f64) {
+        let line_h = size * PT_TO_MM * 1.3;
+        let glyph_w = size * PT_TO_MM * 0.55;
+        self.ensure_space(line_h);
+        self.y -= line_h;
+        let value = pdf_safe(value);
+        let max_value_chars = ((PAGE_W - 2.0 * MARGIN) / glyph_w) as usize / 2;
+        let value: String = if value.chars().count() > max_value_chars {
+            let skip = value.chars().count() - max_value_chars + 3;
+            format!("...{}", value.chars().skip(skip).collect::<String>())
+        } else {
+            value
+        };
+        let value_x = PAGE_W - MARGIN - glyph_w * value.chars().count() as f64;
+        self.layer.use_text(pdf_safe(name), size, Mm(MARGIN), Mm(self.y), self.font(face));
+        self.layer.use_text(value, size, Mm(value_x), Mm(self.y), self.font(face));
+    }
+
+    /// A filled bar, full content width at 100%, under a "% Daily Value" line.
+    fn bar(&mut self, percent: u32) {
+        const BAR_H: f64 = 2.5;
+        self.ensure_space(BAR_H + 1.0);
+        self.y -= BAR_H + 1.0;
+        let width = (PAGE_W - 2.0 * MARGIN) * (percent.min(100) as f64) / 100.0;
+        let (x0, x1, y0, y1) = (Mm(MARGIN), Mm(MARGIN + width), Mm(self.y), Mm(self.y + BAR_H));
+        self.layer.set_fill_color(Color::Greyscale(Greyscale::new(0.25, None)));
+        if width > 0.0 {
+            self.layer.add_shape(Line {
+                points: vec![
+                    (Point::new(x0, y0), false),
+                    (Point::new(x1, y0), false),
+                    (Point::new(x1, y1), false),
+                    (Point::new(x0, y1), false),
+                ],
+                is_closed: true,
+                has_fill: true,
+                has_stroke: false,
+                is_clipping_path: false,
+            });
+        }
+        self.layer.set_fill_color(Color::Greyscale(Greyscale::new(0.0, None)));
+    }
+
+    fn gap(&mut self, height: f64) {
+        self.y -= height;
+    }
+
+    /// Horizontal rule across the content width, `thickness` in points.
+    fn rule(&mut self, thickness: f64) {
+        self.ensure_space(2.0);
+        self.y -= 1.5;
+        self.layer.set_outline_thickness(thickness);
+        self.layer.add_shape(Line {
+            points: vec![
+                (Point::new(Mm(MARGIN), Mm(self.y)), false),
+                (Point::new(Mm(PAGE_W - MARGIN), Mm(self.y)), false),
+            ],
+            is_closed: false,
+            has_fill: false,
+            has_stroke: true,
+            is_clipping_path: false,
+        });
+        self.y -= 1.0;
+    }
+
+    /// Box around the content width between two y positions on the current page.
+    fn frame(&mut self, top: f64, bottom: f64) {
+        let (left, right) = (Mm(MARGIN - 2.0), Mm(PAGE_W - MARGIN + 2.0));
+        self.layer.set_outline_thickness(1.0);
+        self.layer.add_shape(Line {
+            points: vec![
+                (Point::new(left, Mm(top)), false),
+                (Point::new(right, Mm(top)), false),
+                (Point::new(right, Mm(bottom)), false),
+                (Point::new(left, Mm(bottom)), false),
+            ],
+            is_closed: true,
+            has_fill: false,
+            has_stroke: true,
+            is_clipping_path: false,
+        });
+    }
+
+    /// Stamps headers and "Page N of M" footers now that the page count is known, then saves.
+    fn finish(self, facts: &NutritionFacts, path: &std::path::Path) -> crate::DynResult<()> {
+        let total = self.pages.len();
+        let header = pdf_safe(&format!("Cyber Nutrition Facts - {}", facts.provenance.input));
+        let hash = format!("SHA-256 {}", facts.provenance.sha256.as_deref().unwrap_or("unknown"));
+        for (i, (page, layer)) in self.pages.iter().enumerate() {
+            let layer = self.doc.get_page(*page).get_layer(*layer);
+            layer.use_text(header.as_str(), 8.0, Mm(MARGIN), Mm(PAGE_H - MARGIN), &self.regular);
+            layer.use_text(hash.as_str(), 7.0, Mm(MARGIN), Mm(MARGIN), &self.mono);
+            layer.use_text(format!("Page {} of {}", i + 1, total), 8.0, Mm(PAGE_W - MARGIN - 22.0), Mm(MARGIN), &self.regular);
+        }
+        self.doc.save(&mut BufWriter::new(File::create(path)?))?;
+        Ok(())
+    }
+}
+
+/// The builtin PDF fonts only cover WinAnsi; replace anything else so it doesn't render as garbage.
+fn pdf_safe(text: &str) -> String {
+    text.chars().map(|c| if c.is_ascii() && !c.is_ascii_control() { c } else { '?' }).collect()
+}
+
+fn wrap(text: &str, max_chars: usize) -> Vec<String> {
+    let chars: Vec<char> = text.chars().collect();
+    if chars.is_empty() {
+        return vec![String::new()];
+    }
+    chars.chunks(max_chars).map(|c| c.iter().collect()).collect()
+}
@@ -0,0 +1,204 @@
+
+use std::collections::BTreeMap;
+
+use serde_json::{json, Value};
+
+use super::*;
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Render every finding in `facts` as a SARIF 2.1.0 log with one run.
+/// Code-scanning tools filter by level themselves, so `--style` does not remove findings here.
+pub fn render_sarif(facts: &NutritionFacts) -> crate::DynResult<String> {
+    let findings: Vec<&Finding> = facts.findings().collect();
+
+    // One rule per distinct rule_id, described by the first finding which uses it
+    let mut rules: BTreeMap<&str, &Finding> = BTreeMap::new();
+    for finding in findings.iter() {
+        rules.entry(finding.rule_id.as_str()).or_insert(finding);
+    }
+    let rule_index: BTreeMap<&str, usize> = rules.keys().enumerate().map(|(i, id)| (*id, i)).collect();
+
+    let rules_json: Vec<Value> = rules.values().map(|f| json!({
+        "id": f.rule_id,
+        "name": rule_name(&f.title),
+        "shortDescription": { "text": f.title },
+        "defaultConfiguration": { "level": sarif_level(f.severity) },
+        "properties": {
+            "security-severity": security_severity(f.severity),
+            "tags": ["security", f.category],
+        },
+    })).collect();
+
+    let results: Vec<Value> = findings.iter().map(|f| json!({
+        "ruleId": f.rule_id,
+        "ruleIndex": rule_index[f.rule_id.as_str()],
+        "level": sarif_level(f.severity),
+        "message": { "text": f.message },
+        "locations": [sarif_location(f.location.as_ref(), &facts.provenance)],
+        "properties": {
+            "severity": f.severity,
+            "category": f.category,
+        },
+    })).collect();
+
+    let log = json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "cyber-nutrition-facts",
+                    "version": facts.provenance.tool_version,
+                    "rules": rules_json,
+                }
+            },
+            "artifacts": [{
+                "location": { "uri": artifact_uri(&facts.provenance.input) },
+                "hashes": facts.provenance.sha256.as_ref().map(|h| json!({ "sha-256": h })).unwrap_or(json!({})),
+            }],
+            "results": results,
+        }]
+    });
+
+    let mut out = serde_json::to_string_pretty(&log)?;
+    out.push('\n');
+    Ok(out)
+}
+
+/// Source findings get a line/column region, binary findings a byte offset and virtual address.
+/// Findings without a location point at the analyzed input as a whole.
+fn sarif_location(location: Option<&Location>, provenance: &Provenance) -> Value {
+    let Some(location) = location else {
+        return json!({ "physicalLocation": { "artifactLocation": { "uri": artifact_uri(&provenance.input) } } });
+    };
+    let mut physical = json!({
+        "artifactLocation": { "uri": artifact_uri(&location.file.to_string_lossy()) },
+    });
+    let mut region = serde_json::Map::new();
+    if let Some(line) = location.line {
+        region.insert("startLine".into(), json!(line));
+    }
+    if let Some(column) = location.column {
+        region.insert("startColumn".into(), json!(column));
+    }
+    if let Some(offset) = location.offset {
+        region.insert("byteOffset".into(), json!(offset));
+    }
+    if !region.is_empty() {
+        physical["region"] = Value::Object(region);
+    }
+    if let Some(address) = location.address {
+        physical["address"] = json!({ "absoluteAddress": address });
+    }
+    json!({ "physicalLocation": physical })
+}
+
+fn sarif_level(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical | Severity::High => "error",
+        Severity::Medium => "warning",
+        Severity::Low => "note",
+        Severity::Info => "none",
+    }
+}
+
+/// CVSS-like score GitHub code scanning uses to bucket security results.
+fn security_severity(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Critical => "9.5",
+        Severity::High => "8.0",
+        Severity::Medium => "5.5",
+        Severity::Low => "3.0",
+        Severity::Info => "0.0",
+    }
+}
+
+/// "Unbounded %s of attacker-controlled input" -> "UnboundedSOfAttackerControlledInput"
+fn rule_name(title: &str) -> String {
+    title.split(|c: char| !c.is_ascii_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| {
+            let mut chars = w.chars();
+            chars.next().map(|c| c.to_ascii_uppercase().to_string() + chars.as_str()).unwrap_or_default()
+        })
+        .collect()
+}
+
+/// SARIF wants URIs; relative paths stay relative, separators become forward slashes.
+fn artifact_uri(path: &str) -> String {
+    let path = path.replace('\\', "/");
+    if path.starts_with('/') {
+        format!("file://{}", path)
+    }
+    else if path.as_bytes().get(1) == Some(&b':') {
+        format!("file:///{}", path)
+    }
+    else {
+        path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn facts_with(location: Option<Location>) -> NutritionFacts {
+        let mut facts = NutritionFacts::new(Provenance::for_url("https://example.com/a.c", b""));
+        let mut section = Section::new("Source Findings");
+        section.findings.push(Finding {
+            rule_id: "CNF-SRC-001".into(),
+            title: "Unbounded %s of attacker-controlled input".into(),
+            message: "printf() prints `argv[1]` with an unbounded %s".into(),
+            severity: Severity::High,
+            category: Category::MemorySafety,
+            location,
+            detail: ReportStyle::Terse,
+        });
+        facts.push(section);
+        facts
+    }
+
+    fn first_location(facts: &NutritionFacts) -> Value {
+        let log: Value = serde_json::from_str(&render_sarif(facts).unwrap()).unwrap();
+        log["runs"][0]["results"][0]["locations"][0]["physicalLocation"].clone()
+    }
+
+    #[test]
+    fn source_findings_get_a_line_and_column_region() {
+        let facts = facts_with(Some(Location {
+            file: "/src/samples/c_unsafe_a.c".into(),
+            line: Some(10),
+            column: Some(5),
+            ..Default::default()
+        }));
+        assert_eq!(first_location(&facts), json!({
+            "artifactLocation": { "uri": "file:///src/samples/c_unsafe_a.c" },
+            "region": { "startLine": 10, "startColumn": 5 },
+        }));
+    }
+
+    #[test]
+    fn binary_findings_get_a_byte_offset_and_address() {
+        let facts = facts_with(Some(Location {
+            file: "bin/app".into(),
+            offset: Some(0x2dd0),
+            address: Some(0x3dd0),
+            ..Default::default()
+        }));
+        assert_eq!(first_location(&facts), json!({
+            "artifactLocation": { "uri": "bin/app" },
+            "region": { "byteOffset": 0x2dd0 },
+            "address": { "absoluteAddress": 0x3dd0 },
+        }));
+    }
+
+    #[test]
+    fn findings_without_a_location_point_at_the_input() {
+        let facts = facts_with(None);
+        assert_eq!(first_location(&facts), json!({
+            "artifactLocation": { "uri": "https://example.com/a.c" },
+        }));
+        assert_eq!(artifact_uri("C:\\src\\a.c"), "file:///C:/src/a.c");
+    }
+}
@@ -0,0 +1,210 @@
+
+use serde_json::{json, Value};
+
+use super::*;
+
+const CYCLONEDX_SCHEMA: &str = "http://cyclonedx.org/schema/bom-1.5.schema.json";
+
+/// Render the shared libraries of a binary as a CycloneDX 1.5 JSON BOM.
+/// The analyzed binary is `metadata.component`; every library is a component with its own dependency edges.
+pub fn render_cyclonedx(facts: &NutritionFacts) -> crate::DynResult<String> {
+    let (direct, components) = shared_libraries(facts)?;
+    let p = &facts.provenance;
+    let target_ref = target_name(p);
+
+    let mut target = json!({
+        "type": "application",
+        "bom-ref": target_ref,
+        "name": target_ref,
+        "properties": [{ "name": "cnf:path", "value": p.input }],
+    });
+    if let Some(sha256) = &p.sha256 {
+        target["hashes"] = json!([{ "alg": "SHA-256", "content": sha256 }]);
+    }
+
+    let components_json: Vec<Value> = components.iter().map(|c| {
+        let mut component = json!({
+            "type": "library",
+            "bom-ref": c.name,
+            "name": c.name,
+        });
+        if let Some(version) = &c.version {
+            component["version"] = json!(version);
+        }
+        if let Some(sha256) = &c.sha256 {
+            component["hashes"] = json!([{ "alg": "SHA-256", "content": sha256 }]);
+        }
+        let mut properties = vec![json!({ "name": "cnf:soname", "value": c.name })];
+        match &c.path {
+            Some(path) => properties.push(json!({ "name": "cnf:path", "value": path })),
+            None => properties.push(json!({ "name": "cnf:resolved", "value": "false" })),
+        }
+        component["properties"] = json!(properties);
+        component
+    }).collect();
+
+    let mut dependencies = vec![json!({ "ref": target_ref, "dependsOn": direct })];
+    for c in components.iter() {
+        dependencies.push(json!({ "ref": c.name, "dependsOn": known_dependencies(c, components) }));
+    }
+
+    let bom = json!({
+        "$schema": CYCLONEDX_SCHEMA,
+        "bomFormat": "CycloneDX",
+        "specVersion": "1.5",
+        "serialNumber": format!("urn:uuid:{}", document_uuid(p)),
+        "version": 1,
+        "metadata": {
+            "timestamp": utc_timestamp(),
+            "tools": {
+                "components": [{ "type": "application", "name": "cyber-nutrition-facts", "version": p.tool_version }]
+            },
+            "component": target,
+        },
+        "components": components_json,
+        "dependencies": dependencies,
+    });
+
+    let mut out = serde_json::to_string_pretty(&bom)?;
+    out.push('\n');
+    Ok(out)
+}
+
+/// Render the shared libraries of a binary as an SPDX 2.3 JSON document.
+/// Every library is a package; DEPENDS_ON relationships mirror DT_NEEDED / the import directory.
+pub fn render_spdx(facts: &NutritionFacts) -> crate::DynResult<String> {
+    let (direct, components) = shared_libraries(facts)?;
+    let p = &facts.provenance;
+    let name = target_name(p);
+    const TARGET_ID: &str = "SPDXRef-Target";
+
+    let mut target = spdx_package(TARGET_ID, &name, None, p.sha256.as_deref(), Some(&p.input), "APPLICATION");
+    if let Some(mime) = &p.mime {
+        target["comment"] = json!(format!("MIME type {}", mime));
+    }
+    let mut packages = vec![target];
+    for c in components.iter() {
+        packages.push(spdx_package(&spdx_id(&c.name), &c.name, c.version.as_deref(), c.sha256.as_deref(), c.path.as_deref(), "LIBRARY"));
+    }
+
+    let mut relationships = vec![json!({
+        "spdxElementId": "SPDXRef-DOCUMENT",
+        "relationshipType": "DESCRIBES",
+        "relatedSpdxElement": TARGET_ID,
+    })];
+    for dep in direct.iter() {
+        relationships.push(depends_on(TARGET_ID, &spdx_id(dep)));
+    }
+    for c in components.iter() {
+        for dep in known_dependencies(c, components) {
+            relationships.push(depends_on(&spdx_id(&c.name), &spdx_id(dep)));
+        }
+    }
+
+    let doc = json!({
+        "spdxVersion": "SPDX-2.3",
+        "dataLicense": "CC0-1.0",
+        "SPDXID": "SPDXRef-DOCUMENT",
+        "name": name,
+        "documentNamespace": format!("https://spdx.org/spdxdocs/cyber-nutrition-facts/{}-{}", spdx_id_chars(&name), document_uuid(p)),
+        "creationInfo": {
+            "created": utc_timestamp(),
+            "creators": [format!("Tool: cyber-nutrition-facts-{}", p.tool_version)],
+        },
+        "documentDescribes": [TARGET_ID],
+        "packages": packages,
+        "relationships": relationships,
+    });
+
+    let mut out = serde_json::to_string_pretty(&doc)?;
+    out.push('\n');
+    Ok(out)
+}
+
+/// The direct dependencies and all library components of the report's shared library section.
+fn shared_libraries(facts: &NutritionFacts) -> crate::DynResult<(&[String], &[LibraryComponent])> {
+    let found = facts.sections.iter().find_map(|s| match &s.data {
+        Some(SectionData::SharedLibraries { libraries, components, .. }) => Some((libraries.as_slice(), components.as_slice())),
+        _ => None,
+    });
+    match found {
+        Some(found) => Ok(found),
+        None => Err(Box::new(crate::tracked_err!(format!("SBOM output needs a binary with shared library information, {} has none", facts.provenance.input)))),
+    }
+}
+
+/// Edges to libraries which are not components (eg a dependency of a DLL we found but whose
+/// own dependency was never looked up) would dangle, so they are dropped.
+fn known_dependencies<'a>(component: &'a LibraryComponent, components: &[LibraryComponent]) -> Vec<&'a String> {
+    component.depends_on.iter().filter(|d| components.iter().any(|c| &c.name == *d)).collect()
+}
+
+fn spdx_package(id: &str, name: &str, version: Option<&str>, sha256: Option<&str>, path: Option<&str>, purpose: &str) -> Value {
+    let mut package = json!({
+        "SPDXID": id,
+        "name": name,
+        "downloadLocation": "NOASSERTION",
+        "filesAnalyzed": false,
+        "licenseConcluded": "NOASSERTION",
+        "licenseDeclared": "NOASSERTION",
+        "copyrightText": "NOASSERTION",
+        "primaryPackagePurpose": purpose,
+    });
+    if let Some(version) = version {
+        package["versionInfo"] = json!(version);
+    }
+    if let Some(sha256) = sha256 {
+        package["checksums"] = json!([{ "algorithm": "SHA256", "checksumValue": sha256 }]);
+    }
+    match path {
+        Some(path) => package["packageFileName"] = json!(path),
+        None => package["comment"] = json!("Not found in the library search paths"),
+    }
+    package
+}
+
+fn depends_on(from: &str, to: &str) -> Value {
+    json!({ "spdxElementId": from, "relationshipType": "DEPENDS_ON", "relatedSpdxElement": to })
+}
+
+fn spdx_id(name: &str) -> String {
+    format!("SPDXRef-Library-{}", spdx_id_chars(name))
+}
+
+/// SPDX identifiers may only contain letters, digits, '.' and '-'.
+fn spdx_id_chars(name: &str) -> String {
+    name.chars().map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '-' }).collect()
+}
+
+fn target_name(p: &Provenance) -> String {
+    std::path::Path::new(&p.input).file_name().map(|f| f.to_string_lossy().to_string()).unwrap_or_else(|| p.input.clone())
+}
+
+/// Derived from the input's hash so re-running on the same binary gives the same document identity.
+fn document_uuid(p: &Provenance) -> String {
+    use sha2::Digest;
+    let seed = format!("{}\n{}\n{}", p.input, p.sha256.as_deref().unwrap_or(""), p.tool_version);
+    let mut bytes: [u8; 16] = sha2::Sha256::digest(seed.as_bytes())[..16].try_into().unwrap_or_default();
+    // RFC 4122 version 5 (name-based) and variant bits
+    bytes[6] = (bytes[6] & 0x0f) | 0x50;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+    format!("{}-{}-{}-{}-{}", &hex[0..8], &hex[8..12], &hex[12..16], &hex[16..20], &hex[20..32])
+}
+
+/// Current time as "YYYY-MM-DDTHH:MM:SSZ", which both formats require.
+fn utc_timestamp() -> String {
+    let secs = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (days, rem) = (secs / 86400, secs % 86400);
+    // Howard Hinnant's civil_from_days
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, rem / 3600, rem % 3600 / 60, rem % 60)
+}
@@ -0,0 +1,59 @@
+
+use std::fmt::Write;
+
+use super::*;
+
+/// Render `facts` as the plain-text report; `color` adds ANSI escapes to the label.
+pub fn render_text(facts: &NutritionFacts, style: &ReportStyle, color: bool) -> String {
+    // Scores come from the unfiltered report so the label doesn't change with --style
+    let label = Label::from_facts(facts);
+    let facts = facts.filtered(style);
+    let mut out = String::with_capacity(4096);
+
+    out.push_str(&render_label_text(&label, color));
+
+    if *style >= ReportStyle::Detailed {
+        let p = &facts.provenance;
+        let _ = writeln!(out, "Input: {} (analyzer {}, cyber-nutrition-facts {})", p.input, p.analyzer, p.tool_version);
+        if let Some(mime) = &p.mime {
+            let _ = writeln!(out, "MIME: {}", mime);
+        }
+        if let Some(size) = p.file_size {
+            let _ = writeln!(out, "Size: {} bytes", size);
+        }
+        if let Some(sha256) = &p.sha256 {
+            let _ = writeln!(out, "SHA-256: {}", sha256);
+        }
+    }
+
+    for section in facts.sections.iter() {
+        let _ = writeln!(out, "= = = = {} = = = =", section.title);
+        if let SectionStatus::Unavailable(reason) = &section.status {
+            let _ = writeln!(out, "MISSING/UNKNOWN: {}", reason);
+        }
+        if let Some(summary) = &section.summary {
+            let _ = writeln!(out, "{}", summary);
+        }
+        for entry in section.entries.iter() {
+            write_entry(&mut out, entry, 0);
+        }
+        for metric in section.metrics.iter() {
+            let _ = writeln!(out, " {:<24} {:.2}", metric.name, metric.value);
+        }
+        for finding in section.findings.iter() {
+            let _ = writeln!(out, " [{}] {}: {}", finding.severity.as_str(), finding.title, finding.message);
+            if let Some(location) = &finding.location {
+                let _ = writeln!(out, "   at {}", location.describe());
+            }
+        }
+    }
+
+    out
+}
+
+fn write_entry(out: &mut String, entry: &Entry, depth: usize) {
+    let _ = writeln!(out, "{} - {}", "  ".repeat(depth), entry.text);
+    for child in entry.children.iter() {
+        write_entry(out, child, depth + 1);
+    }
+}